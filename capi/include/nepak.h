@@ -0,0 +1,134 @@
+#ifndef NEPAK_H
+#define NEPAK_H
+
+/* Generated by cbindgen from capi/src/lib.rs. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Result of every fallible call.
+typedef enum NepakStatus {
+  NEPAK_STATUS_OK = 0,
+  // A required pointer argument was NULL.
+  NEPAK_STATUS_NULL_ARGUMENT = 1,
+  // A string argument was not valid UTF-8.
+  NEPAK_STATUS_INVALID_UTF8 = 2,
+  // The file could not be opened or read.
+  NEPAK_STATUS_IO = 3,
+  // The file is not a valid pak, or an entry failed its hash check.
+  NEPAK_STATUS_INVALID_PAK = 4,
+  // No entry with that path.
+  NEPAK_STATUS_NOT_FOUND = 5,
+  // Entry index is not below `nepak_entry_count`.
+  NEPAK_STATUS_INDEX_OUT_OF_RANGE = 6,
+  // The output buffer is smaller than the entry; the needed size was stored.
+  NEPAK_STATUS_BUFFER_TOO_SMALL = 7,
+  // The entry's codec is not available: zstd in a library built without
+  // zstd, or a custom codec.
+  NEPAK_STATUS_UNSUPPORTED = 8,
+  // Internal error (a Rust panic was caught at the boundary).
+  NEPAK_STATUS_INTERNAL = 9,
+} NepakStatus;
+
+// An open pak. Create with [`nepak_open`], free with [`nepak_close`].
+typedef struct NepakPak NepakPak;
+
+// Upper bounds checked while opening and reading a pak; see
+// [`nepak_default_limits`] and [`nepak_open_with_limits`].
+typedef struct NepakLimits {
+  // Largest index block, stored or decompressed, in bytes.
+  uint64_t max_index_len;
+  // Most index entries.
+  uint32_t max_entries;
+  // Largest decompressed entry, in bytes.
+  uint64_t max_raw_len;
+} NepakLimits;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// The limits [`nepak_open`] uses. They accept any pak a normal build
+// produces; lower them for paks from untrusted sources.
+struct NepakLimits nepak_default_limits(void);
+
+// Open the pak at `path` and check its index. On success `*out` receives a
+// handle to pass to [`nepak_close`]; on failure it is set to NULL.
+//
+// # Safety
+// `path` must be a NUL-terminated UTF-8 string and `out` a valid pointer.
+enum NepakStatus nepak_open(const char *path, struct NepakPak **out);
+
+// [`nepak_open`] with custom limits. Paks over a limit fail with
+// [`NepakStatus::InvalidPak`].
+//
+// # Safety
+// `path` must be a NUL-terminated UTF-8 string; `limits` and `out` valid pointers.
+enum NepakStatus nepak_open_with_limits(const char *path,
+                                        const struct NepakLimits *limits,
+                                        struct NepakPak **out);
+
+// Close a handle from [`nepak_open`]. NULL is ignored.
+//
+// # Safety
+// `pak` must be NULL or a handle that was not closed yet.
+void nepak_close(struct NepakPak *pak);
+
+// Number of entries; iterate with indexes `0..count` using
+// [`nepak_entry_path`] and [`nepak_entry_size`]. Entries are sorted by path.
+// Returns 0 for NULL.
+//
+// # Safety
+// `pak` must be NULL or a handle from `nepak_open` that was not closed.
+size_t nepak_entry_count(const struct NepakPak *pak);
+
+// Store the path of entry `index` in `*out_path`. The string is owned by the
+// handle and stays valid until [`nepak_close`].
+//
+// # Safety
+// `pak` must be a handle from `nepak_open` that was not closed; `out_path` a valid pointer.
+enum NepakStatus nepak_entry_path(const struct NepakPak *pak, size_t index, const char **out_path);
+
+// Look up an entry by its exact path and store its index in `*out_index`.
+//
+// # Safety
+// `pak` must be a handle from `nepak_open` that was not closed; `path` a
+// NUL-terminated string; `out_index` a valid pointer.
+enum NepakStatus nepak_find(const struct NepakPak *pak, const char *path, size_t *out_index);
+
+// Store the decompressed size of entry `index` in `*out_size`: the buffer size
+// [`nepak_read`] needs.
+//
+// # Safety
+// `pak` must be a handle from `nepak_open` that was not closed; `out_size` a valid pointer.
+enum NepakStatus nepak_entry_size(const struct NepakPak *pak, size_t index, uint64_t *out_size);
+
+// Read entry `index` (decompressed and hash-checked) into `buf`. `*out_len`
+// receives the entry size, also when `buf_len` is too small
+// ([`NepakStatus::BufferTooSmall`]). `buf` may be NULL when `buf_len` is 0.
+//
+// # Safety
+// `pak` must be a handle from `nepak_open` that was not closed; `buf` must be
+// valid for `buf_len` bytes of writes; `out_len` a valid pointer.
+enum NepakStatus nepak_read(struct NepakPak *pak,
+                            size_t index,
+                            uint8_t *buf,
+                            size_t buf_len,
+                            size_t *out_len);
+
+// Static, human-readable name of a status code ("unknown status" for values
+// outside [`NepakStatus`]).
+const char *nepak_status_str(int32_t status);
+
+// Message describing the last failed call on this thread (empty if none).
+// Valid until the next failing call on the same thread.
+const char *nepak_last_error(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* NEPAK_H */
@@ -0,0 +1,397 @@
+//! C ABI for reading NEPAK v1 paks, built on the `nepak` crate's reader so
+//! engines don't need their own parser. The header is `include/nepak.h`.
+//!
+//! Every function returns a [`NepakStatus`]; on failure a message for the
+//! calling thread is available from [`nepak_last_error`]. Handles are not
+//! thread-safe: open one per thread.
+
+#![deny(unsafe_op_in_unsafe_fn)]
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use nepak::pak::{PakError, PakFile, ReadLimits};
+
+/// Result of every fallible call.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NepakStatus {
+    Ok = 0,
+    /// A required pointer argument was NULL.
+    NullArgument = 1,
+    /// A string argument was not valid UTF-8.
+    InvalidUtf8 = 2,
+    /// The file could not be opened or read.
+    Io = 3,
+    /// The file is not a valid pak, or an entry failed its hash check.
+    InvalidPak = 4,
+    /// No entry with that path.
+    NotFound = 5,
+    /// Entry index is not below `nepak_entry_count`.
+    IndexOutOfRange = 6,
+    /// The output buffer is smaller than the entry; the needed size was stored.
+    BufferTooSmall = 7,
+    /// The entry's codec is not available: zstd in a library built without
+    /// zstd, or a custom codec.
+    Unsupported = 8,
+    /// Internal error (a Rust panic was caught at the boundary).
+    Internal = 9,
+}
+
+/// Upper bounds checked while opening and reading a pak; see
+/// [`nepak_default_limits`] and [`nepak_open_with_limits`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct NepakLimits {
+    /// Largest index block, stored or decompressed, in bytes.
+    pub max_index_len: u64,
+    /// Most index entries.
+    pub max_entries: u32,
+    /// Largest decompressed entry, in bytes.
+    pub max_raw_len: u64,
+}
+
+impl From<NepakLimits> for ReadLimits {
+    fn from(l: NepakLimits) -> Self {
+        ReadLimits {
+            max_index_len: l.max_index_len,
+            max_entries: l.max_entries,
+            max_raw_len: l.max_raw_len,
+        }
+    }
+}
+
+/// An open pak. Create with [`nepak_open`], free with [`nepak_close`].
+pub struct NepakPak {
+    file: PakFile,
+    /// Entry paths (index order) as C strings, and their decompressed sizes.
+    paths: Vec<CString>,
+    sizes: Vec<u64>,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+fn set_last_error(msg: impl Into<Vec<u8>>) {
+    let mut msg = msg.into();
+    msg.retain(|&b| b != 0);
+    let msg = CString::new(msg).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = msg);
+}
+
+fn pak_status(e: &PakError) -> NepakStatus {
+    match e {
+        PakError::Io(_) => NepakStatus::Io,
+        PakError::NotFound(_) => NepakStatus::NotFound,
+        PakError::NoZstd | PakError::UnknownCodec { .. } => NepakStatus::Unsupported,
+        _ => NepakStatus::InvalidPak,
+    }
+}
+
+/// Run `f`, turning errors into a status plus last-error message and panics into `Internal`.
+fn guard(f: impl FnOnce() -> Result<(), (NepakStatus, String)>) -> NepakStatus {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => NepakStatus::Ok,
+        Ok(Err((status, msg))) => {
+            set_last_error(msg);
+            status
+        }
+        Err(_) => {
+            set_last_error("internal error (panic)");
+            NepakStatus::Internal
+        }
+    }
+}
+
+fn pak_err(e: PakError) -> (NepakStatus, String) {
+    (pak_status(&e), e.to_string())
+}
+
+fn null(what: &str) -> (NepakStatus, String) {
+    (NepakStatus::NullArgument, format!("{what} is NULL"))
+}
+
+/// # Safety
+/// `s` must be NULL or a valid NUL-terminated string.
+unsafe fn str_arg<'a>(s: *const c_char, what: &str) -> Result<&'a str, (NepakStatus, String)> {
+    if s.is_null() {
+        return Err(null(what));
+    }
+    // SAFETY: non-NULL and NUL-terminated per the caller's contract.
+    unsafe { CStr::from_ptr(s) }.to_str().map_err(|_| {
+        (
+            NepakStatus::InvalidUtf8,
+            format!("{what} is not valid UTF-8"),
+        )
+    })
+}
+
+/// # Safety
+/// `pak` must be NULL or a handle from `nepak_open` that was not closed.
+unsafe fn pak_arg<'a>(pak: *const NepakPak) -> Result<&'a NepakPak, (NepakStatus, String)> {
+    // SAFETY: valid or NULL per the caller's contract.
+    unsafe { pak.as_ref() }.ok_or_else(|| null("pak"))
+}
+
+fn check_index(pak: &NepakPak, index: usize) -> Result<(), (NepakStatus, String)> {
+    if index < pak.paths.len() {
+        Ok(())
+    } else {
+        Err((
+            NepakStatus::IndexOutOfRange,
+            format!("entry index {index} >= {}", pak.paths.len()),
+        ))
+    }
+}
+
+/// The limits [`nepak_open`] uses. They accept any pak a normal build
+/// produces; lower them for paks from untrusted sources.
+#[no_mangle]
+pub extern "C" fn nepak_default_limits() -> NepakLimits {
+    let l = ReadLimits::default();
+    NepakLimits {
+        max_index_len: l.max_index_len,
+        max_entries: l.max_entries,
+        max_raw_len: l.max_raw_len,
+    }
+}
+
+/// Open the pak at `path` and check its index. On success `*out` receives a
+/// handle to pass to [`nepak_close`]; on failure it is set to NULL.
+///
+/// # Safety
+/// `path` must be a NUL-terminated UTF-8 string and `out` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn nepak_open(path: *const c_char, out: *mut *mut NepakPak) -> NepakStatus {
+    let limits = nepak_default_limits();
+    // SAFETY: per this function's contract; `limits` is a local.
+    unsafe { nepak_open_with_limits(path, &limits, out) }
+}
+
+/// [`nepak_open`] with custom limits. Paks over a limit fail with
+/// [`NepakStatus::InvalidPak`].
+///
+/// # Safety
+/// `path` must be a NUL-terminated UTF-8 string; `limits` and `out` valid pointers.
+#[no_mangle]
+pub unsafe extern "C" fn nepak_open_with_limits(
+    path: *const c_char,
+    limits: *const NepakLimits,
+    out: *mut *mut NepakPak,
+) -> NepakStatus {
+    guard(|| {
+        if out.is_null() {
+            return Err(null("out"));
+        }
+        // SAFETY: checked non-NULL above; the caller guarantees it is writable.
+        unsafe { *out = std::ptr::null_mut() };
+        // SAFETY: per this function's contract.
+        let path = unsafe { str_arg(path, "path") }?;
+        // SAFETY: valid or NULL per this function's contract.
+        let limits = unsafe { limits.as_ref() }.ok_or_else(|| null("limits"))?;
+
+        let file = PakFile::open_with_limits(path, &(*limits).into()).map_err(pak_err)?;
+        let entries = file.entries();
+        let sizes = entries.iter().map(|e| e.raw_len).collect();
+        let paths = entries
+            .into_iter()
+            .map(|e| {
+                CString::new(e.path)
+                    .map_err(|_| pak_err(PakError::Invalid("NUL in entry path".into())))
+            })
+            .collect::<Result<_, _>>()?;
+
+        let pak = Box::new(NepakPak { file, paths, sizes });
+        // SAFETY: as above.
+        unsafe { *out = Box::into_raw(pak) };
+        Ok(())
+    })
+}
+
+/// Close a handle from [`nepak_open`]. NULL is ignored.
+///
+/// # Safety
+/// `pak` must be NULL or a handle that was not closed yet.
+#[no_mangle]
+pub unsafe extern "C" fn nepak_close(pak: *mut NepakPak) {
+    if !pak.is_null() {
+        // SAFETY: created by `Box::into_raw` in `nepak_open`, freed only here.
+        drop(unsafe { Box::from_raw(pak) });
+    }
+}
+
+/// Number of entries; iterate with indexes `0..count` using
+/// [`nepak_entry_path`] and [`nepak_entry_size`]. Entries are sorted by path.
+/// Returns 0 for NULL.
+///
+/// # Safety
+/// `pak` must be NULL or a handle from `nepak_open` that was not closed.
+#[no_mangle]
+pub unsafe extern "C" fn nepak_entry_count(pak: *const NepakPak) -> usize {
+    // SAFETY: per this function's contract.
+    unsafe { pak.as_ref() }.map_or(0, |p| p.paths.len())
+}
+
+/// Store the path of entry `index` in `*out_path`. The string is owned by the
+/// handle and stays valid until [`nepak_close`].
+///
+/// # Safety
+/// `pak` must be a handle from `nepak_open` that was not closed; `out_path` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn nepak_entry_path(
+    pak: *const NepakPak,
+    index: usize,
+    out_path: *mut *const c_char,
+) -> NepakStatus {
+    guard(|| {
+        // SAFETY: per this function's contract.
+        let pak = unsafe { pak_arg(pak) }?;
+        if out_path.is_null() {
+            return Err(null("out_path"));
+        }
+        check_index(pak, index)?;
+        // SAFETY: checked non-NULL above.
+        unsafe { *out_path = pak.paths[index].as_ptr() };
+        Ok(())
+    })
+}
+
+/// Look up an entry by its exact path and store its index in `*out_index`.
+///
+/// # Safety
+/// `pak` must be a handle from `nepak_open` that was not closed; `path` a
+/// NUL-terminated string; `out_index` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn nepak_find(
+    pak: *const NepakPak,
+    path: *const c_char,
+    out_index: *mut usize,
+) -> NepakStatus {
+    guard(|| {
+        // SAFETY: per this function's contract.
+        let pak = unsafe { pak_arg(pak) }?;
+        // SAFETY: per this function's contract.
+        let path = unsafe { str_arg(path, "path") }?;
+        if out_index.is_null() {
+            return Err(null("out_index"));
+        }
+        let index = pak
+            .paths
+            .binary_search_by(|p| p.as_bytes().cmp(path.as_bytes()))
+            .map_err(|_| pak_err(PakError::NotFound(path.to_string())))?;
+        // SAFETY: checked non-NULL above.
+        unsafe { *out_index = index };
+        Ok(())
+    })
+}
+
+/// Store the decompressed size of entry `index` in `*out_size`: the buffer size
+/// [`nepak_read`] needs.
+///
+/// # Safety
+/// `pak` must be a handle from `nepak_open` that was not closed; `out_size` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn nepak_entry_size(
+    pak: *const NepakPak,
+    index: usize,
+    out_size: *mut u64,
+) -> NepakStatus {
+    guard(|| {
+        // SAFETY: per this function's contract.
+        let pak = unsafe { pak_arg(pak) }?;
+        if out_size.is_null() {
+            return Err(null("out_size"));
+        }
+        check_index(pak, index)?;
+        // SAFETY: checked non-NULL above.
+        unsafe { *out_size = pak.sizes[index] };
+        Ok(())
+    })
+}
+
+/// Read entry `index` (decompressed and hash-checked) into `buf`. `*out_len`
+/// receives the entry size, also when `buf_len` is too small
+/// ([`NepakStatus::BufferTooSmall`]). `buf` may be NULL when `buf_len` is 0.
+///
+/// # Safety
+/// `pak` must be a handle from `nepak_open` that was not closed; `buf` must be
+/// valid for `buf_len` bytes of writes; `out_len` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn nepak_read(
+    pak: *mut NepakPak,
+    index: usize,
+    buf: *mut u8,
+    buf_len: usize,
+    out_len: *mut usize,
+) -> NepakStatus {
+    guard(|| {
+        // SAFETY: per this function's contract.
+        let pak = unsafe { pak.as_mut() }.ok_or_else(|| null("pak"))?;
+        if out_len.is_null() {
+            return Err(null("out_len"));
+        }
+        check_index(pak, index)?;
+
+        let size = usize::try_from(pak.sizes[index]).map_err(|_| {
+            (
+                NepakStatus::InvalidPak,
+                "entry does not fit in memory".to_string(),
+            )
+        })?;
+        // SAFETY: checked non-NULL above.
+        unsafe { *out_len = size };
+        if buf_len < size {
+            return Err((
+                NepakStatus::BufferTooSmall,
+                format!("buffer of {buf_len} bytes is too small for {size}"),
+            ));
+        }
+        if size == 0 {
+            return Ok(());
+        }
+        if buf.is_null() {
+            return Err(null("buf"));
+        }
+
+        let path = pak.paths[index]
+            .to_str()
+            .expect("paths come from UTF-8 strings");
+        let bytes = pak.file.read(path).map_err(pak_err)?;
+        // SAFETY: `buf` holds at least `buf_len >= size == bytes.len()` bytes.
+        unsafe { std::ptr::copy_nonoverlapping(bytes.as_ptr(), buf, bytes.len()) };
+        Ok(())
+    })
+}
+
+/// Static, human-readable name of a status code ("unknown status" for values
+/// outside [`NepakStatus`]).
+#[no_mangle]
+pub extern "C" fn nepak_status_str(status: i32) -> *const c_char {
+    // Indexed by `NepakStatus` discriminant.
+    const NAMES: [&CStr; 10] = [
+        c"ok",
+        c"NULL argument",
+        c"invalid UTF-8",
+        c"I/O error",
+        c"invalid pak",
+        c"entry not found",
+        c"entry index out of range",
+        c"buffer too small",
+        c"unsupported payload codec",
+        c"internal error",
+    ];
+    usize::try_from(status)
+        .ok()
+        .and_then(|i| NAMES.get(i))
+        .unwrap_or(&c"unknown status")
+        .as_ptr()
+}
+
+/// Message describing the last failed call on this thread (empty if none).
+/// Valid until the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn nepak_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ptr())
+}
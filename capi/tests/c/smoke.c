@@ -0,0 +1,78 @@
+/* Exercises the C API against a pak built by tests/c_api.rs:
+ *   a.txt      "hello pak\n"
+ *   sub/b.bin  bytes 0..=255
+ * Usage: smoke <pak> */
+
+#include <stdio.h>
+#include <string.h>
+
+#include "nepak.h"
+
+#define CHECK(cond)                                                          \
+    do {                                                                     \
+        if (!(cond)) {                                                       \
+            fprintf(stderr, "%s:%d: check failed: %s (last error: %s)\n",    \
+                    __FILE__, __LINE__, #cond, nepak_last_error());          \
+            return 1;                                                        \
+        }                                                                    \
+    } while (0)
+
+int main(int argc, char **argv) {
+    NepakPak *pak = NULL;
+    const char *path = NULL;
+    size_t index = 0, len = 0;
+    uint64_t size = 0;
+    unsigned char buf[256];
+
+    CHECK(argc == 2);
+
+    /* Errors leave *out NULL and set a message. */
+    pak = (NepakPak *)1;
+    CHECK(nepak_open("/nonexistent/x.pak", &pak) == NEPAK_STATUS_IO);
+    CHECK(pak == NULL);
+    CHECK(strlen(nepak_last_error()) > 0);
+    CHECK(nepak_open(NULL, &pak) == NEPAK_STATUS_NULL_ARGUMENT);
+    CHECK(strcmp(nepak_status_str(NEPAK_STATUS_NOT_FOUND), "entry not found") == 0);
+    CHECK(strcmp(nepak_status_str(99), "unknown status") == 0);
+
+    /* sub/b.bin is over a 100-byte entry limit. */
+    NepakLimits limits = nepak_default_limits();
+    limits.max_raw_len = 100;
+    CHECK(nepak_open_with_limits(argv[1], &limits, &pak) == NEPAK_STATUS_INVALID_PAK);
+    CHECK(pak == NULL);
+
+    CHECK(nepak_open(argv[1], &pak) == NEPAK_STATUS_OK);
+    CHECK(pak != NULL);
+
+    /* Iteration, sorted by path. */
+    CHECK(nepak_entry_count(pak) == 2);
+    CHECK(nepak_entry_path(pak, 0, &path) == NEPAK_STATUS_OK);
+    CHECK(strcmp(path, "a.txt") == 0);
+    CHECK(nepak_entry_path(pak, 1, &path) == NEPAK_STATUS_OK);
+    CHECK(strcmp(path, "sub/b.bin") == 0);
+    CHECK(nepak_entry_path(pak, 2, &path) == NEPAK_STATUS_INDEX_OUT_OF_RANGE);
+
+    /* Lookup. */
+    CHECK(nepak_find(pak, "sub/b.bin", &index) == NEPAK_STATUS_OK);
+    CHECK(index == 1);
+    CHECK(nepak_find(pak, "missing", &index) == NEPAK_STATUS_NOT_FOUND);
+
+    /* Sizing, then reading. */
+    CHECK(nepak_entry_size(pak, 0, &size) == NEPAK_STATUS_OK);
+    CHECK(size == 10);
+    CHECK(nepak_read(pak, 0, NULL, 0, &len) == NEPAK_STATUS_BUFFER_TOO_SMALL);
+    CHECK(len == 10);
+    CHECK(nepak_read(pak, 0, buf, sizeof buf, &len) == NEPAK_STATUS_OK);
+    CHECK(len == 10 && memcmp(buf, "hello pak\n", 10) == 0);
+
+    CHECK(nepak_read(pak, 1, buf, sizeof buf, &len) == NEPAK_STATUS_OK);
+    CHECK(len == 256);
+    for (size_t i = 0; i < 256; i++) {
+        CHECK(buf[i] == (unsigned char)i);
+    }
+
+    nepak_close(pak);
+    nepak_close(NULL);
+    puts("ok");
+    return 0;
+}
@@ -0,0 +1,84 @@
+//! Compile `tests/c/smoke.c` against `include/nepak.h` and a freshly built
+//! shared library, then run it on a pak built with the `nepak` crate.
+
+#![cfg(target_os = "linux")]
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use nepak::pak::{self, BuildOptions, InputRoot};
+
+/// Cargo doesn't build the cdylib for integration tests, so build it here
+/// (with this test's features) into a target dir of its own.
+fn lib_dir() -> PathBuf {
+    let exe = std::env::current_exe().unwrap();
+    let target_dir = exe.ancestors().nth(3).unwrap().join("capi-c-test");
+    let mut cargo = Command::new(env!("CARGO"));
+    cargo
+        .args(["build", "--lib", "--no-default-features"])
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .current_dir(env!("CARGO_MANIFEST_DIR"));
+    if cfg!(feature = "zstd") {
+        cargo.args(["--features", "zstd"]);
+    }
+    assert!(cargo.status().unwrap().success(), "building the library failed");
+    target_dir.join("debug")
+}
+
+fn build_pak(root: &Path) -> PathBuf {
+    let input = root.join("input");
+    std::fs::create_dir_all(input.join("sub")).unwrap();
+    std::fs::write(input.join("a.txt"), b"hello pak\n").unwrap();
+    std::fs::write(input.join("sub/b.bin"), (0..=255u8).collect::<Vec<_>>()).unwrap();
+
+    let output = root.join("smoke.pak");
+    let opts = BuildOptions {
+        inputs: vec![InputRoot::new(&input, "")],
+        compress: cfg!(feature = "zstd"),
+        zstd_level: 3,
+        ..Default::default()
+    };
+    pak::build(&opts, &output).unwrap();
+    output
+}
+
+#[test]
+fn c_smoke_test() {
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let root = std::env::temp_dir().join(format!("nepak-capi-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+
+    let pak = build_pak(&root);
+    let lib_dir = lib_dir();
+    let exe = root.join("smoke");
+    let cc = std::env::var("CC").unwrap_or_else(|_| "cc".into());
+    let status = Command::new(cc)
+        .args(["-std=c99", "-Wall", "-Werror", "-o"])
+        .arg(&exe)
+        .arg(crate_dir.join("tests/c/smoke.c"))
+        .arg("-I")
+        .arg(crate_dir.join("include"))
+        .arg("-L")
+        .arg(&lib_dir)
+        .arg("-lnepak_capi")
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .status()
+        .expect("failed to run the C compiler");
+    assert!(status.success(), "compiling smoke.c failed");
+
+    // Cargo's LD_LIBRARY_PATH would win over the rpath and load a stale library.
+    let out = Command::new(&exe)
+        .arg(&pak)
+        .env_remove("LD_LIBRARY_PATH")
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "smoke failed:\n{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert_eq!(out.stdout, b"ok\n");
+    let _ = std::fs::remove_dir_all(&root);
+}
@@ -0,0 +1,25 @@
+//! `include/nepak.h` is generated by cbindgen. This test fails when it is out of
+//! date; run with `NEPAK_BLESS=1` to rewrite it.
+
+use std::path::Path;
+
+#[test]
+fn header_is_up_to_date() {
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml")).unwrap();
+    let mut generated = Vec::new();
+    cbindgen::generate_with_config(crate_dir, config)
+        .unwrap()
+        .write(&mut generated);
+
+    let header = crate_dir.join("include/nepak.h");
+    if std::env::var_os("NEPAK_BLESS").is_some() {
+        std::fs::write(&header, &generated).unwrap();
+        return;
+    }
+    let committed = std::fs::read(&header).unwrap_or_default();
+    assert!(
+        committed == generated,
+        "include/nepak.h is out of date; rerun with NEPAK_BLESS=1"
+    );
+}
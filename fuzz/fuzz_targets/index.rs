@@ -0,0 +1,7 @@
+//! Index block decoding (all encodings), without the footer or hash check.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| nepak::pak::fuzz::index(data));
@@ -0,0 +1,7 @@
+//! Whole pak files: footer, index and every payload.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| nepak::pak::fuzz::reader(data));
@@ -7,7 +7,7 @@ use eframe::egui;
 use std::sync::mpsc;
 
 #[cfg(feature = "gui")]
-use crate::gui::form::BuildForm;
+use crate::gui::form::{BuildForm, InputRow};
 
 #[cfg(feature = "gui")]
 use crate::gui::jobs::JobMsg;
@@ -15,6 +15,9 @@ use crate::gui::jobs::JobMsg;
 #[cfg(feature = "gui")]
 use crate::gui::tabs::Tab;
 
+#[cfg(feature = "gui")]
+use crate::pak;
+
 #[cfg(feature = "gui")]
 pub struct NePakApp {
     pub tab: Tab,
@@ -33,12 +36,12 @@ pub struct NePakApp {
     pub status: String,
     pub busy: bool,
 
-    pub progress_stage: String,
-    pub progress_done: u64,
-    pub progress_total: u64,
-    pub progress_item: String,
+    /// Latest progress event of the running job.
+    pub progress: Option<pak::Progress>,
 
     pub rx: Option<mpsc::Receiver<JobMsg>>,
+    /// Token of the running job; set by the Cancel button.
+    pub cancel: Option<pak::CancelToken>,
 }
 
 #[cfg(feature = "gui")]
@@ -47,12 +50,20 @@ impl Default for NePakApp {
         Self {
             tab: Tab::Build,
             build: BuildForm {
-                input_dir: "./assets".into(),
+                inputs: vec![InputRow {
+                    dir: "./assets".into(),
+                    mount: String::new(),
+                }],
                 output_pak: "./assets.pak".into(),
                 prefix: "assets".into(),
+                allow_override: false,
                 excludes_csv: ".git,target".into(),
                 compress: true,
                 zstd_level: 6,
+                strict_portability: false,
+                nfc: false,
+                #[cfg(feature = "watch")]
+                watch: false,
             },
             list_pak: "./assets.pak".into(),
             extract_pak: "./assets.pak".into(),
@@ -64,11 +75,9 @@ impl Default for NePakApp {
             logs: vec!["NEPAK GUI ready.".into()],
             status: String::new(),
             busy: false,
-            progress_stage: String::new(),
-            progress_done: 0,
-            progress_total: 0,
-            progress_item: String::new(),
+            progress: None,
             rx: None,
+            cancel: None,
         }
     }
 }
@@ -85,7 +94,7 @@ impl NePakApp {
     }
 
     pub fn poll_jobs(&mut self) {
-        let mut rx = match self.rx.take() {
+        let rx = match self.rx.take() {
             Some(rx) => rx,
             None => return,
         };
@@ -96,26 +105,14 @@ impl NePakApp {
             match msg {
                 JobMsg::Log(s) => self.push_log(s),
 
-                JobMsg::Progress {
-                    stage,
-                    done,
-                    total,
-                    item,
-                } => {
-                    self.progress_stage = stage;
-                    self.progress_done = done;
-                    self.progress_total = total;
-                    self.progress_item = item.unwrap_or_default();
-                }
+                JobMsg::Progress(p) => self.progress = Some(p),
 
                 JobMsg::Done(r) => {
                     self.busy = false;
-                    self.progress_stage.clear();
-                    self.progress_done = 0;
-                    self.progress_total = 0;
-                    self.progress_item.clear();
+                    self.progress = None;
                     self.status = match r {
                         Ok(()) => "Done.".into(),
+                        Err(_) if self.cancel_requested() => "Cancelled.".into(),
                         Err(e) => format!("Error: {e}"),
                     };
                     self.push_log(self.status.clone());
@@ -125,16 +122,16 @@ impl NePakApp {
 
                 JobMsg::ListDone(r) => {
                     self.busy = false;
-                    self.progress_stage.clear();
-                    self.progress_done = 0;
-                    self.progress_total = 0;
-                    self.progress_item.clear();
+                    self.progress = None;
                     match r {
                         Ok(list) => {
                             self.entries = list;
                             self.entries_err = None;
                             self.status = format!("Loaded {} entries.", self.entries.len());
                         }
+                        Err(_) if self.cancel_requested() => {
+                            self.status = "Cancelled.".into();
+                        }
                         Err(e) => {
                             self.entries.clear();
                             self.entries_err = Some(e.clone());
@@ -148,12 +145,20 @@ impl NePakApp {
 
                 JobMsg::VerifyDone(r) => {
                     self.busy = false;
-                    self.progress_stage.clear();
-                    self.progress_done = 0;
-                    self.progress_total = 0;
-                    self.progress_item.clear();
+                    self.progress = None;
                     self.status = match r {
-                        Ok(()) => "Pak verified OK.".into(),
+                        Ok(report) if report.is_ok() => "Pak verified OK.".into(),
+                        Ok(report) => {
+                            for issue in &report.issues {
+                                self.push_log(issue.to_string());
+                            }
+                            format!(
+                                "Verify found {} problem(s) in {} entries.",
+                                report.issues.len(),
+                                report.entries
+                            )
+                        }
+                        Err(_) if self.cancel_requested() => "Cancelled.".into(),
                         Err(e) => format!("Error: {e}"),
                     };
                     self.push_log(self.status.clone());
@@ -167,22 +172,39 @@ impl NePakApp {
             self.rx = Some(rx);
         } else {
             self.rx = None;
+            self.cancel = None;
         }
     }
 
-    pub fn start_job(&mut self, f: impl FnOnce(mpsc::Sender<JobMsg>) + Send + 'static) {
+    fn cancel_requested(&self) -> bool {
+        self.cancel.as_ref().is_some_and(pak::CancelToken::is_cancelled)
+    }
+
+    pub fn cancel_job(&mut self) {
+        if let Some(cancel) = &self.cancel {
+            if !cancel.is_cancelled() {
+                cancel.cancel();
+                self.status = "Cancelling...".into();
+                self.push_log("Cancel requested.");
+            }
+        }
+    }
+
+    pub fn start_job(
+        &mut self,
+        f: impl FnOnce(mpsc::Sender<JobMsg>, pak::CancelToken) + Send + 'static,
+    ) {
         if self.busy {
             return;
         }
         let (tx, rx) = mpsc::channel();
+        let cancel = pak::CancelToken::new();
         self.rx = Some(rx);
+        self.cancel = Some(cancel.clone());
         self.busy = true;
         self.status = "Working...".into();
-        self.progress_stage = "Starting".into();
-        self.progress_done = 0;
-        self.progress_total = 0;
-        self.progress_item.clear();
-        std::thread::spawn(move || f(tx));
+        self.progress = None;
+        std::thread::spawn(move || f(tx, cancel));
     }
 
     pub fn browse_folder(target: &mut String) {
@@ -224,10 +246,8 @@ impl eframe::App for NePakApp {
                 Tab::Extract => self.ui_extract(ui),
                 Tab::Verify => self.ui_verify(ui),
             }
+            self.ui_progress(ui);
             self.ui_logs(ui);
         });
     }
 }
-
-#[cfg(feature = "gui")]
-use crate::gui::ui::{build::*, extract::*, list::*, logs::*, topbar::*, verify::*};
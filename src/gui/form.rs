@@ -3,28 +3,41 @@
 #[cfg(feature = "gui")]
 use std::path::PathBuf;
 
+#[cfg(feature = "gui")]
+use crate::pak;
+
+/// One input root row of the Build tab.
+#[cfg(feature = "gui")]
+#[derive(Default, Clone)]
+pub struct InputRow {
+    pub dir: String,
+    /// Mount prefix for this root; empty uses the form's default prefix.
+    pub mount: String,
+}
+
 #[cfg(feature = "gui")]
 #[derive(Default, Clone)]
 pub struct BuildForm {
-    pub input_dir: String,
+    /// Input roots, in override order.
+    pub inputs: Vec<InputRow>,
     pub output_pak: String,
     pub prefix: String,
+    /// Let later inputs override earlier ones on path collisions.
+    pub allow_override: bool,
     pub excludes_csv: String,
     pub compress: bool,
     pub zstd_level: i32,
+    pub strict_portability: bool,
+    pub nfc: bool,
+    /// Keep rebuilding on input changes until the job is cancelled.
+    #[cfg(feature = "watch")]
+    pub watch: bool,
 }
 
 #[cfg(feature = "gui")]
 impl BuildForm {
     pub fn normalized_prefix(&self) -> String {
-        let mut s = self.prefix.trim().replace('\\', "/");
-        if s == "." {
-            s.clear();
-        }
-        if !s.is_empty() && !s.ends_with('/') {
-            s.push('/');
-        }
-        s
+        normalize_mount(&self.prefix)
     }
 
     pub fn excludes(&self) -> Vec<String> {
@@ -53,13 +66,29 @@ impl BuildForm {
         s
     }
 
-    pub fn to_args(&self) -> Result<(PathBuf, PathBuf, String, Vec<String>, bool, i32), String> {
-        let input = PathBuf::from(self.input_dir.trim());
-        if self.input_dir.trim().is_empty() {
-            return Err("Input directory is empty".into());
+    pub fn to_args(&self) -> Result<(pak::BuildOptions, PathBuf), String> {
+        let prefix = self.normalized_prefix();
+        let mut inputs = Vec::with_capacity(self.inputs.len());
+        for (i, row) in self.inputs.iter().enumerate() {
+            let dir = row.dir.trim();
+            if dir.is_empty() {
+                return Err(format!("Input directory {} is empty", i + 1));
+            }
+            let input = PathBuf::from(dir);
+            if !input.is_dir() {
+                return Err(format!(
+                    "Input directory '{dir}' does not exist or is not a directory"
+                ));
+            }
+            let mount = if row.mount.trim().is_empty() {
+                prefix.clone()
+            } else {
+                normalize_mount(&row.mount)
+            };
+            inputs.push(pak::InputRoot::new(input, mount));
         }
-        if !input.exists() || !input.is_dir() {
-            return Err("Input directory does not exist or is not a directory".into());
+        if inputs.is_empty() {
+            return Err("No input directories".into());
         }
 
         let out_s = Self::ensure_pak_ext(&self.output_pak);
@@ -68,7 +97,6 @@ impl BuildForm {
         }
         let output = PathBuf::from(out_s);
 
-        let prefix = self.normalized_prefix();
         let excludes = self.excludes();
 
         let level = if self.compress {
@@ -77,6 +105,32 @@ impl BuildForm {
             0
         };
 
-        Ok((input, output, prefix, excludes, self.compress, level))
+        let opts = pak::BuildOptions {
+            inputs,
+            excludes,
+            allow_override: self.allow_override,
+            compress: self.compress,
+            zstd_level: level,
+            portability: if self.strict_portability {
+                pak::PortabilityMode::Error
+            } else {
+                pak::PortabilityMode::Warn
+            },
+            nfc: self.nfc,
+            ..Default::default()
+        };
+        Ok((opts, output))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(feature = "gui")]
+fn normalize_mount(mount: &str) -> String {
+    let mut s = mount.trim().replace('\\', "/");
+    if s == "." {
+        s.clear();
+    }
+    if !s.is_empty() && !s.ends_with('/') {
+        s.push('/');
+    }
+    s
+}
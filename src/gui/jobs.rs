@@ -1,37 +1,25 @@
 #![forbid(unsafe_code)]
 
 #[cfg(feature = "gui")]
-use crate::pak;
+use std::sync::mpsc;
 
 #[cfg(feature = "gui")]
-use std::sync::mpsc;
+use crate::pak;
 
 #[cfg(feature = "gui")]
 #[derive(Debug)]
 pub enum JobMsg {
     Log(String),
-    Progress {
-        stage: String,
-        done: u64,
-        total: u64,
-        item: Option<String>,
-    },
+    Progress(pak::Progress),
     Done(Result<(), String>),
     ListDone(Result<Vec<pak::EntryInfo>, String>),
-    VerifyDone(Result<(), String>),
+    VerifyDone(Result<pak::VerifyReport, String>),
 }
 
+/// Progress callback that forwards every event to the GUI thread.
 #[cfg(feature = "gui")]
-pub struct JobHandle {
-    pub rx: mpsc::Receiver<JobMsg>,
+pub fn forward_progress(tx: &mpsc::Sender<JobMsg>) -> impl FnMut(pak::Progress) + '_ {
+    move |p| {
+        let _ = tx.send(JobMsg::Progress(p));
+    }
 }
-
-#[cfg(feature = "gui")]
-pub fn spawn_job<F>(f: F) -> JobHandle
-where
-    F: FnOnce(mpsc::Sender<JobMsg>) + Send + 'static,
-{
-    let (tx, rx) = mpsc::channel();
-    std::thread::spawn(move || f(tx));
-    JobHandle { rx }
-}
\ No newline at end of file
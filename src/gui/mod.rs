@@ -24,10 +24,7 @@ pub fn run() -> pak::PakResult<()> {
         Box::new(|_cc| Box::new(app::NePakApp::default())),
     )
         .map_err(|e| {
-            pak::PakError::Io(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("eframe: {e}"),
-            ))
+            pak::PakError::Io(std::io::Error::other(format!("eframe: {e}")))
         })
 }
 
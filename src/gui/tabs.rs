@@ -1,17 +1,11 @@
 #![forbid(unsafe_code)]
 
 #[cfg(feature = "gui")]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub enum Tab {
+    #[default]
     Build,
     List,
     Extract,
     Verify,
 }
-
-#[cfg(feature = "gui")]
-impl Default for Tab {
-    fn default() -> Self {
-        Self::Build
-    }
-}
\ No newline at end of file
@@ -4,7 +4,11 @@
 use eframe::egui;
 
 #[cfg(feature = "gui")]
-use crate::gui::{app::NePakApp, jobs::JobMsg};
+use crate::gui::{
+    app::NePakApp,
+    form::InputRow,
+    jobs::{forward_progress, JobMsg},
+};
 
 #[cfg(feature = "gui")]
 use crate::pak;
@@ -12,16 +16,41 @@ use crate::pak;
 #[cfg(feature = "gui")]
 impl NePakApp {
     pub fn ui_build(&mut self, ui: &mut egui::Ui) {
-        ui.label("Create a deterministic .pak from an input directory.");
+        ui.label("Create a deterministic .pak from one or more input directories.");
         ui.add_space(6.0);
 
         ui.group(|ui| {
+            let can_remove = self.build.inputs.len() > 1;
+            let mut remove = None;
+            for (i, row) in self.build.inputs.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label("Input dir");
+                    ui.text_edit_singleline(&mut row.dir);
+                    if ui.button("Browse…").clicked() {
+                        Self::browse_folder(&mut row.dir);
+                    }
+                    ui.label("mount");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut row.mount)
+                            .hint_text("prefix")
+                            .desired_width(120.0),
+                    );
+                    if ui.add_enabled(can_remove, egui::Button::new("Remove")).clicked() {
+                        remove = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = remove {
+                self.build.inputs.remove(i);
+            }
             ui.horizontal(|ui| {
-                ui.label("Input dir");
-                ui.text_edit_singleline(&mut self.build.input_dir);
-                if ui.button("Browse…").clicked() {
-                    Self::browse_folder(&mut self.build.input_dir);
+                if ui.button("Add input").clicked() {
+                    self.build.inputs.push(InputRow::default());
                 }
+                ui.checkbox(
+                    &mut self.build.allow_override,
+                    "later inputs override earlier ones on collisions",
+                );
             });
 
             ui.horizontal(|ui| {
@@ -35,7 +64,7 @@ impl NePakApp {
             ui.horizontal(|ui| {
                 ui.label("Prefix");
                 ui.text_edit_singleline(&mut self.build.prefix);
-                ui.label("→ mounted as '<prefix>/path'");
+                ui.label("→ mounted as '<prefix>/path' unless an input sets its own mount");
             });
 
             ui.horizontal(|ui| {
@@ -52,6 +81,14 @@ impl NePakApp {
                 );
             });
 
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.build.strict_portability, "fail on portability issues");
+                ui.checkbox(&mut self.build.nfc, "normalize paths to NFC");
+            });
+
+            #[cfg(feature = "watch")]
+            ui.checkbox(&mut self.build.watch, "watch: rebuild when the input changes (Cancel stops)");
+
             ui.add_space(8.0);
 
             ui.horizontal(|ui| {
@@ -59,34 +96,61 @@ impl NePakApp {
                 if ui.add_enabled(can_run, egui::Button::new("Build")).clicked() {
                     let args = self.build.to_args();
                     match args {
-                        Ok((input, output, prefix, excludes, compress, level)) => {
+                        Ok((opts, output)) => {
+                            let inputs: Vec<String> = opts
+                                .inputs
+                                .iter()
+                                .map(|root| {
+                                    let mount = if root.mount.is_empty() {
+                                        "<none>"
+                                    } else {
+                                        &root.mount
+                                    };
+                                    format!("'{}' at '{mount}'", root.dir.display())
+                                })
+                                .collect();
                             self.push_log(format!(
-                                "Build: input='{}' output='{}' prefix='{}' compress={} level={}",
-                                input.display(),
+                                "Build: inputs={} output='{}' compress={} level={}",
+                                inputs.join(", "),
                                 output.display(),
-                                if prefix.is_empty() { "<none>" } else { &prefix },
-                                compress,
-                                level
+                                opts.compress,
+                                opts.zstd_level
                             ));
 
-                            self.start_job(move |tx| {
+                            #[cfg(feature = "watch")]
+                            if self.build.watch {
+                                self.start_job(move |tx, cancel| {
+                                    let res = pak::watch(
+                                        &opts,
+                                        &output,
+                                        std::time::Duration::from_millis(300),
+                                        &cancel,
+                                        forward_progress(&tx),
+                                        |event| {
+                                            for line in watch_log(&event) {
+                                                let _ = tx.send(JobMsg::Log(line));
+                                            }
+                                        },
+                                    )
+                                    .map_err(|e| e.to_string());
+                                    let _ = tx.send(JobMsg::Done(res));
+                                });
+                                return;
+                            }
+
+                            self.start_job(move |tx, cancel| {
                                 let _ = tx.send(JobMsg::Log("Scanning + building…".into()));
                                 let res = pak::build_with_progress(
-                                    &input,
+                                    &opts,
                                     &output,
-                                    &prefix,
-                                    &excludes,
-                                    compress,
-                                    level,
-                                    |p| {
-                                        let _ = tx.send(JobMsg::Progress {
-                                            stage: p.stage.as_str().to_string(),
-                                            done: p.done,
-                                            total: p.total,
-                                            item: p.current,
-                                        });
-                                    },
+                                    &cancel,
+                                    forward_progress(&tx),
                                 )
+                                .map(|summary| {
+                                    for issue in summary.portability {
+                                        let _ = tx.send(JobMsg::Log(format!("warning: {issue}")));
+                                    }
+                                })
                                 .map_err(|e| e.to_string());
                                 let _ = tx.send(JobMsg::Done(res));
                             });
@@ -98,29 +162,48 @@ impl NePakApp {
                     }
                 }
             });
+        });
+    }
+}
 
-            if self.busy {
-                ui.add_space(10.0);
-                let total = self.progress_total.max(1);
-                let frac = (self.progress_done.min(total) as f32) / (total as f32);
-
-                let mut label = if self.progress_stage.is_empty() {
-                    "Working…".to_string()
-                } else {
-                    self.progress_stage.clone()
-                };
-                if !self.progress_item.is_empty() {
-                    // Keep the UI stable and readable.
-                    let item = if self.progress_item.len() > 64 {
-                        format!("{}…", &self.progress_item[..64])
-                    } else {
-                        self.progress_item.clone()
-                    };
-                    label.push_str(&format!("  |  {item}"));
-                }
+/// Log lines for one watch event; long change lists are cut short.
+#[cfg(all(feature = "gui", feature = "watch"))]
+fn watch_log(event: &pak::WatchEvent) -> Vec<String> {
+    const MAX_CHANGES: usize = 20;
 
-                ui.add(egui::ProgressBar::new(frac).show_percentage().text(label));
+    match event {
+        pak::WatchEvent::Rebuilding { changed } if changed.is_empty() => {
+            vec!["Scanning + building…".into()]
+        }
+        pak::WatchEvent::Rebuilding { changed } => {
+            vec![format!("{} change(s) detected, rebuilding…", changed.len())]
+        }
+        pak::WatchEvent::Built { summary, elapsed } => {
+            let mut lines: Vec<String> = summary
+                .portability
+                .iter()
+                .map(|issue| format!("warning: {issue}"))
+                .collect();
+            lines.push(format!(
+                "Built {} entries in {} ms ({} reused)",
+                summary.entries,
+                elapsed.as_millis(),
+                summary.reused
+            ));
+            if let Some(diff) = &summary.changes {
+                for c in diff.changes.iter().take(MAX_CHANGES) {
+                    lines.push(format!("  {:?}: {}", c.kind, c.path));
+                }
+                if diff.changes.len() > MAX_CHANGES {
+                    lines.push(format!("  … {} more", diff.changes.len() - MAX_CHANGES));
+                }
             }
-        });
+            lines.push("Watching for changes…".into());
+            lines
+        }
+        pak::WatchEvent::Failed { error, elapsed } => vec![
+            format!("Error after {} ms: {error}", elapsed.as_millis()),
+            "Watching for changes…".into(),
+        ],
     }
-}
\ No newline at end of file
+}
@@ -7,7 +7,10 @@ use eframe::egui;
 use std::path::PathBuf;
 
 #[cfg(feature = "gui")]
-use crate::gui::{app::NePakApp, jobs::JobMsg};
+use crate::gui::{
+    app::NePakApp,
+    jobs::{forward_progress, JobMsg},
+};
 
 #[cfg(feature = "gui")]
 use crate::pak;
@@ -54,13 +57,20 @@ impl NePakApp {
                     .map(|x| x.to_string())
                     .collect();
 
-                self.start_job(move |tx| {
+                self.start_job(move |tx, cancel| {
                     let _ = tx.send(JobMsg::Log(format!(
                         "Extract: pak='{}' -> '{}'",
                         pak_path.display(),
                         out_dir.display()
                     )));
-                    let res = pak::extract(&pak_path, &out_dir, &filters).map_err(|e| e.to_string());
+                    let res = pak::extract_with_progress(
+                        &pak_path,
+                        &out_dir,
+                        &filters,
+                        &cancel,
+                        forward_progress(&tx),
+                    )
+                    .map_err(|e| e.to_string());
                     let _ = tx.send(JobMsg::Done(res));
                 });
             }
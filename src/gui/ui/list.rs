@@ -27,7 +27,7 @@ impl NePakApp {
                 }
                 if ui.add_enabled(!self.busy, egui::Button::new("Load")).clicked() {
                     let pak_path = PathBuf::from(self.list_pak.trim());
-                    self.start_job(move |tx| {
+                    self.start_job(move |tx, _| {
                         let res = pak::entries(&pak_path).map_err(|e| e.to_string());
                         let _ = tx.send(JobMsg::ListDone(res));
                     });
@@ -11,4 +11,6 @@ pub mod extract;
 #[cfg(feature = "gui")]
 pub mod verify;
 #[cfg(feature = "gui")]
-pub mod logs;
\ No newline at end of file
+pub mod logs;
+#[cfg(feature = "gui")]
+pub mod progress;
\ No newline at end of file
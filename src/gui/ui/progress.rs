@@ -0,0 +1,66 @@
+#![forbid(unsafe_code)]
+
+#[cfg(feature = "gui")]
+use eframe::egui;
+
+#[cfg(feature = "gui")]
+use crate::gui::app::NePakApp;
+
+#[cfg(feature = "gui")]
+fn human_bytes(n: f64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut v = n;
+    let mut unit = 0;
+    while v >= 1024.0 && unit + 1 < UNITS.len() {
+        v /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{v:.0} {}", UNITS[unit])
+    } else {
+        format!("{v:.1} {}", UNITS[unit])
+    }
+}
+
+#[cfg(feature = "gui")]
+impl NePakApp {
+    /// Progress bar of the running job, shown below whichever tab is open.
+    pub fn ui_progress(&mut self, ui: &mut egui::Ui) {
+        if !self.busy {
+            return;
+        }
+        ui.add_space(10.0);
+
+        let Some(p) = &self.progress else {
+            ui.add(egui::ProgressBar::new(0.0).text("Starting…"));
+            return;
+        };
+
+        // Bytes track long entries better than entry counts; fall back when unknown.
+        let frac = if p.bytes_total > 0 {
+            p.bytes_done.min(p.bytes_total) as f32 / p.bytes_total as f32
+        } else {
+            p.done.min(p.total) as f32 / p.total.max(1) as f32
+        };
+
+        let mut label = format!("{}  {}/{}", p.stage.as_str(), p.done, p.total);
+        if p.bytes_total > 0 {
+            label.push_str(&format!(
+                "  |  {} / {}  @ {}/s",
+                human_bytes(p.bytes_done as f64),
+                human_bytes(p.bytes_total as f64),
+                human_bytes(p.bytes_per_sec)
+            ));
+        }
+        if let Some(item) = &p.current {
+            // Keep the UI stable and readable.
+            let item = match item.char_indices().nth(64) {
+                Some((cut, _)) => format!("{}…", &item[..cut]),
+                None => item.clone(),
+            };
+            label.push_str(&format!("  |  {item}"));
+        }
+
+        ui.add(egui::ProgressBar::new(frac).show_percentage().text(label));
+    }
+}
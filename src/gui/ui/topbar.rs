@@ -47,6 +47,13 @@ impl NePakApp {
             if self.busy {
                 ui.label("Running…");
                 ui.add(egui::Spinner::new());
+                let cancelling = self.cancel.as_ref().is_some_and(|c| c.is_cancelled());
+                if ui
+                    .add_enabled(!cancelling, egui::Button::new("Cancel"))
+                    .clicked()
+                {
+                    self.cancel_job();
+                }
             }
         });
         ui.separator();
@@ -7,7 +7,10 @@ use eframe::egui;
 use std::path::PathBuf;
 
 #[cfg(feature = "gui")]
-use crate::gui::{app::NePakApp, jobs::JobMsg};
+use crate::gui::{
+    app::NePakApp,
+    jobs::{forward_progress, JobMsg},
+};
 
 #[cfg(feature = "gui")]
 use crate::pak;
@@ -27,8 +30,9 @@ impl NePakApp {
                 }
                 if ui.add_enabled(!self.busy, egui::Button::new("Verify")).clicked() {
                     let pak_path = PathBuf::from(self.verify_pak.trim());
-                    self.start_job(move |tx| {
-                        let res = pak::verify(&pak_path).map_err(|e| e.to_string());
+                    self.start_job(move |tx, cancel| {
+                        let res = pak::verify_with_progress(&pak_path, &cancel, forward_progress(&tx))
+                            .map_err(|e| e.to_string());
                         let _ = tx.send(JobMsg::VerifyDone(res));
                     });
                 }
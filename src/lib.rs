@@ -0,0 +1,5 @@
+#![forbid(unsafe_code)]
+
+//! NEPAK v1 container library used by the `nepak` CLI and GUI.
+
+pub mod pak;
@@ -1,12 +1,20 @@
 #![forbid(unsafe_code)]
 
-mod pak;
+mod output;
 mod ui;
 #[cfg(feature = "gui")]
 mod gui;
 
-use clap::{Parser, Subcommand};
-use std::path::PathBuf;
+use clap::builder::PossibleValuesParser;
+use clap::{Args, Parser, Subcommand};
+use nepak::pak;
+use output::{Format, ListFormat, ListSort, ProgressMode, TableFormat};
+#[cfg(feature = "watch")]
+use std::cell::RefCell;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "watch")]
+use std::time::Duration;
 
 #[derive(Debug, Parser)]
 #[command(name = "nepak", version, about = "NewEngine PakBuilder (NEPAK v1)")]
@@ -15,6 +23,27 @@ struct Cli {
     cmd: Command,
 }
 
+/// Progress options shared by long-running commands.
+#[derive(Debug, Args)]
+struct ProgressArgs {
+    /// Progress output on stderr: auto (bar on a terminal), ndjson or none.
+    #[arg(long, value_enum, default_value_t = ProgressMode::Auto)]
+    progress: ProgressMode,
+    /// No progress output and no success summary; warnings and errors are still printed.
+    #[arg(long, short, conflicts_with = "progress")]
+    quiet: bool,
+}
+
+impl ProgressArgs {
+    fn view(&self) -> output::ProgressView {
+        output::ProgressView::new(if self.quiet {
+            ProgressMode::None
+        } else {
+            self.progress
+        })
+    }
+}
+
 #[derive(Debug, Subcommand)]
 enum Command {
     /// Interactive wizard for building a pak (terminal).
@@ -24,17 +53,23 @@ enum Command {
     #[cfg(feature = "gui")]
     Gui,
 
-    /// Build a .pak from an input directory.
+    /// Build a .pak from one or more input directories.
     Build {
-        /// Input directory.
-        #[arg(long)]
-        input: PathBuf,
+        /// Input directory as `dir[:mount]` (repeatable; `::` is a literal colon). Roots are merged into one index.
+        #[arg(long, required = true)]
+        input: Vec<String>,
         /// Output pak file.
-        #[arg(long)]
-        output: PathBuf,
-        /// Optional mount prefix inside pak (e.g. "assets/").
+        #[arg(long, required_unless_present = "check", conflicts_with = "check")]
+        output: Option<PathBuf>,
+        /// Build in memory and fail unless the result is byte-identical to this pak.
+        #[arg(long, value_name = "PAK")]
+        check: Option<PathBuf>,
+        /// Default mount prefix inside pak for inputs without `:mount` (e.g. "assets/").
         #[arg(long, default_value = "")]
         prefix: String,
+        /// Let later --input roots override earlier ones on path collisions (default: error).
+        #[arg(long, default_value_t = false)]
+        allow_override: bool,
         /// Exclude glob-like substring (repeatable). Simple contains() filter on normalized paths.
         #[arg(long)]
         exclude: Vec<String>,
@@ -44,15 +79,66 @@ enum Command {
         /// Zstd level (1..=22). Only used with --compress.
         #[arg(long, default_value_t = 6)]
         zstd_level: i32,
+        /// How to handle paths that break on other platforms: warn|error.
+        #[arg(long, default_value = "warn")]
+        portability: pak::PortabilityMode,
+        /// Normalize logical paths to Unicode NFC.
+        #[arg(long, default_value_t = false)]
+        nfc: bool,
+        /// Front-code index paths (share prefixes with the previous path).
+        #[arg(long, default_value_t = false)]
+        front_code_index: bool,
+        /// zstd-compress the index block (requires feature "zstd").
+        #[arg(long, default_value_t = false)]
+        compress_index: bool,
+        /// JSON file of transform rules (glob + builtin or command) applied before packing.
+        #[arg(long, value_name = "FILE")]
+        transforms: Option<PathBuf>,
+        /// Directory caching transform outputs by input hash. Only used with --transforms.
+        #[arg(long, value_name = "DIR", requires = "transforms")]
+        transform_cache: Option<PathBuf>,
+        /// Write a Makefile-style depfile (for Make and Ninja) listing every source file and input directory.
+        #[arg(long, value_name = "FILE", conflicts_with = "check")]
+        depfile: Option<PathBuf>,
+        /// Leave --output untouched (keeping its mtime) when the rebuilt pak is byte-identical.
+        #[arg(long, default_value_t = false, conflicts_with = "check")]
+        stamp: bool,
+        /// Keep running and rebuild incrementally whenever an input changes (requires feature "watch").
+        #[arg(long, default_value_t = false, conflicts_with = "check")]
+        watch: bool,
+        /// Quiet period before a rebuild starts, in milliseconds. Only used with --watch.
+        #[arg(long, default_value_t = 300, requires = "watch")]
+        debounce_ms: u64,
+        #[command(flatten)]
+        progress: ProgressArgs,
     },
 
     /// List entries in a pak.
     List {
         #[arg(long)]
         pak: PathBuf,
-        /// Print hashes too.
+        /// Print all fields in plain output.
         #[arg(long, default_value_t = false)]
         verbose: bool,
+        /// Output format.
+        #[arg(long, value_enum, default_value_t = ListFormat::Plain)]
+        format: ListFormat,
+        /// Comma-separated fields to print (names as in the JSON output).
+        #[arg(
+            long,
+            value_delimiter = ',',
+            value_parser = PossibleValuesParser::new(pak::EntryInfo::FIELDS)
+        )]
+        fields: Vec<String>,
+        /// Sort order.
+        #[arg(long, value_enum, default_value_t = ListSort::Path)]
+        sort: ListSort,
+        /// Reverse the sort order.
+        #[arg(long, default_value_t = false)]
+        reverse: bool,
+        /// Only list paths matching this glob (repeatable), e.g. "textures/**/*.png".
+        #[arg(long)]
+        glob: Vec<String>,
     },
 
     /// Extract pak to an output directory.
@@ -64,15 +150,171 @@ enum Command {
         /// Only extract entries that contain this substring (repeatable).
         #[arg(long)]
         filter: Vec<String>,
+        #[command(flatten)]
+        progress: ProgressArgs,
     },
 
-    /// Verify pak integrity (hashes, bounds).
+    /// Verify pak integrity (hashes, bounds, layout). Reports every problem found.
     Verify {
         #[arg(long)]
         pak: PathBuf,
+        /// Output format.
+        #[arg(long, value_enum, default_value_t = Format::Table)]
+        format: Format,
+        #[command(flatten)]
+        progress: ProgressArgs,
+    },
+
+    /// Write one entry's bytes to stdout.
+    Cat {
+        /// Pak to read from.
+        pak: PathBuf,
+        /// Entry path inside the pak.
+        path: String,
+        /// Dump the stored payload as-is (no decompression, no hash check).
+        #[arg(long, default_value_t = false)]
+        raw_payload: bool,
+    },
+
+    /// Header, footer and layout summary.
+    Info {
+        /// Pak to inspect.
+        pak: PathBuf,
+        /// Output format.
+        #[arg(long, value_enum, default_value_t = Format::Table)]
+        format: Format,
+    },
+
+    /// Annotated binary layout dump (header, payloads, index fields, footer).
+    Explain {
+        /// Pak to inspect.
+        pak: PathBuf,
+        /// Only show fields overlapping this byte range, e.g. "0..4096" or "0x100..0x200".
+        #[arg(long, value_parser = output::parse_range)]
+        range: Option<(u64, u64)>,
+        /// Only show the payload and index record of this entry (repeatable).
+        #[arg(long)]
+        entry: Vec<String>,
+    },
+
+    /// Compare two paks entry by entry (index only).
+    Diff {
+        /// Old pak.
+        old: PathBuf,
+        /// New pak.
+        new: PathBuf,
+        /// Output format.
+        #[arg(long, value_enum, default_value_t = Format::Table)]
+        format: Format,
+    },
+
+    /// Size breakdown by extension and directory, largest entries, duplicates.
+    Stats {
+        /// Pak to inspect.
+        pak: PathBuf,
+        /// Directory components used for the directory breakdown.
+        #[arg(long, default_value_t = 1)]
+        depth: usize,
+        /// Number of entries in the largest / worst-compressed lists.
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+        /// Print a du-style directory tree instead of the summary tables.
+        #[arg(long, default_value_t = false)]
+        tree: bool,
+        /// Output format.
+        #[arg(long, value_enum, default_value_t = TableFormat::Table)]
+        format: TableFormat,
+    },
+
+    /// Recover entries from a pak with a damaged or truncated index/footer.
+    Salvage {
+        #[arg(long)]
+        pak: PathBuf,
+        /// Directory to write recovered entries to.
+        #[arg(long)]
+        output: PathBuf,
+        /// Also write the report as JSON to this file.
+        #[arg(long)]
+        report: Option<PathBuf>,
+        /// Rebuild a clean pak from the recovered entries.
+        #[arg(long)]
+        rebuild: Option<PathBuf>,
     },
 }
 
+/// Exit code for `cat` when the requested entry does not exist.
+const EXIT_NOT_FOUND: i32 = 3;
+/// Conventional exit code for "terminated by SIGINT".
+const EXIT_INTERRUPTED: i32 = 130;
+
+/// Token that is cancelled by the first Ctrl-C. A second Ctrl-C exits immediately.
+fn ctrl_c_token() -> pak::CancelToken {
+    let token = pak::CancelToken::new();
+    let handler = token.clone();
+    let installed = ctrlc::set_handler(move || {
+        if handler.is_cancelled() {
+            std::process::exit(EXIT_INTERRUPTED);
+        }
+        eprintln!("\ncancelling… (press Ctrl-C again to abort)");
+        handler.cancel();
+    });
+    if let Err(e) = installed {
+        eprintln!("warning: cannot install Ctrl-C handler: {e}");
+    }
+    token
+}
+
+/// `build --watch`: rebuild until Ctrl-C, printing a report per rebuild.
+#[cfg(feature = "watch")]
+fn watch_build(
+    opts: &pak::BuildOptions,
+    output: &Path,
+    depfile: Option<&Path>,
+    debounce_ms: u64,
+    progress: &ProgressArgs,
+) -> pak::PakResult<()> {
+    // The bar is cleared before each report, so both callbacks need the view.
+    let view = RefCell::new(progress.view());
+    pak::watch(
+        opts,
+        output,
+        Duration::from_millis(debounce_ms),
+        &ctrl_c_token(),
+        |p| view.borrow_mut().update(p),
+        |event| {
+            view.borrow_mut().clear();
+            if let (Some(depfile), pak::WatchEvent::Built { summary, .. }) = (depfile, &event) {
+                if let Err(e) = pak::write_depfile(depfile, output, summary) {
+                    eprintln!("error: {e}");
+                }
+            }
+            output::print_watch_event(&event, output);
+        },
+    )
+}
+
+#[cfg(not(feature = "watch"))]
+fn watch_build(
+    _opts: &pak::BuildOptions,
+    _output: &Path,
+    _depfile: Option<&Path>,
+    _debounce_ms: u64,
+    _progress: &ProgressArgs,
+) -> pak::PakResult<()> {
+    Err(pak::PakError::NoWatch)
+}
+
+/// Rules from `build --transforms`. A bad rules file exits like a failed command.
+fn transform_rules(file: Option<&Path>) -> Vec<pak::TransformRule> {
+    match file.map(pak::load_transforms).transpose() {
+        Ok(rules) => rules.unwrap_or_default(),
+        Err(e) => {
+            eprintln!("error: {e}");
+            std::process::exit(1);
+        }
+    }
+}
+
 fn main() {
     let cli = Cli::parse();
 
@@ -83,18 +325,198 @@ fn main() {
         Command::Build {
             input,
             output,
+            check,
             prefix,
+            allow_override,
             exclude,
             compress,
             zstd_level,
-        } => pak::build(&input, &output, &prefix, &exclude, compress, zstd_level),
-        Command::List { pak, verbose } => pak::list(&pak, verbose),
-        Command::Extract { pak, output, filter } => pak::extract(&pak, &output, &filter),
-        Command::Verify { pak } => pak::verify(&pak),
+            portability,
+            nfc,
+            front_code_index,
+            compress_index,
+            transforms,
+            transform_cache,
+            depfile,
+            stamp,
+            watch,
+            debounce_ms,
+            progress,
+        } => {
+            let opts = pak::BuildOptions {
+                inputs: input
+                    .iter()
+                    .map(|spec| pak::InputRoot::parse(spec, &prefix))
+                    .collect(),
+                excludes: exclude,
+                compress,
+                zstd_level,
+                codec: None,
+                allow_override,
+                portability,
+                nfc,
+                front_code_index,
+                compress_index,
+                stamp,
+                transforms: transform_rules(transforms.as_deref()),
+                transform_cache,
+            };
+            if let Some(existing) = check {
+                pak::check(&opts, &existing).and_then(|report| {
+                    output::print_check(&report, &existing);
+                    if report.is_identical() {
+                        Ok(())
+                    } else {
+                        Err(pak::PakError::Invalid(format!(
+                            "build is not reproducible: differs from {}",
+                            existing.display()
+                        )))
+                    }
+                })
+            } else {
+                let output = output.expect("clap requires --output without --check");
+                if watch {
+                    watch_build(&opts, &output, depfile.as_deref(), debounce_ms, &progress)
+                } else {
+                    let mut view = progress.view();
+                    let res = pak::build_with_progress(&opts, &output, &ctrl_c_token(), |p| {
+                        view.update(p)
+                    });
+                    drop(view);
+                    res.and_then(|summary| {
+                        for issue in &summary.portability {
+                            eprintln!("warning: {issue}");
+                        }
+                        match &depfile {
+                            Some(depfile) => pak::write_depfile(depfile, &output, &summary),
+                            None => Ok(()),
+                        }
+                    })
+                }
+            }
+        }
+        Command::List {
+            pak,
+            verbose,
+            format,
+            fields,
+            sort,
+            reverse,
+            glob,
+        } => pak::entries(&pak).and_then(|entries| {
+            let entries = output::select_entries(entries, &glob, sort, reverse)?;
+            output::print_entries(&entries, format, &fields, verbose)
+        }),
+        Command::Extract {
+            pak,
+            output,
+            filter,
+            progress,
+        } => {
+            let mut view = progress.view();
+            pak::extract_with_progress(&pak, &output, &filter, &ctrl_c_token(), |p| {
+                view.update(p)
+            })
+        }
+        Command::Verify {
+            pak,
+            format,
+            progress,
+        } => {
+            let mut view = progress.view();
+            let res = pak::verify_with_progress(&pak, &ctrl_c_token(), |p| view.update(p));
+            drop(view);
+            res.and_then(|report| {
+                if !(progress.quiet && report.is_ok() && format == Format::Table) {
+                    output::print_verify(&report, format)?;
+                }
+                if report.is_ok() {
+                    Ok(())
+                } else {
+                    Err(pak::PakError::Invalid(format!(
+                        "{} problem(s) found",
+                        report.issues.len()
+                    )))
+                }
+            })
+        }
+        Command::Cat {
+            pak,
+            path,
+            raw_payload,
+        } => {
+            let bytes = if raw_payload {
+                pak::entry_payload(&pak, &path)
+            } else {
+                pak::entry_bytes(&pak, &path)
+            };
+            match bytes {
+                Ok(bytes) => std::io::stdout()
+                    .lock()
+                    .write_all(&bytes)
+                    .map_err(pak::PakError::Io),
+                Err(pak::PakError::NotFound(_)) => {
+                    eprintln!("error: no entry '{path}' in {}", pak.display());
+                    let near = pak::entries(&pak)
+                        .map(|entries| output::closest_paths(&entries, &path, 5))
+                        .unwrap_or_default();
+                    if !near.is_empty() {
+                        eprintln!("closest matches:");
+                        for p in near {
+                            eprintln!("  {p}");
+                        }
+                    }
+                    std::process::exit(EXIT_NOT_FOUND);
+                }
+                Err(e) => Err(e),
+            }
+        }
+        Command::Info { pak, format } => {
+            pak::info(&pak).and_then(|info| output::print_info(&info, format))
+        }
+        Command::Explain { pak, range, entry } => {
+            pak::explain(&pak).map(|fields| output::print_layout(&fields, range, &entry))
+        }
+        Command::Diff { old, new, format } => {
+            pak::diff(&old, &new).and_then(|d| output::print_diff(&d, format))
+        }
+        Command::Stats {
+            pak,
+            depth,
+            top,
+            tree,
+            format,
+        } => {
+            let opts = pak::StatsOptions { depth, top, tree };
+            pak::stats(&pak, &opts).and_then(|st| output::print_stats(&st, tree, format))
+        }
+        Command::Salvage {
+            pak,
+            output,
+            report,
+            rebuild,
+        } => match &rebuild {
+            Some(rebuilt) => pak::salvage_rebuild(&pak, &output, rebuilt),
+            None => pak::salvage(&pak, &output),
+        }
+        .and_then(|r| {
+            output::print_salvage(&r, rebuild.as_deref());
+            if let Some(path) = report {
+                output::write_json(&path, &r)?;
+            }
+            Ok(())
+        }),
     };
 
-    if let Err(e) = res {
-        eprintln!("error: {e}");
-        std::process::exit(1);
+    match res {
+        Ok(()) => {}
+        Err(pak::PakError::Cancelled) => {
+            eprintln!("cancelled");
+            std::process::exit(EXIT_INTERRUPTED);
+        }
+        Err(e) => {
+            eprintln!("error: {e}");
+            std::process::exit(1);
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,730 @@
+#![forbid(unsafe_code)]
+
+//! CLI rendering of library reports. The `pak` module only returns data.
+
+use clap::ValueEnum;
+use globset::{GlobBuilder, GlobSetBuilder};
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::Serialize;
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+
+use crate::pak;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Format {
+    /// Human-readable table.
+    #[default]
+    Table,
+    /// Pretty-printed JSON on stdout.
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ProgressMode {
+    /// Progress bar on stderr when it is a terminal, nothing otherwise.
+    #[default]
+    Auto,
+    /// One JSON object per stage change and per entry on stderr.
+    Ndjson,
+    /// No progress output.
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum TableFormat {
+    /// Human-readable table.
+    #[default]
+    Table,
+    /// Pretty-printed JSON on stdout.
+    Json,
+    /// Comma-separated values with a header row.
+    Csv,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ListFormat {
+    /// One path per line (all fields with --verbose).
+    #[default]
+    Plain,
+    /// A JSON array of entry objects.
+    Json,
+    /// Comma-separated values with a header row.
+    Csv,
+    /// One JSON object per line.
+    Ndjson,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ListSort {
+    /// Path bytes (index order).
+    #[default]
+    Path,
+    /// Raw (uncompressed) size.
+    Size,
+    /// Stored payload size.
+    Payload,
+    /// Payload offset in the file.
+    Offset,
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn print_json(v: &impl Serialize) -> pak::PakResult<()> {
+    let s = serde_json::to_string_pretty(v).map_err(std::io::Error::other)?;
+    println!("{s}");
+    Ok(())
+}
+
+pub fn write_json(path: &Path, v: &impl Serialize) -> pak::PakResult<()> {
+    let s = serde_json::to_string_pretty(v).map_err(std::io::Error::other)?;
+    std::fs::write(path, s)?;
+    Ok(())
+}
+
+pub fn print_verify(report: &pak::VerifyReport, format: Format) -> pak::PakResult<()> {
+    match format {
+        Format::Json => print_json(report)?,
+        Format::Table => {
+            if !report.issues.is_empty() {
+                println!("{:<15} {:>12} {:>12}  PATH", "KIND", "OFFSET", "LEN");
+                for i in &report.issues {
+                    let mut path = i.path.clone().unwrap_or_else(|| "-".into());
+                    if !i.detail.is_empty() {
+                        path.push_str(&format!("  ({})", i.detail));
+                    }
+                    println!(
+                        "{:<15} {:>12} {:>12}  {}",
+                        i.kind.as_str(),
+                        i.offset,
+                        i.len,
+                        path
+                    );
+                }
+                println!();
+            }
+            if report.is_ok() {
+                println!("ok: {} entries", report.entries);
+            } else {
+                println!(
+                    "{} problem(s) in {} entries",
+                    report.issues.len(),
+                    report.entries
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn signed(v: i64) -> String {
+    if v > 0 {
+        format!("+{v}")
+    } else {
+        v.to_string()
+    }
+}
+
+pub fn print_check(report: &pak::CheckReport, existing: &Path) {
+    let Some(first) = &report.first_difference else {
+        println!(
+            "identical: {} ({} bytes)",
+            existing.display(),
+            report.existing_len
+        );
+        return;
+    };
+
+    let location = match (&first.field, &first.path) {
+        (Some(field), Some(path)) => format!("{field} of {path}"),
+        (Some(field), None) => field.clone(),
+        (None, _) => "end of the shorter file".to_string(),
+    };
+    println!("first difference at {:#x}: {location}", first.offset);
+    println!(
+        "size: {} (existing) vs {} (rebuilt)",
+        report.existing_len, report.rebuilt_len
+    );
+    if !report.changes.is_empty() {
+        println!();
+        let _ = print_diff(&report.changes, Format::Table);
+    }
+}
+
+/// One line (plus the changed entries) per `build --watch` event.
+#[cfg(feature = "watch")]
+pub fn print_watch_event(event: &pak::WatchEvent, output: &Path) {
+    match event {
+        pak::WatchEvent::Rebuilding { changed } if changed.is_empty() => {
+            println!("building {}", output.display());
+        }
+        pak::WatchEvent::Rebuilding { changed } => {
+            println!("{} change(s) detected, rebuilding", changed.len());
+        }
+        pak::WatchEvent::Built { summary, elapsed } => {
+            for issue in &summary.portability {
+                eprintln!("warning: {issue}");
+            }
+            println!(
+                "built {} entries in {} ms ({} reused{})",
+                summary.entries,
+                elapsed.as_millis(),
+                summary.reused,
+                if summary.unchanged {
+                    ", output unchanged"
+                } else {
+                    ""
+                }
+            );
+            match &summary.changes {
+                Some(changes) if changes.is_empty() => println!("no entries changed"),
+                Some(changes) => {
+                    let _ = print_diff(changes, Format::Table);
+                }
+                None => {}
+            }
+            println!("watching for changes (Ctrl-C to stop)");
+        }
+        pak::WatchEvent::Failed { error, elapsed } => {
+            eprintln!("error: {error} (after {} ms)", elapsed.as_millis());
+            println!("watching for changes (Ctrl-C to stop)");
+        }
+    }
+}
+
+pub fn print_diff(diff: &pak::PakDiff, format: Format) -> pak::PakResult<()> {
+    if format == Format::Json {
+        return print_json(diff);
+    }
+
+    if !diff.changes.is_empty() {
+        println!("{:<2} {:>12} {:>12}  PATH", "", "RAW", "PAYLOAD");
+    }
+    for c in &diff.changes {
+        let tag = match c.kind {
+            pak::DiffKind::Added => "A",
+            pak::DiffKind::Removed => "D",
+            pak::DiffKind::Modified => "M",
+            pak::DiffKind::Renamed => "R",
+        };
+        let path = match &c.old_path {
+            Some(old) => format!("{old} -> {}", c.path),
+            None => c.path.clone(),
+        };
+        println!(
+            "{:<2} {:>12} {:>12}  {}",
+            tag,
+            signed(c.raw_delta),
+            signed(c.payload_delta),
+            path
+        );
+    }
+
+    let t = &diff.totals;
+    println!();
+    println!(
+        "entries: {} -> {} ({} changed, {} unchanged)",
+        t.old_entries,
+        t.new_entries,
+        diff.changes.len(),
+        t.unchanged
+    );
+    println!(
+        "raw:     {} -> {} ({})",
+        t.old_raw,
+        t.new_raw,
+        signed(t.raw_delta)
+    );
+    println!(
+        "payload: {} -> {} ({})",
+        t.old_payload,
+        t.new_payload,
+        signed(t.payload_delta)
+    );
+    Ok(())
+}
+
+fn print_groups(title: &str, groups: &[pak::GroupStats], empty_key: &str) {
+    println!("{title}");
+    println!(
+        "  {:<40} {:>8} {:>14} {:>14} {:>7}",
+        "KEY", "ENTRIES", "RAW", "PAYLOAD", "RATIO"
+    );
+    for g in groups {
+        let key = if g.key.is_empty() { empty_key } else { &g.key };
+        println!(
+            "  {:<40} {:>8} {:>14} {:>14} {:>7.3}",
+            key, g.entries, g.raw, g.payload, g.ratio
+        );
+    }
+    println!();
+}
+
+fn print_entry_stats(title: &str, list: &[pak::EntryStat]) {
+    println!("{title}");
+    for e in list {
+        println!(
+            "  {:>14} {:>14} {:>7.3}  {}",
+            e.raw, e.payload, e.ratio, e.path
+        );
+    }
+    println!();
+}
+
+pub fn print_stats(st: &pak::PakStats, tree: bool, format: TableFormat) -> pak::PakResult<()> {
+    match format {
+        TableFormat::Json => print_json(st)?,
+        TableFormat::Csv => {
+            println!("section,key,entries,raw,payload,ratio,group");
+            let group_rows = |section: &str, groups: &[pak::GroupStats]| {
+                for g in groups {
+                    println!(
+                        "{section},{},{},{},{},{:.6},",
+                        csv_field(&g.key),
+                        g.entries,
+                        g.raw,
+                        g.payload,
+                        g.ratio
+                    );
+                }
+            };
+            group_rows("total", std::slice::from_ref(&st.total));
+            group_rows("extension", &st.by_extension);
+            group_rows("directory", &st.by_directory);
+            group_rows("tree", &st.tree);
+            for (section, list) in [("largest", &st.largest), ("worst", &st.worst_compressed)] {
+                for e in list {
+                    println!(
+                        "{section},{},1,{},{},{:.6},",
+                        csv_field(&e.path),
+                        e.raw,
+                        e.payload,
+                        e.ratio
+                    );
+                }
+            }
+            for d in &st.duplicates {
+                for p in &d.paths {
+                    println!(
+                        "duplicate,{},1,{},,,{}",
+                        csv_field(p),
+                        d.raw,
+                        d.raw_hash_hex
+                    );
+                }
+            }
+        }
+        TableFormat::Table if tree => {
+            for g in &st.tree {
+                let depth = if g.key.is_empty() {
+                    0
+                } else {
+                    g.key.split('/').count()
+                };
+                let name = g.key.rsplit('/').next().filter(|n| !n.is_empty());
+                println!(
+                    "{:>14} {:>14}  {}{}",
+                    g.payload,
+                    g.raw,
+                    "  ".repeat(depth),
+                    name.unwrap_or(".")
+                );
+            }
+        }
+        TableFormat::Table => {
+            let t = &st.total;
+            println!(
+                "{} entries, raw {} bytes, payload {} bytes, ratio {:.3}\n",
+                t.entries, t.raw, t.payload, t.ratio
+            );
+            print_groups("By extension", &st.by_extension, "<none>");
+            print_groups("By directory", &st.by_directory, "<root>");
+            print_entry_stats("Largest entries (raw, payload, ratio)", &st.largest);
+            print_entry_stats(
+                "Worst compression (raw, payload, ratio)",
+                &st.worst_compressed,
+            );
+            println!("Duplicate content");
+            if st.duplicates.is_empty() {
+                println!("  none");
+            }
+            for d in &st.duplicates {
+                println!(
+                    "  {} x{} ({} raw bytes each, {} payload bytes wasted)",
+                    &d.raw_hash_hex[..16],
+                    d.paths.len(),
+                    d.raw,
+                    d.wasted
+                );
+                for p in &d.paths {
+                    println!("    {p}");
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn print_info(info: &pak::PakInfo, format: Format) -> pak::PakResult<()> {
+    if format == Format::Json {
+        return print_json(info);
+    }
+
+    let f = &info.footer;
+    println!("format        : NEPAK v{}", info.format_version);
+    println!("file size     : {}", info.file_len);
+    println!("index offset  : {}", f.index_offset);
+    println!("index length  : {}", f.index_len);
+    println!("index hash    : {:08x}", f.index_hash);
+    println!("flags         : {:#x}", f.flags);
+    println!("entries       : {}", info.entries);
+    println!("raw bytes     : {}", info.raw_bytes);
+    println!("payload bytes : {}", info.payload_bytes);
+    println!("ratio         : {:.3}", info.ratio);
+    let kinds: Vec<String> = info
+        .payload_kinds
+        .iter()
+        .map(|(k, n)| format!("{k} ({n})"))
+        .collect();
+    println!("payload kinds : {}", kinds.join(", "));
+    for (label, e) in [("smallest", &info.smallest), ("largest", &info.largest)] {
+        if let Some(e) = e {
+            println!(
+                "{label:<14}: {} ({} raw, {} payload)",
+                e.path, e.raw, e.payload
+            );
+        }
+    }
+    println!("slack bytes   : {}", info.slack_bytes);
+    println!("index encoding: {}", info.index_encoding);
+    let saved_pct = if info.index_plain_len == 0 {
+        0.0
+    } else {
+        info.index_saved_bytes as f64 * 100.0 / info.index_plain_len as f64
+    };
+    println!(
+        "index savings : {} bytes vs plain {} ({saved_pct:.1}%)",
+        signed(info.index_saved_bytes),
+        info.index_plain_len
+    );
+    Ok(())
+}
+
+fn parse_offset(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let parsed = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+    parsed.map_err(|e| format!("bad offset '{s}': {e}"))
+}
+
+/// Parse a `START..END` byte range (decimal or 0x-prefixed hex, END exclusive).
+pub fn parse_range(s: &str) -> Result<(u64, u64), String> {
+    let (start, end) = s
+        .split_once("..")
+        .ok_or_else(|| format!("expected START..END, got '{s}'"))?;
+    let start = parse_offset(start)?;
+    let end = if end.is_empty() {
+        u64::MAX
+    } else {
+        parse_offset(end)?
+    };
+    if end < start {
+        return Err(format!("range end {end} is before start {start}"));
+    }
+    Ok((start, end))
+}
+
+pub fn print_layout(fields: &[pak::LayoutField], range: Option<(u64, u64)>, entries: &[String]) {
+    println!("{:<12} {:>10}  {:<28} VALUE", "OFFSET", "LEN", "FIELD");
+    for f in fields {
+        if let Some((start, end)) = range {
+            let f_end = f.offset.saturating_add(f.len.max(1));
+            if f_end <= start || f.offset >= end {
+                continue;
+            }
+        }
+        if !entries.is_empty() && !f.path.as_ref().is_some_and(|p| entries.contains(p)) {
+            continue;
+        }
+
+        let value = match (&f.path, f.name.as_str()) {
+            (Some(path), "payload") => format!("{path} ({})", f.value),
+            _ => f.value.clone(),
+        };
+        println!("{:#012x} {:>10}  {:<28} {}", f.offset, f.len, f.name, value);
+    }
+}
+
+pub fn print_salvage(report: &pak::SalvageReport, rebuilt: Option<&Path>) {
+    match &report.source {
+        pak::SalvageSource::Footer => println!("source: footer (index intact)"),
+        pak::SalvageSource::AlternateFooter { footer_offset } => {
+            println!("source: alternate footer at {footer_offset}")
+        }
+        pak::SalvageSource::IndexScan {
+            index_offset,
+            declared,
+        } => println!("source: index found at {index_offset} ({declared} records declared)"),
+        pak::SalvageSource::Carved => println!("source: carved zstd frames (no index, no names)"),
+    }
+
+    for e in &report.recovered {
+        let tag = if e.verified { "ok" } else { "unverified" };
+        println!("recovered  {:<10} {}", tag, e.path);
+    }
+    for e in &report.lost {
+        println!("lost       {:<10} {}  ({})", "", e.path, e.reason);
+    }
+    println!(
+        "{} recovered, {} lost",
+        report.recovered.len(),
+        report.lost.len()
+    );
+    match rebuilt {
+        Some(path) if report.rebuilt => println!("rebuilt {}", path.display()),
+        Some(path) => println!("no verified entries: {} not written", path.display()),
+        None => {}
+    }
+}
+
+/// Apply `--glob` filters and `--sort` to an index listing.
+pub fn select_entries(
+    mut entries: Vec<pak::EntryInfo>,
+    globs: &[String],
+    sort: ListSort,
+    reverse: bool,
+) -> pak::PakResult<Vec<pak::EntryInfo>> {
+    if !globs.is_empty() {
+        let mut builder = GlobSetBuilder::new();
+        for g in globs {
+            let glob = GlobBuilder::new(g)
+                .literal_separator(true)
+                .build()
+                .map_err(|e| pak::PakError::Invalid(format!("bad glob '{g}': {e}")))?;
+            builder.add(glob);
+        }
+        let set = builder
+            .build()
+            .map_err(|e| pak::PakError::Invalid(e.to_string()))?;
+        entries.retain(|e| set.is_match(&e.path));
+    }
+
+    match sort {
+        ListSort::Path => {}
+        ListSort::Size => entries.sort_by_key(|e| e.raw_len),
+        ListSort::Payload => entries.sort_by_key(|e| e.payload_len),
+        ListSort::Offset => entries.sort_by_key(|e| e.payload_offset),
+    }
+    if reverse {
+        entries.reverse();
+    }
+    Ok(entries)
+}
+
+fn entry_object(e: &pak::EntryInfo, fields: &[String]) -> pak::PakResult<serde_json::Value> {
+    let value = serde_json::to_value(e).map_err(std::io::Error::other)?;
+    if fields.is_empty() {
+        return Ok(value);
+    }
+    let mut obj = serde_json::Map::new();
+    for f in fields {
+        obj.insert(f.clone(), value[f.as_str()].clone());
+    }
+    Ok(serde_json::Value::Object(obj))
+}
+
+pub fn print_entries(
+    entries: &[pak::EntryInfo],
+    format: ListFormat,
+    fields: &[String],
+    verbose: bool,
+) -> pak::PakResult<()> {
+    let all: Vec<String> = pak::EntryInfo::FIELDS
+        .iter()
+        .map(|f| f.to_string())
+        .collect();
+
+    match format {
+        ListFormat::Json => {
+            let list = entries
+                .iter()
+                .map(|e| entry_object(e, fields))
+                .collect::<pak::PakResult<Vec<_>>>()?;
+            print_json(&list)?;
+        }
+        ListFormat::Ndjson => {
+            for e in entries {
+                println!("{}", entry_object(e, fields)?);
+            }
+        }
+        ListFormat::Csv => {
+            let fields = if fields.is_empty() { &all } else { fields };
+            println!("{}", fields.join(","));
+            for e in entries {
+                let row: Vec<String> = fields
+                    .iter()
+                    .map(|f| csv_field(&e.field(f).unwrap_or_default()))
+                    .collect();
+                println!("{}", row.join(","));
+            }
+        }
+        ListFormat::Plain if fields.is_empty() && verbose => {
+            for e in entries {
+                println!(
+                    "{}  off={} len={} raw={} kind={} hash={}",
+                    e.path,
+                    e.payload_offset,
+                    e.payload_len,
+                    e.raw_len,
+                    e.payload_kind,
+                    e.raw_hash_hex
+                );
+            }
+        }
+        ListFormat::Plain => {
+            for e in entries {
+                if fields.is_empty() {
+                    println!("{}", e.path);
+                } else {
+                    let row: Vec<String> = fields
+                        .iter()
+                        .map(|f| e.field(f).unwrap_or_default())
+                        .collect();
+                    println!("{}", row.join("\t"));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let cur = row[j + 1];
+            row[j + 1] = if ca == cb {
+                prev
+            } else {
+                1 + prev.min(cur).min(row[j])
+            };
+            prev = cur;
+        }
+    }
+    row[b.len()]
+}
+
+/// Up to `n` entry paths that look like `wanted` (same file name first, then edit distance).
+pub fn closest_paths(entries: &[pak::EntryInfo], wanted: &str, n: usize) -> Vec<String> {
+    let file_name = |p: &str| p.rsplit('/').next().unwrap_or(p).to_ascii_lowercase();
+    let wanted_name = file_name(wanted);
+
+    let mut scored: Vec<(bool, usize, &str)> = entries
+        .iter()
+        .map(|e| {
+            (
+                file_name(&e.path) != wanted_name,
+                edit_distance(&e.path.to_lowercase(), &wanted.to_lowercase()),
+                e.path.as_str(),
+            )
+        })
+        .collect();
+    scored.sort();
+    scored
+        .into_iter()
+        .take(n)
+        .map(|(_, _, p)| p.to_string())
+        .collect()
+}
+
+/// Renders `pak::Progress` events on stderr as a bar or as NDJSON.
+pub struct ProgressView {
+    mode: ProgressMode,
+    bar: Option<ProgressBar>,
+    stage: Option<pak::Stage>,
+}
+
+impl ProgressView {
+    pub fn new(mode: ProgressMode) -> Self {
+        let mode = match mode {
+            ProgressMode::Auto if !std::io::stderr().is_terminal() => ProgressMode::None,
+            m => m,
+        };
+        Self {
+            mode,
+            bar: None,
+            stage: None,
+        }
+    }
+
+    pub fn update(&mut self, p: pak::Progress) {
+        match self.mode {
+            ProgressMode::None => {}
+            ProgressMode::Ndjson => {
+                if let Ok(line) = serde_json::to_string(&p) {
+                    let _ = writeln!(std::io::stderr().lock(), "{line}");
+                }
+            }
+            ProgressMode::Auto => self.draw(p),
+        }
+    }
+
+    fn draw(&mut self, p: pak::Progress) {
+        if self.stage != Some(p.stage) {
+            if let Some(bar) = self.bar.take() {
+                bar.finish_and_clear();
+            }
+            self.stage = Some(p.stage);
+            // Bytes move more smoothly than entry counts; use them when known.
+            let (len, template) = if p.bytes_total > 0 {
+                (
+                    p.bytes_total,
+                    "{prefix:>16} [{bar:30}] {bytes}/{total_bytes} {bytes_per_sec} eta {eta}  {msg}",
+                )
+            } else {
+                (p.total, "{prefix:>16} [{bar:30}] {pos}/{len}  {msg}")
+            };
+            let bar = ProgressBar::new(len).with_prefix(p.stage.as_str());
+            if let Ok(style) = ProgressStyle::with_template(template) {
+                bar.set_style(style.progress_chars("=> "));
+            }
+            self.bar = Some(bar);
+        }
+
+        if let Some(bar) = &self.bar {
+            bar.set_position(if p.bytes_total > 0 {
+                p.bytes_done
+            } else {
+                p.done
+            });
+            if let Some(current) = p.current {
+                bar.set_message(current);
+            }
+        }
+    }
+
+    /// Remove the bar so plain output can be printed underneath it.
+    pub fn clear(&mut self) {
+        if let Some(bar) = self.bar.take() {
+            bar.finish_and_clear();
+        }
+        self.stage = None;
+    }
+}
+
+impl Drop for ProgressView {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
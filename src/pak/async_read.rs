@@ -0,0 +1,130 @@
+#![forbid(unsafe_code)]
+
+//! Tokio-based reader (feature `async`). Shares footer/index parsing and
+//! payload checks with the sync reader, so both accept and reject the same paks.
+
+#[cfg(feature = "zstd")]
+use async_compression::tokio::bufread::ZstdDecoder;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use tokio::fs::File;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt, SeekFrom};
+
+use crate::pak::codec;
+use crate::pak::error::{PakError, PakResult};
+use crate::pak::format::{Entry, EntryInfo, FOOTER_LEN, MAGIC};
+use crate::pak::limits::{initial_capacity, ReadLimits};
+use crate::pak::read::{
+    check_index_len, check_index_range, decode_payload, parse_checked_index, parse_footer,
+};
+
+/// Boxed byte stream returned by [`AsyncPak::reader`].
+pub type EntryStream = Pin<Box<dyn AsyncRead + Send>>;
+
+/// An opened pak whose index has been read and checked.
+///
+/// Every read opens its own file handle, so one `AsyncPak` can serve
+/// concurrent reads from many tasks.
+#[derive(Debug, Clone)]
+pub struct AsyncPak {
+    path: PathBuf,
+    entries: Vec<Entry>,
+}
+
+impl AsyncPak {
+    /// Open a pak, check its header magic and read the index.
+    pub async fn open(pak: impl AsRef<Path>) -> PakResult<Self> {
+        Self::open_with_limits(pak, &ReadLimits::default()).await
+    }
+
+    /// [`open`](Self::open) with custom limits, for paks from untrusted sources.
+    pub async fn open_with_limits(pak: impl AsRef<Path>, limits: &ReadLimits) -> PakResult<Self> {
+        let path = pak.as_ref().to_path_buf();
+        let mut f = File::open(&path).await?;
+
+        let mut head = [0u8; 8];
+        f.read_exact(&mut head).await?;
+        if head != MAGIC {
+            return Err(PakError::Invalid("bad header magic".into()));
+        }
+
+        let file_len = f.metadata().await?.len();
+        if file_len < FOOTER_LEN {
+            return Err(PakError::Invalid("file too small".into()));
+        }
+        f.seek(SeekFrom::End(-(FOOTER_LEN as i64))).await?;
+        let mut footer_buf = [0u8; FOOTER_LEN as usize];
+        f.read_exact(&mut footer_buf).await?;
+        let footer = parse_footer(&footer_buf)?;
+
+        check_index_range(&footer, file_len)?;
+        check_index_len(footer.index_len, limits)?;
+        f.seek(SeekFrom::Start(footer.index_offset)).await?;
+        let mut index_buf = vec![0u8; footer.index_len as usize];
+        f.read_exact(&mut index_buf).await?;
+
+        let entries = parse_checked_index(&index_buf, &footer, limits)?;
+        Ok(Self { path, entries })
+    }
+
+    /// Index entries, sorted by path.
+    pub fn entries(&self) -> Vec<EntryInfo> {
+        self.entries.iter().map(Entry::info).collect()
+    }
+
+    fn find(&self, path: &str) -> PakResult<&Entry> {
+        self.entries
+            .binary_search_by(|e| e.path.as_bytes().cmp(path.as_bytes()))
+            .map(|i| &self.entries[i])
+            .map_err(|_| PakError::NotFound(path.to_string()))
+    }
+
+    async fn payload_file(&self, e: &Entry) -> PakResult<tokio::io::Take<File>> {
+        let mut f = File::open(&self.path).await?;
+        f.seek(SeekFrom::Start(e.payload_offset)).await?;
+        Ok(f.take(e.payload_len))
+    }
+
+    /// Decompressed, hash-verified bytes of one entry (same checks as `pak::entry_bytes`).
+    pub async fn read(&self, path: &str) -> PakResult<Vec<u8>> {
+        let e = self.find(path)?;
+        let mut payload = Vec::with_capacity(initial_capacity(e.payload_len));
+        self.payload_file(e)
+            .await?
+            .read_to_end(&mut payload)
+            .await?;
+        if payload.len() as u64 != e.payload_len {
+            return Err(PakError::Invalid(format!(
+                "truncated payload for {}",
+                e.path
+            )));
+        }
+        decode_payload(e, payload)
+    }
+
+    /// Stream one entry's decompressed bytes without buffering it whole.
+    ///
+    /// The stream is not hash-checked; use [`read`](Self::read) when the
+    /// bytes must be verified. Entries in registered codecs (which are
+    /// synchronous) are decoded whole before the stream starts.
+    pub async fn reader(&self, path: &str) -> PakResult<EntryStream> {
+        let e = self.find(path)?;
+        let mut payload = self.payload_file(e).await?;
+        match e.payload_kind {
+            codec::RAW => Ok(Box::pin(payload)),
+            #[cfg(feature = "zstd")]
+            codec::ZSTD => {
+                let buffered = tokio::io::BufReader::new(payload);
+                // A bomb can't make the stream yield more than the declared size.
+                Ok(Box::pin(ZstdDecoder::new(buffered).take(e.raw_len)))
+            }
+            kind => {
+                codec::lookup(kind)?;
+                let mut stored = Vec::with_capacity(initial_capacity(e.payload_len));
+                payload.read_to_end(&mut stored).await?;
+                let raw = codec::decode(kind, &stored, e.raw_len, &e.path)?;
+                Ok(Box::pin(std::io::Cursor::new(raw)))
+            }
+        }
+    }
+}
@@ -0,0 +1,227 @@
+#![forbid(unsafe_code)]
+
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use same_file::Handle;
+
+use crate::pak::error::{PakError, PakResult};
+
+/// Output file that only appears at its target path once it is complete.
+///
+/// Writes go to a sibling temp file. `commit` fsyncs it and renames it over the
+/// target; dropping without committing removes the temp file, so a failed build
+/// never leaves a truncated pak behind. An advisory lock on a sibling
+/// `.<name>.lock` file keeps two writers off the same target; the lock file is
+/// removed again on drop.
+pub(crate) struct AtomicFile {
+    target: PathBuf,
+    tmp: PathBuf,
+    file: Option<File>,
+    lock_path: PathBuf,
+    _lock: File,
+}
+
+fn sibling(target: &Path, suffix: &str) -> PathBuf {
+    let name = target
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    target.with_file_name(format!(".{name}.{suffix}"))
+}
+
+/// True when `path` is `target` or one of the lock/temp siblings an
+/// [`AtomicFile`] creates next to it. Builds skip these when the output lives
+/// inside an input root.
+pub(crate) fn is_own_file(target: &Path, path: &Path) -> bool {
+    let (Some(target_name), Some(name)) = (target.file_name(), path.file_name()) else {
+        return false;
+    };
+    let name = name.to_string_lossy();
+    let sibling = format!(".{}.", target_name.to_string_lossy());
+    let own_name = path.file_name() == Some(target_name)
+        || name == format!("{sibling}lock")
+        || (name.starts_with(&sibling) && name.ends_with(".tmp"));
+    own_name && canonical_parent(path).is_some_and(|dir| Some(dir) == canonical_parent(target))
+}
+
+fn canonical_parent(path: &Path) -> Option<PathBuf> {
+    let parent = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    parent.canonicalize().ok()
+}
+
+impl AtomicFile {
+    pub(crate) fn create(target: &Path) -> PakResult<Self> {
+        if target.file_name().is_none() {
+            return Err(PakError::Invalid(format!(
+                "output is not a file path: {}",
+                target.display()
+            )));
+        }
+
+        let lock_path = sibling(target, "lock");
+        let lock = lock_file(target, &lock_path)?;
+
+        let tmp = sibling(target, &format!("{}.tmp", std::process::id()));
+        // Readable too, so `same_as_target` can compare it with the target.
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&tmp)?;
+        Ok(Self {
+            target: target.to_path_buf(),
+            tmp,
+            file: Some(file),
+            lock_path,
+            _lock: lock,
+        })
+    }
+
+    pub(crate) fn file_mut(&mut self) -> &mut File {
+        self.file.as_mut().expect("AtomicFile used after commit")
+    }
+
+    /// True when the target exists and has exactly the bytes written so far.
+    pub(crate) fn same_as_target(&mut self) -> PakResult<bool> {
+        let Ok(target) = File::open(&self.target) else {
+            return Ok(false);
+        };
+        let file = self.file_mut();
+        file.flush()?;
+        if target.metadata()?.len() != file.metadata()?.len() {
+            return Ok(false);
+        }
+        file.seek(SeekFrom::Start(0))?;
+        same_bytes(BufReader::new(&*file), BufReader::new(target))
+    }
+
+    /// Flush to disk and move the temp file over the target.
+    pub(crate) fn commit(mut self) -> PakResult<()> {
+        let file = self.file.take().expect("AtomicFile committed twice");
+        file.sync_all()?;
+        drop(file);
+
+        if let Err(e) = std::fs::rename(&self.tmp, &self.target) {
+            let _ = std::fs::remove_file(&self.tmp);
+            return Err(e.into());
+        }
+
+        // Persist the rename itself. Directories can't be opened as files on Windows.
+        #[cfg(unix)]
+        if let Some(dir) = self.target.parent() {
+            let dir = if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir
+            };
+            File::open(dir)?.sync_all()?;
+        }
+        Ok(())
+    }
+}
+
+/// Open and lock `lock_path`. The previous holder removes the file before
+/// unlocking, so a file opened just before that removal is locked but no longer
+/// reachable by name; the next writer would lock a fresh one. Retry until the
+/// locked file is the one at `lock_path`.
+fn lock_file(target: &Path, lock_path: &Path) -> PakResult<File> {
+    loop {
+        let lock = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(lock_path)?;
+        match lock.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => return Err(PakError::Locked(target.to_path_buf())),
+            Err(TryLockError::Error(e)) => return Err(e.into()),
+        }
+        if is_linked_at(&lock, lock_path) {
+            return Ok(lock);
+        }
+    }
+}
+
+fn is_linked_at(file: &File, path: &Path) -> bool {
+    let (Ok(file), Ok(at_path)) = (file.try_clone(), Handle::from_path(path)) else {
+        return false;
+    };
+    Handle::from_file(file).is_ok_and(|locked| locked == at_path)
+}
+
+fn same_bytes(mut a: impl Read, mut b: impl Read) -> PakResult<bool> {
+    let mut buf_a = vec![0u8; 64 * 1024];
+    let mut buf_b = vec![0u8; 64 * 1024];
+    loop {
+        let n = a.read(&mut buf_a)?;
+        if n == 0 {
+            return Ok(b.read(&mut buf_b[..1])? == 0);
+        }
+        b.read_exact(&mut buf_b[..n])?;
+        if buf_a[..n] != buf_b[..n] {
+            return Ok(false);
+        }
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if self.file.take().is_some() {
+            let _ = std::fs::remove_file(&self.tmp);
+        }
+        // Still locked here: the handle closes after this returns.
+        let _ = std::fs::remove_file(&self.lock_path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("nepak-atomic-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn lock_file_is_removed_after_commit_and_failure() {
+        let dir = scratch("lock");
+        let target = dir.join("out.pak");
+
+        let mut out = AtomicFile::create(&target).unwrap();
+        out.file_mut().write_all(b"pak").unwrap();
+        assert!(matches!(
+            AtomicFile::create(&target),
+            Err(PakError::Locked(_))
+        ));
+        out.commit().unwrap();
+        assert_eq!(std::fs::read(&target).unwrap(), b"pak");
+
+        drop(AtomicFile::create(&target).unwrap());
+        let names: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(names, ["out.pak"]);
+    }
+
+    #[test]
+    fn stale_lock_handle_is_not_trusted() {
+        let dir = scratch("stale");
+        let lock_path = dir.join(".out.pak.lock");
+        let stale = File::create(&lock_path).unwrap();
+        std::fs::remove_file(&lock_path).unwrap();
+        assert!(!is_linked_at(&stale, &lock_path));
+
+        let lock = lock_file(&dir.join("out.pak"), &lock_path).unwrap();
+        assert!(is_linked_at(&lock, &lock_path));
+    }
+}
@@ -1,42 +1,160 @@
 #![forbid(unsafe_code)]
 
 use blake3::Hasher;
+use std::collections::BTreeMap;
 use std::fs::File;
-use std::io::{Read, Seek, Write};
+use std::io::{self, Cursor, Read, Seek, Write};
 use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 use walkdir::WalkDir;
 
+use crate::pak::atomic::{is_own_file, AtomicFile};
+use crate::pak::cache::{BuildCache, CacheSource, FileStamp};
+use crate::pak::cancel::CancelToken;
+use crate::pak::codec;
+use crate::pak::diff::{diff_entries, PakDiff};
 use crate::pak::error::{PakError, PakResult};
-use crate::pak::format::{Entry, PayloadKind, FOOTER_MAGIC, MAGIC};
+use crate::pak::format::{Entry, Footer, FOOTER_MAGIC, INDEX_FRONT_CODED, INDEX_ZSTD, MAGIC};
+use crate::pak::index;
 use crate::pak::io::{write_u32, write_u64};
 use crate::pak::path::{normalize_rel_path, prefixed, should_exclude};
+use crate::pak::progress::{Progress, Reporter, Stage};
+use crate::pak::portability::{check_paths, to_nfc, PortabilityIssue, PortabilityMode};
+use crate::pak::transform::{Pipeline, TransformRule};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum BuildStage {
-    Scanning,
-    WritingPayloads,
-    WritingIndex,
-    Finalizing,
+/// One input directory and the in-pak mount prefix its files are placed under.
+#[derive(Debug, Clone)]
+pub struct InputRoot {
+    pub dir: PathBuf,
+    pub mount: String,
 }
 
-impl BuildStage {
-    #[inline]
-    pub fn as_str(self) -> &'static str {
-        match self {
-            BuildStage::Scanning => "Scanning",
-            BuildStage::WritingPayloads => "Writing payloads",
-            BuildStage::WritingIndex => "Writing index",
-            BuildStage::Finalizing => "Finalizing",
+impl InputRoot {
+    pub fn new(dir: impl Into<PathBuf>, mount: impl Into<String>) -> Self {
+        Self {
+            dir: dir.into(),
+            mount: mount.into(),
+        }
+    }
+
+    /// Parse a `dir[:mount]` spec. Falls back to `default_mount` when no mount is given.
+    ///
+    /// A single-letter component before the colon is treated as a Windows drive
+    /// letter (`C:\assets`), not as a mount separator. A doubled colon (`::`)
+    /// stands for a literal `:` in the directory or mount.
+    pub fn parse(spec: &str, default_mount: &str) -> Self {
+        let unescape = |s: &str| s.replace("::", ":");
+        if let Some(at) = last_separator(spec) {
+            let (dir, mount) = (&spec[..at], &spec[at + 1..]);
+            let drive_letter = dir.len() == 1 && dir.as_bytes()[0].is_ascii_alphabetic();
+            let rooted = mount.starts_with(['/', '\\']);
+            if !dir.is_empty() && !drive_letter && !rooted {
+                return Self::new(unescape(dir), unescape(mount));
+            }
         }
+        Self::new(unescape(spec), default_mount)
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct BuildProgress {
-    pub stage: BuildStage,
-    pub done: u64,
-    pub total: u64,
-    pub current: Option<String>,
+/// Byte offset of the last `:` in `spec` that is not half of a `::` pair.
+fn last_separator(spec: &str) -> Option<usize> {
+    let bytes = spec.as_bytes();
+    let mut found = None;
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b':' {
+            if bytes.get(i + 1) == Some(&b':') {
+                i += 2;
+                continue;
+            }
+            found = Some(i);
+        }
+        i += 1;
+    }
+    found
+}
+
+/// Build settings shared by the CLI, the wizard and the GUI.
+#[derive(Debug, Clone, Default)]
+pub struct BuildOptions {
+    /// Input roots, in override order (later roots win when `allow_override` is set).
+    pub inputs: Vec<InputRoot>,
+    /// Simple contains() filters on normalized logical paths.
+    pub excludes: Vec<String>,
+    pub compress: bool,
+    pub zstd_level: i32,
+    /// Payload codec by name (built-in or registered with `register_codec`).
+    /// `None` means zstd when `compress` is set, raw otherwise.
+    pub codec: Option<String>,
+    /// Let later inputs replace earlier ones on logical path collisions instead of failing.
+    pub allow_override: bool,
+    pub portability: PortabilityMode,
+    /// Normalize logical paths to Unicode NFC before they are indexed.
+    pub nfc: bool,
+    /// Store index paths front-coded (shared prefix with the previous path).
+    pub front_code_index: bool,
+    /// zstd-compress the index block (at `zstd_level`).
+    pub compress_index: bool,
+    /// Leave an existing output (and its mtime) untouched when the new pak has
+    /// the same bytes, so build systems don't rerun downstream steps.
+    pub stamp: bool,
+    /// Transforms applied to file contents (and paths) before packing.
+    pub transforms: Vec<TransformRule>,
+    /// Directory caching transform outputs by input hash; `None` runs every
+    /// transform on every build.
+    pub transform_cache: Option<PathBuf>,
+}
+
+/// Outcome of a successful build.
+#[derive(Debug, Clone, Default)]
+pub struct BuildSummary {
+    pub entries: u64,
+    /// Portability issues found while scanning (only non-empty in `Warn` mode).
+    pub portability: Vec<PortabilityIssue>,
+    /// Entries whose payload was copied from the previous build instead of re-encoded.
+    pub reused: u64,
+    /// Index changes since the previous build (incremental builds after the first only).
+    pub changes: Option<PakDiff>,
+    /// True when `stamp` was set and the output already held these exact bytes.
+    pub unchanged: bool,
+    /// Source file of every entry, in index order.
+    pub sources: Vec<PathBuf>,
+    /// Every input directory walked (roots included, excluded ones left out).
+    /// Adding or removing a file changes one of their mtimes.
+    pub source_dirs: Vec<PathBuf>,
+}
+
+/// Scanned inputs, ready to be written.
+struct Scan {
+    files: ScannedFiles,
+    dirs: Vec<PathBuf>,
+    portability: Vec<PortabilityIssue>,
+    transforms: Pipeline,
+}
+
+impl Scan {
+    fn summary(self, written: &Written, unchanged: bool) -> BuildSummary {
+        BuildSummary {
+            entries: written.entries.len() as u64,
+            portability: self.portability,
+            reused: written.reused,
+            changes: None,
+            unchanged,
+            sources: self
+                .files
+                .into_iter()
+                .map(|(_, physical)| physical)
+                .collect(),
+            source_dirs: self.dirs,
+        }
+    }
+}
+
+/// What [`write_pak`] produced, in scan order.
+struct Written {
+    entries: Vec<Entry>,
+    stamps: Vec<Option<FileStamp>>,
+    reused: u64,
 }
 
 /// NEPAK v1 layout:
@@ -57,163 +175,366 @@ pub struct BuildProgress {
 ///   - [u64 index_offset]
 ///   - [u64 index_len]
 ///   - [u32 index_hash (blake3 truncated to u32)]
-///   - [u32 reserved]
+///   - [u32 flags] (index encoding, see `index.rs`; 0 = the plain layout above)
 ///
 /// Determinism rules:
 /// - paths are normalized to forward slashes
 /// - entries are sorted lexicographically by path bytes
-pub fn build(
-    input: &Path,
-    output: &Path,
-    prefix: &str,
-    excludes: &[String],
-    compress: bool,
-    zstd_level: i32,
-) -> PakResult<()> {
-    build_with_progress(input, output, prefix, excludes, compress, zstd_level, |_| {})
+/// - no file metadata (mtimes, owners, permissions) or build time is recorded
+pub fn build(opts: &BuildOptions, output: &Path) -> PakResult<BuildSummary> {
+    build_with_progress(opts, output, &CancelToken::default(), |_| {})
 }
 
-pub fn build_with_progress(
-    input: &Path,
-    output: &Path,
-    prefix: &str,
-    excludes: &[String],
-    compress: bool,
-    zstd_level: i32,
-    mut progress: impl FnMut(BuildProgress),
-) -> PakResult<()> {
-    if compress {
-        #[cfg(not(feature = "zstd"))]
-        {
-            let _ = zstd_level;
-            return Err(PakError::NoZstd);
+/// Bytes handed to the codec between cancellation checks.
+const COMPRESS_CHUNK: usize = 1 << 20;
+
+/// Feeds raw bytes to a codec in chunks, failing once `cancel` is set so a
+/// cancel doesn't wait for the whole entry.
+struct CancelRead<'a> {
+    data: &'a [u8],
+    cancel: &'a CancelToken,
+}
+
+impl Read for CancelRead<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.cancel.is_cancelled() {
+            return Err(io::Error::other(PakError::Cancelled));
         }
+        let n = buf.len().min(COMPRESS_CHUNK).min(self.data.len());
+        buf[..n].copy_from_slice(&self.data[..n]);
+        self.data = &self.data[n..];
+        Ok(n)
     }
+}
 
-    progress(BuildProgress {
-        stage: BuildStage::Scanning,
-        done: 0,
-        total: 0,
-        current: None,
-    });
-
-    let mut files: Vec<(String, PathBuf)> = Vec::new();
-    for ent in WalkDir::new(input).follow_links(false).into_iter() {
-        let ent = ent.map_err(|e| {
-            let msg = e.to_string();
-            let io = e
-                .into_io_error()
-                .unwrap_or_else(|| std::io::Error::new(std::io::ErrorKind::Other, msg));
-            PakError::Io(io)
-        })?;
-
-        if !ent.file_type().is_file() {
-            continue;
-        }
+/// Scanned inputs as (logical path, source file), sorted by logical path.
+type ScannedFiles = Vec<(String, PathBuf)>;
+
+/// Walk every input root and merge the results into one list sorted by logical path
+/// (after transform renames). Also returns the directories walked. The pak being
+/// written to `output` (and its lock/temp files) is never an input.
+fn scan_inputs(
+    opts: &BuildOptions,
+    output: Option<&Path>,
+    transforms: &mut Pipeline,
+    cancel: &CancelToken,
+) -> PakResult<(ScannedFiles, Vec<PathBuf>)> {
+    if opts.inputs.is_empty() {
+        return Err(PakError::Invalid("no input directories".into()));
+    }
 
-        let rel = normalize_rel_path(input, ent.path())?;
-        let logical = prefixed(prefix, &rel);
-        if should_exclude(&logical, excludes) {
-            continue;
+    // BTreeMap keeps keys in byte order, which is the index order.
+    let mut files: BTreeMap<String, PathBuf> = BTreeMap::new();
+    let mut dirs = Vec::new();
+    for root in &opts.inputs {
+        let logical_of = |rel: &str| {
+            let logical = prefixed(&root.mount, rel);
+            if opts.nfc {
+                to_nfc(&logical)
+            } else {
+                logical
+            }
+        };
+        for ent in WalkDir::new(&root.dir).follow_links(false).into_iter() {
+            let ent = ent.map_err(|e| {
+                let msg = e.to_string();
+                let io = e
+                    .into_io_error()
+                    .unwrap_or_else(|| std::io::Error::other(msg));
+                PakError::Io(io)
+            })?;
+
+            if ent.file_type().is_dir() {
+                let excluded = ent.depth() > 0
+                    && should_exclude(
+                        &logical_of(&normalize_rel_path(&root.dir, ent.path())?),
+                        &opts.excludes,
+                    );
+                if !excluded {
+                    dirs.push(ent.path().to_path_buf());
+                }
+                continue;
+            }
+            if !ent.file_type().is_file() || output.is_some_and(|o| is_own_file(o, ent.path())) {
+                continue;
+            }
+            cancel.check()?;
+
+            let logical = logical_of(&normalize_rel_path(&root.dir, ent.path())?);
+            if should_exclude(&logical, &opts.excludes) {
+                continue;
+            }
+            let logical = transforms.rename(logical);
+
+            let physical = ent.path().to_path_buf();
+            if let Some(first) = files.get(&logical) {
+                if !opts.allow_override {
+                    return Err(PakError::Collision {
+                        path: logical,
+                        first: first.clone(),
+                        second: physical,
+                    });
+                }
+            }
+            files.insert(logical, physical);
         }
-        files.push((logical, ent.path().to_path_buf()));
     }
 
-    files.sort_by(|a, b| a.0.as_bytes().cmp(b.0.as_bytes()));
+    Ok((files.into_iter().collect(), dirs))
+}
 
-    let total_files = files.len() as u64;
-    progress(BuildProgress {
-        stage: BuildStage::WritingPayloads,
-        done: 0,
-        total: total_files,
-        current: None,
-    });
+/// Like [`build`], reporting progress and stopping with [`PakError::Cancelled`]
+/// once `cancel` is set. A cancelled or failed build leaves `output` untouched.
+pub fn build_with_progress(
+    opts: &BuildOptions,
+    output: &Path,
+    cancel: &CancelToken,
+    progress: impl FnMut(Progress),
+) -> PakResult<BuildSummary> {
+    let mut progress = Reporter::new(progress);
+    let scan = prepare(opts, Some(output), cancel, &mut progress)?;
+    let mut out = AtomicFile::create(output)?;
+    let written = write_pak(
+        &scan.files,
+        opts,
+        &scan.transforms,
+        out.file_mut(),
+        None,
+        cancel,
+        &mut progress,
+    )?;
+    let unchanged = commit(out, opts)?;
+    Ok(scan.summary(&written, unchanged))
+}
+
+/// Commit `out`, or drop it when `stamp` is set and the target already has these bytes.
+/// Returns whether the target was left untouched.
+fn commit(mut out: AtomicFile, opts: &BuildOptions) -> PakResult<bool> {
+    if opts.stamp && out.same_as_target()? {
+        return Ok(true);
+    }
+    out.commit()?;
+    Ok(false)
+}
+
+/// Like [`build_with_progress`], but copies the stored payload of every file
+/// that is unchanged since the previous build made with the same `cache`.
+///
+/// The result is identical to a full build. The cache is only updated once the
+/// new pak has been committed, so a failed or cancelled build can be retried.
+pub fn build_incremental(
+    opts: &BuildOptions,
+    output: &Path,
+    cache: &mut BuildCache,
+    cancel: &CancelToken,
+    progress: impl FnMut(Progress),
+) -> PakResult<BuildSummary> {
+    let mut progress = Reporter::new(progress);
+    let scan = prepare(opts, Some(output), cancel, &mut progress)?;
+    let started = SystemTime::now();
+    let mut out = AtomicFile::create(output)?;
+    let mut source = cache.source(output, opts);
+    let written = write_pak(
+        &scan.files,
+        opts,
+        &scan.transforms,
+        out.file_mut(),
+        source.as_mut(),
+        cancel,
+        &mut progress,
+    )?;
+    drop(source);
+    let unchanged = commit(out, opts)?;
+
+    let changes = cache
+        .previous()
+        .map(|previous| diff_entries(previous, &written.entries));
+    cache.update(
+        output,
+        opts,
+        &scan.files,
+        &written.stamps,
+        &written.entries,
+        started,
+    );
+    Ok(BuildSummary {
+        changes,
+        ..scan.summary(&written, unchanged)
+    })
+}
+
+/// Build into memory instead of a file, returning the bytes and the index. Used by `--check`.
+///
+/// `output` is where the pak would be written: like a real build, it and its
+/// lock/temp files are skipped when they lie inside an input root.
+pub(crate) fn build_to_vec(
+    opts: &BuildOptions,
+    output: &Path,
+) -> PakResult<(Vec<u8>, Vec<Entry>)> {
+    let cancel = CancelToken::default();
+    let mut progress = Reporter::new(|_| {});
+    let scan = prepare(opts, Some(output), &cancel, &mut progress)?;
+    let mut out = Cursor::new(Vec::new());
+    let written = write_pak(
+        &scan.files,
+        opts,
+        &scan.transforms,
+        &mut out,
+        None,
+        &cancel,
+        &mut progress,
+    )?;
+    Ok((out.into_inner(), written.entries))
+}
+
+/// Check options, scan the inputs and apply the portability policy.
+fn prepare(
+    opts: &BuildOptions,
+    output: Option<&Path>,
+    cancel: &CancelToken,
+    progress: &mut Reporter<impl FnMut(Progress)>,
+) -> PakResult<Scan> {
+    codec::for_build(opts)?;
+
+    progress.stage(Stage::Scanning, 0, 0);
+
+    let mut transforms = Pipeline::new(opts)?;
+    let (files, dirs) = scan_inputs(opts, output, &mut transforms, cancel)?;
+
+    let portability = check_paths(files.iter().map(|(logical, _)| logical.as_str()));
+    if !portability.is_empty() && opts.portability == PortabilityMode::Error {
+        return Err(PakError::Portability(portability));
+    }
+    Ok(Scan {
+        files,
+        dirs,
+        portability,
+        transforms,
+    })
+}
+
+/// Write header, payloads, index and footer for an already scanned file list.
+/// Payloads found unchanged in `cache` are copied from the previous pak.
+fn write_pak<W: Write + Seek>(
+    files: &[(String, PathBuf)],
+    opts: &BuildOptions,
+    transforms: &Pipeline,
+    out: &mut W,
+    mut cache: Option<&mut CacheSource<'_>>,
+    cancel: &CancelToken,
+    progress: &mut Reporter<impl FnMut(Progress)>,
+) -> PakResult<Written> {
+    let codec = codec::for_build(opts)?;
+    let zstd_level = opts.zstd_level;
+
+    // Stamped before any file is read, so an edit made mid-build is seen as a change next time.
+    let stamps: Vec<Option<FileStamp>> = files
+        .iter()
+        .map(|(_, physical)| FileStamp::of(physical).ok())
+        .collect();
+    let total_bytes = stamps.iter().flatten().map(FileStamp::len).sum();
+    progress.stage(Stage::WritingPayloads, files.len() as u64, total_bytes);
 
-    let mut out = File::create(output)?;
     out.write_all(&MAGIC)?;
 
     let mut entries: Vec<Entry> = Vec::with_capacity(files.len());
+    let mut reused = 0;
 
-    for (i, (logical, physical)) in files.into_iter().enumerate() {
+    for ((logical, physical), stamp) in files.iter().zip(&stamps) {
+        cancel.check()?;
         let payload_offset = out.stream_position()?;
 
-        let mut f = File::open(&physical)?;
+        let cached = cache
+            .as_deref_mut()
+            .and_then(|c| c.lookup(logical, physical, stamp.as_ref()));
+        if let Some((prev, payload)) = cached {
+            out.write_all(&payload)?;
+            progress.advance(Some(logical), prev.raw_len);
+            entries.push(Entry {
+                payload_offset,
+                ..prev
+            });
+            reused += 1;
+            continue;
+        }
+
+        let mut f = File::open(physical)?;
         let mut raw = Vec::new();
         f.read_to_end(&mut raw)?;
+        let raw = transforms.apply(logical, raw)?;
 
         let raw_len: u64 = raw.len() as u64;
         let mut hasher = Hasher::new();
         hasher.update(&raw);
         let raw_hash: [u8; 32] = hasher.finalize().into();
 
-        let (payload, kind) = if compress {
-            #[cfg(feature = "zstd")]
-            {
-                let mut encoder = zstd::Encoder::new(Vec::new(), zstd_level)?;
-                encoder.write_all(&raw)?;
-                let payload = encoder.finish()?;
-                (payload, PayloadKind::Zstd)
-            }
-            #[cfg(not(feature = "zstd"))]
-            {
-                let _ = zstd_level;
-                unreachable!();
-            }
+        let payload = if codec.id() == codec::RAW {
+            raw
         } else {
-            (raw, PayloadKind::Raw)
+            let mut payload = Vec::new();
+            let mut input = CancelRead { data: &raw, cancel };
+            codec
+                .compress(&mut input, &mut payload, zstd_level)
+                .map_err(|e| match e.downcast::<PakError>() {
+                    Ok(e) => e,
+                    Err(e) => e.into(),
+                })?;
+            payload
         };
 
         out.write_all(&payload)?;
         let payload_len: u64 = payload.len() as u64;
 
+        progress.advance(Some(logical), raw_len);
         entries.push(Entry {
-            path: logical,
+            path: logical.clone(),
             payload_offset,
             payload_len,
             raw_len,
             raw_hash,
-            payload_kind: kind,
+            payload_kind: codec.id(),
         });
+    }
 
-        progress(BuildProgress {
-            stage: BuildStage::WritingPayloads,
-            done: (i as u64) + 1,
-            total: total_files,
-            current: entries
-                .last()
-                .map(|e| e.path.clone())
-                .filter(|s| !s.is_empty()),
-        });
+    progress.stage(Stage::WritingIndex, 1, 0);
+
+    let mut index_flags = 0;
+    if opts.front_code_index {
+        index_flags |= INDEX_FRONT_CODED;
+    }
+    if opts.compress_index {
+        index_flags |= INDEX_ZSTD;
     }
+    let footer = write_index(out, &entries, index_flags, zstd_level)?;
 
-    progress(BuildProgress {
-        stage: BuildStage::WritingIndex,
-        done: 0,
-        total: 1,
-        current: None,
-    });
+    progress.finish_stage();
 
-    let index_offset = out.stream_position()?;
-    let mut index_hasher = Hasher::new();
+    progress.stage(Stage::Finalizing, 1, 0);
 
-    let mut index_buf: Vec<u8> = Vec::new();
-    index_buf.extend_from_slice(&MAGIC);
-    index_buf.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+    write_footer(out, &footer)?;
 
-    for e in &entries {
-        let p = e.path.as_bytes();
-        if p.len() > u16::MAX as usize {
-            return Err(PakError::Invalid(format!("path too long: {}", e.path)));
-        }
-        index_buf.extend_from_slice(&(p.len() as u16).to_le_bytes());
-        index_buf.extend_from_slice(p);
-        index_buf.extend_from_slice(&e.payload_offset.to_le_bytes());
-        index_buf.extend_from_slice(&e.payload_len.to_le_bytes());
-        index_buf.extend_from_slice(&e.raw_len.to_le_bytes());
-        index_buf.push(e.payload_kind as u8);
-        index_buf.extend_from_slice(&e.raw_hash);
-    }
+    out.flush()?;
 
+    progress.finish_stage();
+    Ok(Written {
+        entries,
+        stamps,
+        reused,
+    })
+}
+
+/// Encode and write the index block at the current position; returns the
+/// footer describing it.
+pub(crate) fn write_index<W: Write + Seek>(
+    out: &mut W,
+    entries: &[Entry],
+    flags: u32,
+    zstd_level: i32,
+) -> PakResult<Footer> {
+    let index_offset = out.stream_position()?;
+    let index_buf = index::encode(entries, flags, zstd_level)?;
+
+    let mut index_hasher = Hasher::new();
     index_hasher.update(&index_buf);
     let index_hash_full: [u8; 32] = index_hasher.finalize().into();
     let index_hash_u32 = u32::from_le_bytes([
@@ -224,35 +545,19 @@ pub fn build_with_progress(
     ]);
 
     out.write_all(&index_buf)?;
-    let index_len = out.stream_position()? - index_offset;
-
-    progress(BuildProgress {
-        stage: BuildStage::WritingIndex,
-        done: 1,
-        total: 1,
-        current: None,
-    });
-
-    progress(BuildProgress {
-        stage: BuildStage::Finalizing,
-        done: 0,
-        total: 1,
-        current: None,
-    });
+    Ok(Footer {
+        index_offset,
+        index_len: out.stream_position()? - index_offset,
+        index_hash: index_hash_u32,
+        flags,
+    })
+}
 
+pub(crate) fn write_footer(out: &mut impl Write, footer: &Footer) -> PakResult<()> {
     out.write_all(&FOOTER_MAGIC)?;
-    write_u64(&mut out, index_offset)?;
-    write_u64(&mut out, index_len)?;
-    write_u32(&mut out, index_hash_u32)?;
-    write_u32(&mut out, 0)?;
-
-    out.flush()?;
-
-    progress(BuildProgress {
-        stage: BuildStage::Finalizing,
-        done: 1,
-        total: 1,
-        current: None,
-    });
+    write_u64(out, footer.index_offset)?;
+    write_u64(out, footer.index_len)?;
+    write_u32(out, footer.index_hash)?;
+    write_u32(out, footer.flags)?;
     Ok(())
 }
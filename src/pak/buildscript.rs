@@ -0,0 +1,126 @@
+#![forbid(unsafe_code)]
+
+//! Helpers for Cargo build scripts: build a pak into `OUT_DIR` and tell Cargo
+//! when to run the script again. Pair with [`EmbeddedPak`](crate::pak::EmbeddedPak)
+//! to read it back from `include_bytes!`.
+
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::pak::build::{build, BuildOptions, InputRoot};
+use crate::pak::error::PakResult;
+use crate::pak::transform::RuleSpec;
+
+/// Pak manifest for [`cargo_build_manifest`].
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Manifest {
+    /// `dir[:mount]` specs, as for `nepak build --input`.
+    inputs: Vec<String>,
+    exclude: Vec<String>,
+    compress: bool,
+    zstd_level: Option<i32>,
+    /// Codec name; custom codecs must be registered before the build.
+    codec: Option<String>,
+    allow_override: bool,
+    nfc: bool,
+    /// Rules as for `nepak build --transforms`.
+    transforms: Vec<RuleSpec>,
+}
+
+/// `nepak build`'s default `--zstd-level`.
+const DEFAULT_ZSTD_LEVEL: i32 = 6;
+
+/// Build `opts` into `$OUT_DIR/<name>` and print `cargo:rerun-if-changed` for
+/// every source file and input directory. Returns the pak's path.
+///
+/// The pak is only rewritten when its bytes change (see [`BuildOptions::stamp`]),
+/// so crates embedding it are not recompiled needlessly. Portability issues
+/// become `cargo:warning` lines. Transform outputs are cached under `OUT_DIR`
+/// unless [`BuildOptions::transform_cache`] is set.
+pub fn cargo_build(opts: &BuildOptions, name: &str) -> PakResult<PathBuf> {
+    let out_dir = std::env::var_os("OUT_DIR").ok_or_else(|| {
+        std::io::Error::other("OUT_DIR is not set; call this from a Cargo build script")
+    })?;
+    let output = Path::new(&out_dir).join(name);
+    let opts = BuildOptions {
+        stamp: true,
+        transform_cache: opts
+            .transform_cache
+            .clone()
+            .or_else(|| Some(Path::new(&out_dir).join("nepak-transform-cache"))),
+        ..opts.clone()
+    };
+
+    let summary = build(&opts, &output)?;
+    for issue in &summary.portability {
+        println!("cargo:warning={issue}");
+    }
+    for path in summary.source_dirs.iter().chain(&summary.sources) {
+        println!("cargo:rerun-if-changed={}", path.display());
+    }
+    Ok(output)
+}
+
+/// [`cargo_build`] for a single directory, uncompressed and mounted at the pak root.
+pub fn cargo_build_dir(dir: impl AsRef<Path>, name: &str) -> PakResult<PathBuf> {
+    let opts = BuildOptions {
+        inputs: vec![InputRoot::new(dir.as_ref(), "")],
+        ..Default::default()
+    };
+    cargo_build(&opts, name)
+}
+
+/// [`cargo_build`] with options read from a JSON manifest:
+///
+/// ```json
+/// { "inputs": ["assets", "../shared:shared"], "exclude": [".git"], "compress": true }
+/// ```
+///
+/// Other keys: `zstd_level` (default 6), `codec` (a name registered with
+/// [`register_codec`](crate::pak::register_codec)), `allow_override`, `nfc`, and
+/// `transforms` (rules as for [`load_transforms`](crate::pak::load_transforms)).
+/// Input dirs and transform programs are relative to the manifest's directory.
+/// The manifest and transform programs are registered with Cargo too.
+pub fn cargo_build_manifest(manifest: impl AsRef<Path>, name: &str) -> PakResult<PathBuf> {
+    let manifest = manifest.as_ref();
+    println!("cargo:rerun-if-changed={}", manifest.display());
+
+    let text = std::fs::read_to_string(manifest)?;
+    let m: Manifest = serde_json::from_str(&text).map_err(|e| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("{}: {e}", manifest.display()),
+        )
+    })?;
+
+    let base = manifest.parent().unwrap_or(Path::new(""));
+    let opts = BuildOptions {
+        inputs: m
+            .inputs
+            .iter()
+            .map(|spec| {
+                let root = InputRoot::parse(spec, "");
+                InputRoot::new(base.join(root.dir), root.mount)
+            })
+            .collect(),
+        excludes: m.exclude,
+        compress: m.compress,
+        zstd_level: m.zstd_level.unwrap_or(DEFAULT_ZSTD_LEVEL),
+        codec: m.codec,
+        allow_override: m.allow_override,
+        nfc: m.nfc,
+        transforms: m
+            .transforms
+            .into_iter()
+            .map(|spec| {
+                if let Some(program) = spec.program_file(base) {
+                    println!("cargo:rerun-if-changed={}", program.display());
+                }
+                spec.into_rule(base)
+            })
+            .collect::<PakResult<_>>()?,
+        ..Default::default()
+    };
+    cargo_build(&opts, name)
+}
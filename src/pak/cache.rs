@@ -0,0 +1,168 @@
+#![forbid(unsafe_code)]
+
+//! Incremental build cache: remembers what the previous build of an output
+//! stored for each source file, so unchanged files can have their payload
+//! copied from the old pak instead of being read, hashed and compressed again.
+//!
+//! A file counts as unchanged when its source path, size and mtime match the
+//! ones seen before it was read last time. The output produced is byte-for-byte
+//! what a full build would write.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::pak::build::BuildOptions;
+use crate::pak::format::Entry;
+use crate::pak::read::read_payload;
+use crate::pak::transform::fingerprint;
+
+/// Files modified this close to the start of a build are not cached: on
+/// filesystems with coarse mtimes a later edit could keep the same stamp.
+const RACY_WINDOW: Duration = Duration::from_secs(2);
+
+/// Size and mtime of a file, taken before its contents are read.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct FileStamp {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl FileStamp {
+    pub(crate) fn of(path: &Path) -> std::io::Result<Self> {
+        let meta = std::fs::metadata(path)?;
+        Ok(Self {
+            len: meta.len(),
+            modified: meta.modified().ok(),
+        })
+    }
+
+    pub(crate) fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Stamps without an mtime never match, so such files are always rebuilt.
+    fn matches(&self, other: &Self) -> bool {
+        self.len == other.len && self.modified.is_some() && self.modified == other.modified
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CachedFile {
+    physical: PathBuf,
+    stamp: FileStamp,
+    entry: Entry,
+}
+
+/// State carried from one [`build_incremental`](crate::pak::build_incremental)
+/// call to the next. Start with [`BuildCache::new`]; the first build is a full one.
+#[derive(Debug, Clone, Default)]
+pub struct BuildCache {
+    /// Output of the last build and its stamp right after it was committed.
+    pak: Option<(PathBuf, FileStamp)>,
+    /// `(compress, zstd_level, codec, transforms fingerprint)` the cached payloads were written with.
+    settings: (bool, i32, Option<String>, String),
+    files: HashMap<String, CachedFile>,
+    entries: Vec<Entry>,
+}
+
+impl BuildCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forget the previous build; the next build reads every file again.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Index of the previous build, if there was one.
+    pub(crate) fn previous(&self) -> Option<&[Entry]> {
+        self.pak.as_ref().map(|_| self.entries.as_slice())
+    }
+
+    /// Open the previous pak for payload reuse, if it is still the file this
+    /// cache wrote and was built with the same payload settings.
+    pub(crate) fn source(&self, output: &Path, opts: &BuildOptions) -> Option<CacheSource<'_>> {
+        let (pak, stamp) = self.pak.as_ref()?;
+        if pak != output || self.settings != settings(opts) {
+            return None;
+        }
+        if !FileStamp::of(pak).ok()?.matches(stamp) {
+            return None;
+        }
+        let file = File::open(pak).ok()?;
+        Some(CacheSource { cache: self, file })
+    }
+
+    /// Record a committed build. `started` is when the build began reading files.
+    pub(crate) fn update(
+        &mut self,
+        output: &Path,
+        opts: &BuildOptions,
+        files: &[(String, PathBuf)],
+        stamps: &[Option<FileStamp>],
+        entries: &[Entry],
+        started: SystemTime,
+    ) {
+        let racy_after = started.checked_sub(RACY_WINDOW).unwrap_or(started);
+        self.files = files
+            .iter()
+            .zip(stamps)
+            .zip(entries)
+            .filter_map(|(((logical, physical), stamp), entry)| {
+                let stamp = (*stamp)?;
+                if stamp.modified.is_none_or(|m| m >= racy_after) {
+                    return None;
+                }
+                let cached = CachedFile {
+                    physical: physical.clone(),
+                    stamp,
+                    entry: entry.clone(),
+                };
+                Some((logical.clone(), cached))
+            })
+            .collect();
+        self.settings = settings(opts);
+        self.pak = FileStamp::of(output)
+            .ok()
+            .map(|stamp| (output.to_path_buf(), stamp));
+        self.entries = entries.to_vec();
+    }
+}
+
+/// Options that change stored payloads.
+fn settings(opts: &BuildOptions) -> (bool, i32, Option<String>, String) {
+    (
+        opts.compress,
+        opts.zstd_level,
+        opts.codec.clone(),
+        fingerprint(&opts.transforms),
+    )
+}
+
+/// Open handle on the previous pak, used while writing the next one.
+pub(crate) struct CacheSource<'a> {
+    cache: &'a BuildCache,
+    file: File,
+}
+
+impl CacheSource<'_> {
+    /// The cached index record and stored payload for `logical`, if the file
+    /// behind it is unchanged. Any read problem just means "not cached".
+    pub(crate) fn lookup(
+        &mut self,
+        logical: &str,
+        physical: &Path,
+        stamp: Option<&FileStamp>,
+    ) -> Option<(Entry, Vec<u8>)> {
+        let cached = self.cache.files.get(logical)?;
+        if cached.physical != physical || !stamp?.matches(&cached.stamp) {
+            return None;
+        }
+        let e = &cached.entry;
+        let payload = read_payload(&mut self.file, e).ok()?;
+        Some((e.clone(), payload))
+    }
+}
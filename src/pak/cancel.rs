@@ -0,0 +1,36 @@
+#![forbid(unsafe_code)]
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::pak::error::{PakError, PakResult};
+
+/// Shared flag used to stop a running operation from another thread.
+///
+/// Clones share the same flag. Operations poll it between entries (and between
+/// chunks of long compressions) and return [`PakError::Cancelled`] once it is set.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// `Err(PakError::Cancelled)` once [`cancel`](Self::cancel) has been called.
+    pub fn check(&self) -> PakResult<()> {
+        if self.is_cancelled() {
+            Err(PakError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
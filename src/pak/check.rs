@@ -0,0 +1,75 @@
+#![forbid(unsafe_code)]
+
+use serde::Serialize;
+use std::path::Path;
+
+use crate::pak::build::{build_to_vec, BuildOptions};
+use crate::pak::diff::{diff_entries, PakDiff};
+use crate::pak::error::PakResult;
+use crate::pak::explain::explain;
+use crate::pak::limits::ReadLimits;
+use crate::pak::read::open_pak;
+
+/// Where a rebuilt pak first departs from the existing one.
+#[derive(Debug, Clone, Serialize)]
+pub struct FirstDifference {
+    pub offset: u64,
+    /// Field of the existing pak at `offset` (as in `explain`), if any.
+    pub field: Option<String>,
+    /// Entry owning that field, for payloads and index records.
+    pub path: Option<String>,
+}
+
+/// Result of rebuilding the inputs and comparing against an existing pak.
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckReport {
+    pub existing_len: u64,
+    pub rebuilt_len: u64,
+    /// `None` when both files are byte-identical.
+    pub first_difference: Option<FirstDifference>,
+    /// Index-level changes from the existing pak to the rebuilt one.
+    pub changes: PakDiff,
+}
+
+impl CheckReport {
+    pub fn is_identical(&self) -> bool {
+        self.first_difference.is_none()
+    }
+}
+
+/// Build `opts` in memory and compare the result byte for byte with `existing`.
+/// `existing` is treated as the build's output, so it is never one of its inputs.
+pub fn check(opts: &BuildOptions, existing: &Path) -> PakResult<CheckReport> {
+    let (rebuilt, new_entries) = build_to_vec(opts, existing)?;
+    let current = std::fs::read(existing)?;
+
+    let offset = rebuilt
+        .iter()
+        .zip(&current)
+        .position(|(a, b)| a != b)
+        .or_else(|| (rebuilt.len() != current.len()).then(|| rebuilt.len().min(current.len())))
+        .map(|o| o as u64);
+
+    let (_, old_entries) = open_pak(existing, &ReadLimits::default())?;
+
+    let first_difference = match offset {
+        None => None,
+        Some(offset) => {
+            let field = explain(existing)?
+                .into_iter()
+                .find(|f| offset >= f.offset && offset < f.offset + f.len);
+            Some(FirstDifference {
+                offset,
+                field: field.as_ref().map(|f| f.name.clone()),
+                path: field.and_then(|f| f.path),
+            })
+        }
+    };
+
+    Ok(CheckReport {
+        existing_len: current.len() as u64,
+        rebuilt_len: rebuilt.len() as u64,
+        first_difference,
+        changes: diff_entries(&old_entries, &new_entries),
+    })
+}
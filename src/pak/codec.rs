@@ -0,0 +1,227 @@
+#![forbid(unsafe_code)]
+
+//! Payload codecs: how entry bytes are stored, keyed by the `payload_kind` byte
+//! of each index record.
+//!
+//! Ids 0..=127 are reserved for nepak (`0` raw, `1` zstd). Other crates register
+//! their own codecs under [`CUSTOM_CODEC_IDS`] with [`register_codec`]; the
+//! registry is process-wide, so every reader in the process can decode them.
+
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+use std::ops::RangeInclusive;
+use std::sync::{Arc, RwLock};
+
+use crate::pak::build::BuildOptions;
+use crate::pak::error::{PakError, PakResult};
+use crate::pak::limits::initial_capacity;
+
+/// `payload_kind` of entries stored as-is.
+pub(crate) const RAW: u8 = 0;
+/// `payload_kind` of entries stored as one zstd frame.
+pub(crate) const ZSTD: u8 = 1;
+
+/// Ids available to [`register_codec`].
+pub const CUSTOM_CODEC_IDS: RangeInclusive<u8> = 128..=255;
+
+/// A way of storing payloads.
+pub trait Codec: Send + Sync {
+    /// The `payload_kind` byte written to the index.
+    fn id(&self) -> u8;
+
+    /// Name shown by `list`, `info` and `explain`, and accepted by
+    /// [`BuildOptions::codec`].
+    fn name(&self) -> &str;
+
+    /// Compress everything read from `raw` into `out`. `level` is
+    /// [`BuildOptions::zstd_level`]; codecs without levels ignore it.
+    ///
+    /// The output must depend only on the input and `level`, or builds stop
+    /// being reproducible.
+    fn compress(&self, raw: &mut dyn Read, out: &mut dyn Write, level: i32) -> io::Result<()>;
+
+    /// Stream the decompressed bytes of `stored`. Callers stop reading after
+    /// the entry's `raw_len` (plus one byte, to detect overruns).
+    fn decompress<'a>(&self, stored: Box<dyn Read + 'a>) -> io::Result<Box<dyn Read + 'a>>;
+}
+
+struct Raw;
+
+impl Codec for Raw {
+    fn id(&self) -> u8 {
+        RAW
+    }
+
+    fn name(&self) -> &str {
+        "raw"
+    }
+
+    fn compress(&self, raw: &mut dyn Read, out: &mut dyn Write, _level: i32) -> io::Result<()> {
+        io::copy(raw, out).map(drop)
+    }
+
+    fn decompress<'a>(&self, stored: Box<dyn Read + 'a>) -> io::Result<Box<dyn Read + 'a>> {
+        Ok(stored)
+    }
+}
+
+#[cfg(feature = "zstd")]
+struct Zstd;
+
+#[cfg(feature = "zstd")]
+impl Codec for Zstd {
+    fn id(&self) -> u8 {
+        ZSTD
+    }
+
+    fn name(&self) -> &str {
+        "zstd"
+    }
+
+    fn compress(&self, raw: &mut dyn Read, out: &mut dyn Write, level: i32) -> io::Result<()> {
+        let mut encoder = zstd::Encoder::new(out, level)?;
+        io::copy(raw, &mut encoder)?;
+        encoder.finish().map(drop)
+    }
+
+    fn decompress<'a>(&self, stored: Box<dyn Read + 'a>) -> io::Result<Box<dyn Read + 'a>> {
+        Ok(Box::new(zstd::stream::read::Decoder::new(stored)?))
+    }
+}
+
+static CUSTOM: RwLock<BTreeMap<u8, Arc<dyn Codec>>> = RwLock::new(BTreeMap::new());
+
+/// Name of a reserved id, whether or not this build includes the codec.
+fn builtin_name(id: u8) -> Option<&'static str> {
+    match id {
+        RAW => Some("raw"),
+        ZSTD => Some("zstd"),
+        _ => None,
+    }
+}
+
+fn builtin(id: u8) -> Option<Arc<dyn Codec>> {
+    match id {
+        RAW => Some(Arc::new(Raw)),
+        #[cfg(feature = "zstd")]
+        ZSTD => Some(Arc::new(Zstd)),
+        _ => None,
+    }
+}
+
+/// Make `codec` available to every build and reader in this process.
+///
+/// Fails if its id is outside [`CUSTOM_CODEC_IDS`], or if the id or name is
+/// already taken by another codec. Registering the same id and name again is a
+/// no-op, so it is safe to call from every entry point.
+pub fn register_codec(codec: impl Codec + 'static) -> PakResult<()> {
+    let (id, name) = (codec.id(), codec.name().to_string());
+    let refuse = |reason: String| {
+        Err(PakError::CodecRegistration {
+            id,
+            name: name.clone(),
+            reason,
+        })
+    };
+
+    if !CUSTOM_CODEC_IDS.contains(&id) {
+        return refuse(format!(
+            "ids below {} are reserved for nepak",
+            CUSTOM_CODEC_IDS.start()
+        ));
+    }
+    // Check and insert under one lock, so two threads can't both claim a name.
+    let mut custom = CUSTOM.write().unwrap_or_else(|e| e.into_inner());
+    let name_taken = (0..=127).filter_map(builtin_name).any(|n| n == name)
+        || custom
+            .iter()
+            .any(|(&other, c)| other != id && c.name() == name);
+    if name_taken {
+        return refuse("name is already registered".into());
+    }
+    match custom.get(&id) {
+        Some(c) if c.name() == name => Ok(()),
+        Some(c) => refuse(format!("id is already registered as {}", c.name())),
+        None => {
+            custom.insert(id, Arc::new(codec));
+            Ok(())
+        }
+    }
+}
+
+/// The codec stored under `id`, if this build has it or it was registered.
+pub fn codec_by_id(id: u8) -> Option<Arc<dyn Codec>> {
+    builtin(id).or_else(|| {
+        let custom = CUSTOM.read().unwrap_or_else(|e| e.into_inner());
+        custom.get(&id).cloned()
+    })
+}
+
+/// The codec registered under `name`, if any.
+pub fn codec_by_name(name: &str) -> Option<Arc<dyn Codec>> {
+    codecs().into_iter().find(|c| c.name() == name)
+}
+
+/// Every codec available in this process, by id.
+pub fn codecs() -> Vec<Arc<dyn Codec>> {
+    let custom = CUSTOM.read().unwrap_or_else(|e| e.into_inner());
+    (0..=127)
+        .filter_map(builtin)
+        .chain(custom.values().cloned())
+        .collect()
+}
+
+/// Like [`codec_by_id`], but an error naming the id (and its codec, if known).
+pub(crate) fn lookup(id: u8) -> PakResult<Arc<dyn Codec>> {
+    codec_by_id(id).ok_or_else(|| PakError::UnknownCodec {
+        id: Some(id),
+        name: builtin_name(id).map(String::from),
+    })
+}
+
+/// Display name of `id`: the codec's name, or `codec-<id>` when it is unknown.
+pub(crate) fn name_of(id: u8) -> String {
+    match builtin_name(id) {
+        Some(name) => name.to_string(),
+        None => codec_by_id(id).map_or_else(|| format!("codec-{id}"), |c| c.name().to_string()),
+    }
+}
+
+/// The codec a build writes payloads with.
+pub(crate) fn for_build(opts: &BuildOptions) -> PakResult<Arc<dyn Codec>> {
+    match &opts.codec {
+        Some(name) => codec_by_name(name).ok_or_else(|| PakError::UnknownCodec {
+            id: None,
+            name: Some(name.clone()),
+        }),
+        None if opts.compress => {
+            #[cfg(feature = "zstd")]
+            {
+                Ok(Arc::new(Zstd))
+            }
+            #[cfg(not(feature = "zstd"))]
+            {
+                Err(PakError::NoZstd)
+            }
+        }
+        None => Ok(Arc::new(Raw)),
+    }
+}
+
+/// Decompress a stored payload, failing once the output would exceed
+/// `max_len` bytes (a decompression bomb, or data that doesn't match its
+/// declared size).
+pub(crate) fn decode(id: u8, stored: &[u8], max_len: u64, what: &str) -> PakResult<Vec<u8>> {
+    let codec = lookup(id)?;
+    let mut out = Vec::with_capacity(initial_capacity(max_len));
+    codec
+        .decompress(Box::new(stored))?
+        .take(max_len.saturating_add(1))
+        .read_to_end(&mut out)?;
+    if out.len() as u64 > max_len {
+        return Err(PakError::Invalid(format!(
+            "{what} decompresses to more than {max_len} bytes"
+        )));
+    }
+    Ok(out)
+}
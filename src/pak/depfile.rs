@@ -0,0 +1,116 @@
+#![forbid(unsafe_code)]
+
+//! Makefile-style dependency files (`nepak build --depfile`), as read by Make
+//! (`-include out.d`) and Ninja (`depfile = out.d`).
+
+use std::fmt::Write as _;
+use std::path::Path;
+
+use crate::pak::build::BuildSummary;
+use crate::pak::error::PakResult;
+
+/// Escape a path for a Makefile rule; Ninja's depfile parser accepts the same escapes.
+///
+/// Space, `#` and `:` get a backslash. Backslashes right before them or at the
+/// end of the path are doubled (as gcc does), so they are not read as escapes.
+/// A drive letter's colon (`C:\`) is left alone.
+fn escape(path: &Path) -> PakResult<String> {
+    let s = path.to_string_lossy();
+    if s.contains(['\n', '\r']) {
+        return Err(
+            std::io::Error::other(format!("path cannot be written to a depfile: {s:?}")).into(),
+        );
+    }
+    let drive = matches!(s.as_bytes(), [d, b':', b'/' | b'\\', ..] if d.is_ascii_alphabetic());
+
+    let mut out = String::with_capacity(s.len());
+    let mut backslashes = 0;
+    for (i, c) in s.char_indices() {
+        if c == '\\' {
+            backslashes += 1;
+            continue;
+        }
+        let escaped = matches!(c, ' ' | '#') || (c == ':' && !(drive && i == 1));
+        let run = if escaped {
+            2 * backslashes
+        } else {
+            backslashes
+        };
+        out.extend(std::iter::repeat_n('\\', run));
+        backslashes = 0;
+        match c {
+            '$' => out.push_str("$$"),
+            _ if escaped => {
+                out.push('\\');
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    out.extend(std::iter::repeat_n('\\', 2 * backslashes));
+    Ok(out)
+}
+
+/// Write `depfile` declaring that `target` depends on every source file and
+/// input directory of `summary`. Directories are listed so that adding a file
+/// triggers a rebuild.
+///
+/// Every source file also gets an empty rule (like `gcc -MP`), so deleting one
+/// does not make Make fail with "no rule to make target".
+pub fn write_depfile(depfile: &Path, target: &Path, summary: &BuildSummary) -> PakResult<()> {
+    let deps = summary.source_dirs.iter().chain(&summary.sources);
+
+    let mut out = format!("{}:", escape(target)?);
+    for dep in deps {
+        let _ = write!(out, " \\\n  {}", escape(dep)?);
+    }
+    out.push('\n');
+    for source in &summary.sources {
+        let _ = write!(out, "\n{}:\n", escape(source)?);
+    }
+
+    std::fs::write(depfile, out)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn esc(path: &str) -> String {
+        escape(Path::new(path)).unwrap()
+    }
+
+    #[test]
+    fn escapes_make_syntax() {
+        assert_eq!(esc("assets/a.png"), "assets/a.png");
+        assert_eq!(esc("my assets/a b.png"), "my\\ assets/a\\ b.png");
+        assert_eq!(esc("a#1.txt"), "a\\#1.txt");
+        assert_eq!(esc("cost$5"), "cost$$5");
+        assert_eq!(esc("a:b/c:d"), "a\\:b/c\\:d");
+    }
+
+    #[test]
+    fn doubles_backslashes_before_escaped_characters() {
+        assert_eq!(esc(r"dir\ x"), r"dir\\\ x");
+        assert_eq!(esc(r"dir\#x"), r"dir\\\#x");
+        assert_eq!(esc(r"dir\:x"), r"dir\\\:x");
+        // A trailing backslash would escape the separator after it.
+        assert_eq!(esc(r"dir\"), r"dir\\");
+        // Other backslashes (Windows separators) are kept as they are.
+        assert_eq!(esc(r"a\b\\c"), r"a\b\\c");
+    }
+
+    #[test]
+    fn keeps_drive_letters() {
+        assert_eq!(esc(r"C:\assets\a b.png"), r"C:\assets\a\ b.png");
+        assert_eq!(esc("d:/x:y"), "d:/x\\:y");
+        assert_eq!(esc("C:x"), "C\\:x");
+    }
+
+    #[test]
+    fn rejects_newlines() {
+        assert!(escape(Path::new("a\nb")).is_err());
+        assert!(escape(Path::new("a\rb")).is_err());
+    }
+}
@@ -0,0 +1,179 @@
+#![forbid(unsafe_code)]
+
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use crate::pak::error::PakResult;
+use crate::pak::format::Entry;
+use crate::pak::limits::ReadLimits;
+use crate::pak::read::open_pak;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffKind {
+    Added,
+    Removed,
+    /// Same path, different `raw_hash`.
+    Modified,
+    /// Same `raw_hash`, path only present on one side.
+    Renamed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffEntry {
+    pub kind: DiffKind,
+    /// Path in the new pak (the old path for `Removed`).
+    pub path: String,
+    /// Previous path, only set for `Renamed`.
+    pub old_path: Option<String>,
+    pub old_raw_len: u64,
+    pub new_raw_len: u64,
+    pub old_payload_len: u64,
+    pub new_payload_len: u64,
+    pub raw_delta: i64,
+    pub payload_delta: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DiffTotals {
+    pub old_entries: u64,
+    pub new_entries: u64,
+    pub unchanged: u64,
+    pub old_raw: u64,
+    pub new_raw: u64,
+    pub old_payload: u64,
+    pub new_payload: u64,
+    /// `new_raw - old_raw`, clamped to the `i64` range.
+    pub raw_delta: i64,
+    /// `new_payload - old_payload`, clamped to the `i64` range.
+    pub payload_delta: i64,
+}
+
+/// Index-only comparison of two paks. Payload bytes are never read.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PakDiff {
+    /// Changes sorted by path.
+    pub changes: Vec<DiffEntry>,
+    pub totals: DiffTotals,
+}
+
+impl PakDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+fn change(kind: DiffKind, old: Option<&Entry>, new: Option<&Entry>) -> DiffEntry {
+    let path = new.or(old).map(|e| e.path.clone()).unwrap_or_default();
+    let raw = |e: Option<&Entry>| e.map_or(0, |e| e.raw_len);
+    let payload = |e: Option<&Entry>| e.map_or(0, |e| e.payload_len);
+    DiffEntry {
+        kind,
+        path,
+        old_path: match kind {
+            DiffKind::Renamed => old.map(|e| e.path.clone()),
+            _ => None,
+        },
+        old_raw_len: raw(old),
+        new_raw_len: raw(new),
+        old_payload_len: payload(old),
+        new_payload_len: payload(new),
+        raw_delta: delta(raw(old), raw(new)),
+        payload_delta: delta(payload(old), payload(new)),
+    }
+}
+
+fn sum(entries: &[Entry], len: impl Fn(&Entry) -> u64) -> u64 {
+    entries.iter().map(len).fold(0, u64::saturating_add)
+}
+
+fn delta(old: u64, new: u64) -> i64 {
+    (new as i128 - old as i128).clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
+
+pub(crate) fn diff_entries(old: &[Entry], new: &[Entry]) -> PakDiff {
+    let old_by_path: BTreeMap<&str, &Entry> = old.iter().map(|e| (e.path.as_str(), e)).collect();
+    let new_by_path: BTreeMap<&str, &Entry> = new.iter().map(|e| (e.path.as_str(), e)).collect();
+
+    let (old_raw, new_raw) = (sum(old, |e| e.raw_len), sum(new, |e| e.raw_len));
+    let (old_payload, new_payload) = (sum(old, |e| e.payload_len), sum(new, |e| e.payload_len));
+    let mut totals = DiffTotals {
+        old_entries: old.len() as u64,
+        new_entries: new.len() as u64,
+        unchanged: 0,
+        old_raw,
+        new_raw,
+        old_payload,
+        new_payload,
+        raw_delta: delta(old_raw, new_raw),
+        payload_delta: delta(old_payload, new_payload),
+    };
+
+    let mut changes = Vec::new();
+    let mut removed: Vec<&Entry> = Vec::new();
+    for (path, o) in &old_by_path {
+        match new_by_path.get(path) {
+            Some(n) if n.raw_hash == o.raw_hash => totals.unchanged += 1,
+            Some(n) => changes.push(change(DiffKind::Modified, Some(o), Some(n))),
+            None => removed.push(o),
+        }
+    }
+
+    // Removed paths whose content reappears under a new path are renames.
+    let mut removed_by_hash: HashMap<[u8; 32], Vec<&Entry>> = HashMap::new();
+    for o in removed.iter().rev() {
+        removed_by_hash.entry(o.raw_hash).or_default().push(o);
+    }
+    for (path, n) in &new_by_path {
+        if old_by_path.contains_key(path) {
+            continue;
+        }
+        match removed_by_hash.get_mut(&n.raw_hash).and_then(Vec::pop) {
+            Some(o) => changes.push(change(DiffKind::Renamed, Some(o), Some(n))),
+            None => changes.push(change(DiffKind::Added, None, Some(n))),
+        }
+    }
+    for o in removed_by_hash.into_values().flatten() {
+        changes.push(change(DiffKind::Removed, Some(o), None));
+    }
+
+    changes.sort_by(|a, b| a.path.as_bytes().cmp(b.path.as_bytes()));
+    PakDiff { changes, totals }
+}
+
+pub fn diff(old: &Path, new: &Path) -> PakResult<PakDiff> {
+    let (_, old_entries) = open_pak(old, &ReadLimits::default())?;
+    let (_, new_entries) = open_pak(new, &ReadLimits::default())?;
+    Ok(diff_entries(&old_entries, &new_entries))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, len: u64, hash: u8) -> Entry {
+        Entry {
+            path: path.into(),
+            payload_offset: 8,
+            payload_len: len,
+            raw_len: len,
+            raw_hash: [hash; 32],
+            payload_kind: 0,
+        }
+    }
+
+    #[test]
+    fn huge_deltas_are_clamped() {
+        let old = [entry("a", u64::MAX, 1), entry("b", u64::MAX, 2)];
+        let new = [entry("c", 1, 3)];
+        let diff = diff_entries(&old, &new);
+        let t = &diff.totals;
+        assert_eq!((t.old_raw, t.new_raw), (u64::MAX, 1));
+        assert_eq!((t.raw_delta, t.payload_delta), (i64::MIN, i64::MIN));
+        assert_eq!(diff.changes[0].raw_delta, i64::MIN);
+
+        let diff = diff_entries(&new, &old);
+        assert_eq!(diff.totals.raw_delta, i64::MAX);
+    }
+}
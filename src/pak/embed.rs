@@ -0,0 +1,77 @@
+#![forbid(unsafe_code)]
+
+//! Reader for paks compiled into the binary with `include_bytes!`.
+
+use std::borrow::Cow;
+
+use crate::pak::error::{PakError, PakResult};
+use crate::pak::format::{Entry, EntryInfo, FOOTER_LEN, MAGIC};
+use crate::pak::limits::ReadLimits;
+use crate::pak::read::{check_index_range, decode_payload_cow, parse_checked_index, parse_footer};
+
+/// A pak held in static memory, e.g.
+/// `EmbeddedPak::open(include_bytes!(concat!(env!("OUT_DIR"), "/assets.pak")))`.
+///
+/// The index and every payload range are checked once in [`open`](Self::open);
+/// reads only decompress and hash.
+#[derive(Debug, Clone)]
+pub struct EmbeddedPak {
+    data: &'static [u8],
+    entries: Vec<Entry>,
+}
+
+impl EmbeddedPak {
+    /// Check the header magic, read the index and check that every payload lies in `data`.
+    pub fn open(data: &'static [u8]) -> PakResult<Self> {
+        Self::open_with_limits(data, &ReadLimits::default())
+    }
+
+    /// [`open`](Self::open) with custom limits.
+    pub fn open_with_limits(data: &'static [u8], limits: &ReadLimits) -> PakResult<Self> {
+        if data.len() < MAGIC.len() || data[..MAGIC.len()] != MAGIC {
+            return Err(PakError::Invalid("bad header magic".into()));
+        }
+        let footer_at = data
+            .len()
+            .checked_sub(FOOTER_LEN as usize)
+            .ok_or_else(|| PakError::Invalid("file too small".into()))?;
+        let footer_buf = data[footer_at..]
+            .try_into()
+            .map_err(|_| PakError::Invalid("file too small".into()))?;
+        let footer = parse_footer(footer_buf)?;
+
+        check_index_range(&footer, data.len() as u64)?;
+        let start = footer.index_offset as usize;
+        let index = &data[start..start + footer.index_len as usize];
+        // Also checks that every payload lies between the header and the index,
+        // like the file and async readers.
+        let entries = parse_checked_index(index, &footer, limits)?;
+        Ok(Self { data, entries })
+    }
+
+    /// Index entries, sorted by path.
+    pub fn entries(&self) -> Vec<EntryInfo> {
+        self.entries.iter().map(Entry::info).collect()
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.find(path).is_ok()
+    }
+
+    fn find(&self, path: &str) -> PakResult<&Entry> {
+        self.entries
+            .binary_search_by(|e| e.path.as_bytes().cmp(path.as_bytes()))
+            .map(|i| &self.entries[i])
+            .map_err(|_| PakError::NotFound(path.to_string()))
+    }
+
+    /// Hash-checked bytes of one entry. Uncompressed entries are borrowed from
+    /// the embedded data; zstd entries are decompressed into a new buffer.
+    pub fn read(&self, path: &str) -> PakResult<Cow<'static, [u8]>> {
+        let e = self.find(path)?;
+        // Ranges were checked by `parse_checked_index` in `open`.
+        let start = e.payload_offset as usize;
+        let payload = &self.data[start..start + e.payload_len as usize];
+        decode_payload_cow(e, Cow::Borrowed(payload))
+    }
+}
@@ -1,7 +1,10 @@
 #![forbid(unsafe_code)]
 
+use std::path::PathBuf;
 use thiserror::Error;
 
+use crate::pak::portability::PortabilityIssue;
+
 #[derive(Debug, Error)]
 pub enum PakError {
     #[error("io: {0}")]
@@ -10,11 +13,76 @@ pub enum PakError {
     #[error("invalid pak: {0}")]
     Invalid(String),
 
+    #[error("entry not found: {0}")]
+    NotFound(String),
+
     #[error("path is outside input dir: {0}")]
     Outside(String),
 
+    #[error(
+        "path collision: {path} is provided by both {} and {}",
+        first.display(),
+        second.display()
+    )]
+    Collision {
+        path: String,
+        first: PathBuf,
+        second: PathBuf,
+    },
+
+    #[error("portability check failed:{}", format_issues(.0))]
+    Portability(Vec<PortabilityIssue>),
+
+    #[error("unsafe entry path {path:?}: {reason}")]
+    UnsafePath { path: String, reason: &'static str },
+
+    #[error("{} is being written by another process", .0.display())]
+    Locked(PathBuf),
+
+    #[error("transform {transform} failed on {path}: {message}")]
+    Transform {
+        path: String,
+        transform: String,
+        message: String,
+    },
+
+    #[error("unknown payload codec {}", describe_codec(*.id, .name.as_deref()))]
+    UnknownCodec {
+        id: Option<u8>,
+        name: Option<String>,
+    },
+
+    #[error("cannot register codec {name} (id {id}): {reason}")]
+    CodecRegistration {
+        id: u8,
+        name: String,
+        reason: String,
+    },
+
+    #[error("operation cancelled")]
+    Cancelled,
+
+    #[cfg(feature = "watch")]
+    #[error("watch: {0}")]
+    Watch(#[from] notify::Error),
+
     #[error("compression requested but nepak was built without zstd feature")]
     NoZstd,
+
+    #[error("watch mode requested but nepak was built without watch feature")]
+    NoWatch,
 }
 
-pub type PakResult<T> = Result<T, PakError>;
\ No newline at end of file
+pub type PakResult<T> = Result<T, PakError>;
+fn format_issues(issues: &[PortabilityIssue]) -> String {
+    issues.iter().map(|i| format!("\n  {i}")).collect()
+}
+
+fn describe_codec(id: Option<u8>, name: Option<&str>) -> String {
+    match (id, name) {
+        (Some(id), Some(name)) => format!("{id} ({name}, not built in)"),
+        (Some(id), None) => id.to_string(),
+        (None, Some(name)) => format!("'{name}'"),
+        (None, None) => "?".into(),
+    }
+}
@@ -0,0 +1,158 @@
+#![forbid(unsafe_code)]
+
+use serde::Serialize;
+use std::io::{Seek, SeekFrom};
+use std::path::Path;
+
+use crate::pak::codec;
+use crate::pak::error::PakResult;
+use crate::pak::format::{FOOTER_LEN, FOOTER_MAGIC, INDEX_FRONT_CODED, INDEX_ZSTD, MAGIC};
+use crate::pak::index;
+use crate::pak::io::{hex32, read_u16};
+use crate::pak::limits::ReadLimits;
+use crate::pak::read::{open_pak, read_footer};
+
+/// One annotated byte range of a pak file.
+#[derive(Debug, Clone, Serialize)]
+pub struct LayoutField {
+    pub offset: u64,
+    pub len: u64,
+    /// Field name as in the README spec, e.g. `index[3].payload_len`.
+    pub name: String,
+    pub value: String,
+    /// Entry this range belongs to (payloads and index records).
+    pub path: Option<String>,
+}
+
+fn escape_magic(bytes: &[u8]) -> String {
+    bytes.escape_ascii().to_string()
+}
+
+/// Walk the file and describe every field, in file order.
+pub fn explain(pak: &Path) -> PakResult<Vec<LayoutField>> {
+    let (mut f, entries) = open_pak(pak, &ReadLimits::default())?;
+    let footer = read_footer(&mut f)?;
+    let file_len = f.metadata()?.len();
+
+    let mut out = Vec::new();
+    let mut field = |offset: u64, len: u64, name: String, value: String, path: Option<&str>| {
+        out.push(LayoutField {
+            offset,
+            len,
+            name,
+            value,
+            path: path.map(str::to_string),
+        })
+    };
+
+    field(
+        0,
+        MAGIC.len() as u64,
+        "header.magic".into(),
+        escape_magic(&MAGIC),
+        None,
+    );
+
+    let mut by_offset: Vec<_> = entries.iter().collect();
+    by_offset.sort_by_key(|e| (e.payload_offset, e.payload_len));
+    for e in by_offset {
+        let kind = codec::name_of(e.payload_kind);
+        field(
+            e.payload_offset,
+            e.payload_len,
+            "payload".into(),
+            format!("{kind}, raw_len {}", e.raw_len),
+            Some(&e.path),
+        );
+    }
+
+    if footer.flags & INDEX_ZSTD != 0 {
+        // A compressed index is one zstd frame; its records have no offsets
+        // in the file.
+        field(
+            footer.index_offset,
+            footer.index_len,
+            "index.block".into(),
+            format!(
+                "{}, {} entries, {} bytes decoded as plain",
+                index::encoding_name(footer.flags),
+                entries.len(),
+                index::plain_len(&entries)
+            ),
+            None,
+        );
+    } else {
+        let front_coded = footer.flags & INDEX_FRONT_CODED != 0;
+        let mut at = footer.index_offset;
+        field(at, 8, "index.magic".into(), escape_magic(&MAGIC), None);
+        at += 8;
+        field(
+            at,
+            4,
+            "index.entry_count".into(),
+            entries.len().to_string(),
+            None,
+        );
+        at += 4;
+
+        for (i, e) in entries.iter().enumerate() {
+            let path = Some(e.path.as_str());
+            let mut rows: Vec<(&str, u64, String)> = if front_coded {
+                f.seek(SeekFrom::Start(at))?;
+                let shared = usize::from(read_u16(&mut f)?);
+                let suffix = e.path.get(shared..).map_or_else(
+                    || format!("\"{}\"", e.path.as_bytes()[shared..].escape_ascii()),
+                    |s| format!("{s:?}"),
+                );
+                let suffix_len = (e.path.len() - shared) as u64;
+                vec![
+                    ("shared", 2, shared.to_string()),
+                    ("suffix_len", 2, suffix_len.to_string()),
+                    ("suffix", suffix_len, suffix),
+                ]
+            } else {
+                let path_len = e.path.len() as u64;
+                vec![
+                    ("path_len", 2, path_len.to_string()),
+                    ("path", path_len, format!("{:?}", e.path)),
+                ]
+            };
+            let kind = e.payload_kind;
+            let kind_name = codec::name_of(kind);
+            rows.extend([
+                ("payload_offset", 8, e.payload_offset.to_string()),
+                ("payload_len", 8, e.payload_len.to_string()),
+                ("raw_len", 8, e.raw_len.to_string()),
+                ("payload_kind", 1, format!("{kind} ({kind_name})")),
+                ("raw_hash", 32, hex32(&e.raw_hash)),
+            ]);
+            for (name, len, value) in rows {
+                field(at, len, format!("index[{i}].{name}"), value, path);
+                at += len;
+            }
+        }
+    }
+
+    let mut at = file_len - FOOTER_LEN;
+    let rows: [(&str, u64, String); 5] = [
+        ("magic", 8, escape_magic(&FOOTER_MAGIC)),
+        ("index_offset", 8, footer.index_offset.to_string()),
+        ("index_len", 8, footer.index_len.to_string()),
+        ("index_hash", 4, format!("{:08x}", footer.index_hash)),
+        (
+            "flags",
+            4,
+            format!(
+                "{:#x} ({})",
+                footer.flags,
+                index::encoding_name(footer.flags)
+            ),
+        ),
+    ];
+    for (name, len, value) in rows {
+        field(at, len, format!("footer.{name}"), value, None);
+        at += len;
+    }
+
+    Ok(out)
+}
@@ -1,16 +1,34 @@
 #![forbid(unsafe_code)]
 
+use serde::Serialize;
+
+use crate::pak::codec;
+use crate::pak::io::hex32;
+
 /// NEPAK v1 header magic.
 pub const MAGIC: [u8; 8] = *b"NEPAK\x01\x00\x00";
 
 /// NEPAK v1 footer magic.
 pub const FOOTER_MAGIC: [u8; 8] = *b"NEPAKEND";
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-#[repr(u8)]
-pub(crate) enum PayloadKind {
-    Raw = 0,
-    Zstd = 1,
+/// Footer `flags` bit: index paths are front-coded against the previous record.
+pub const INDEX_FRONT_CODED: u32 = 1;
+
+/// Footer `flags` bit: the index block is stored as a single zstd frame.
+pub const INDEX_ZSTD: u32 = 1 << 1;
+
+/// Size of the fixed footer at the end of every pak.
+pub const FOOTER_LEN: u64 = 8 + 8 + 8 + 4 + 4;
+
+/// Decoded footer fields (after `FOOTER_MAGIC`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Footer {
+    pub index_offset: u64,
+    pub index_len: u64,
+    /// blake3(index bytes) truncated to u32 (little-endian).
+    pub index_hash: u32,
+    /// Index encoding bits (`INDEX_*`); 0 is the plain v1 index.
+    pub flags: u32,
 }
 
 #[derive(Debug, Clone)]
@@ -20,18 +38,60 @@ pub(crate) struct Entry {
     pub payload_len: u64,
     pub raw_len: u64,
     pub raw_hash: [u8; 32],
-    pub payload_kind: PayloadKind,
+    /// Codec id (see [`codec::codec_by_id`]).
+    pub payload_kind: u8,
+}
+
+impl Entry {
+    pub(crate) fn info(&self) -> EntryInfo {
+        EntryInfo {
+            path: self.path.clone(),
+            payload_offset: self.payload_offset,
+            payload_len: self.payload_len,
+            raw_len: self.raw_len,
+            payload_kind: codec::name_of(self.payload_kind),
+            raw_hash_hex: hex32(&self.raw_hash),
+        }
+    }
 }
 
 /// Public view of a pak entry (for GUI tooling, inspectors, etc.).
-#[derive(Debug, Clone)]
+///
+/// Field names are stable: `nepak list --format json|csv|ndjson` uses them as keys.
+#[derive(Debug, Clone, Serialize)]
 pub struct EntryInfo {
     pub path: String,
     pub payload_offset: u64,
     pub payload_len: u64,
     pub raw_len: u64,
-    /// "raw" or "zstd"
-    pub payload_kind: &'static str,
+    /// Codec name: "raw", "zstd", a registered codec's name, or `codec-<id>`
+    /// for codecs unknown to this process.
+    pub payload_kind: String,
     /// Blake3 hash (hex) of the raw, uncompressed bytes.
     pub raw_hash_hex: String,
-}
\ No newline at end of file
+}
+
+impl EntryInfo {
+    /// Names accepted by `nepak list --fields`, in output order.
+    pub const FIELDS: &'static [&'static str] = &[
+        "path",
+        "payload_offset",
+        "payload_len",
+        "raw_len",
+        "payload_kind",
+        "raw_hash_hex",
+    ];
+
+    /// Field value rendered as text (CSV / plain output).
+    pub fn field(&self, name: &str) -> Option<String> {
+        Some(match name {
+            "path" => self.path.clone(),
+            "payload_offset" => self.payload_offset.to_string(),
+            "payload_len" => self.payload_len.to_string(),
+            "raw_len" => self.raw_len.to_string(),
+            "payload_kind" => self.payload_kind.clone(),
+            "raw_hash_hex" => self.raw_hash_hex.clone(),
+            _ => return None,
+        })
+    }
+}
@@ -0,0 +1,42 @@
+#![forbid(unsafe_code)]
+
+//! Entry points for the cargo-fuzz targets in `fuzz/`. Only built under
+//! `--cfg fuzzing`, which also skips the index hash check (see `read.rs`).
+
+use std::io::Cursor;
+
+use crate::pak::format::MAGIC;
+use crate::pak::index;
+use crate::pak::io::read_exact;
+use crate::pak::limits::ReadLimits;
+use crate::pak::read::{decode_payload, read_index, read_payload};
+
+/// Tight enough that the fuzzer reports slow or huge inputs as real findings.
+const LIMITS: ReadLimits = ReadLimits {
+    max_index_len: 1 << 20,
+    max_entries: 1 << 12,
+    max_raw_len: 1 << 20,
+};
+
+/// First byte: footer flags; the rest: a stored index block.
+pub fn index(data: &[u8]) {
+    if let Some((&flags, block)) = data.split_first() {
+        let _ = index::decode(block, u32::from(flags), &LIMITS);
+    }
+}
+
+/// A whole pak file, opened and read entry by entry like [`PakFile`](crate::pak::PakFile).
+pub fn reader(data: &[u8]) {
+    let mut f = Cursor::new(data);
+    if read_exact::<8>(&mut f).ok() != Some(MAGIC) {
+        return;
+    }
+    let Ok(entries) = read_index(&mut f, &LIMITS) else {
+        return;
+    };
+    for e in &entries {
+        if let Ok(payload) = read_payload(&mut f, e) {
+            let _ = decode_payload(e, payload);
+        }
+    }
+}
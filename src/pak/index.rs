@@ -0,0 +1,212 @@
+#![forbid(unsafe_code)]
+
+//! Index block encodings, selected by the footer `flags` bits.
+//!
+//! - plain: `[u16 path_len][path]` per record (the original v1 layout)
+//! - front-coded (`INDEX_FRONT_CODED`): `[u16 shared][u16 suffix_len][suffix]`,
+//!   where `shared` bytes are taken from the previous record's path
+//! - zstd (`INDEX_ZSTD`): the whole block, magic included, is one zstd frame
+//!
+//! The footer's `index_len` and `index_hash` always describe the stored bytes.
+
+use std::borrow::Cow;
+use std::io::{Cursor, Read};
+
+#[cfg(feature = "zstd")]
+use crate::pak::codec;
+use crate::pak::error::{PakError, PakResult};
+use crate::pak::format::{Entry, INDEX_FRONT_CODED, INDEX_ZSTD, MAGIC};
+use crate::pak::io::{read_exact, read_u16, read_u32};
+use crate::pak::limits::ReadLimits;
+use crate::pak::read::{check_sorted, parse_index, read_entry_fields};
+
+/// Fixed bytes per record after the path: offset, len, raw_len, kind, hash.
+const RECORD_FIELDS_LEN: u64 = 8 + 8 + 8 + 1 + 32;
+
+/// Smallest plain record (empty path).
+pub(crate) const PLAIN_RECORD_MIN: u64 = 2 + RECORD_FIELDS_LEN;
+
+/// Smallest front-coded record (empty suffix).
+const FRONT_CODED_RECORD_MIN: u64 = 2 + 2 + RECORD_FIELDS_LEN;
+
+/// Check a declared record `count` against the limits and against the bytes
+/// left in the block, then allocate room for the records.
+pub(crate) fn with_capacity_for(
+    count: u32,
+    cur: &Cursor<&[u8]>,
+    record_min: u64,
+    limits: &ReadLimits,
+) -> PakResult<Vec<Entry>> {
+    if count > limits.max_entries {
+        return Err(PakError::Invalid(format!(
+            "index declares {count} entries, over the limit of {}",
+            limits.max_entries
+        )));
+    }
+    let left = (cur.get_ref().len() as u64).saturating_sub(cur.position());
+    if u64::from(count) * record_min > left {
+        return Err(PakError::Invalid(format!(
+            "index declares {count} entries but holds only {left} bytes of records"
+        )));
+    }
+    Ok(Vec::with_capacity(count as usize))
+}
+
+/// Human-readable name of an index encoding, e.g. `front_coded+zstd`.
+pub(crate) fn encoding_name(flags: u32) -> String {
+    let mut parts = Vec::new();
+    if flags & INDEX_FRONT_CODED != 0 {
+        parts.push("front_coded");
+    }
+    if flags & INDEX_ZSTD != 0 {
+        parts.push("zstd");
+    }
+    if parts.is_empty() {
+        "plain".into()
+    } else {
+        parts.join("+")
+    }
+}
+
+/// Size the index would take in the plain encoding.
+pub(crate) fn plain_len(entries: &[Entry]) -> u64 {
+    let records: u64 = entries
+        .iter()
+        .map(|e| 2 + e.path.len() as u64 + RECORD_FIELDS_LEN)
+        .sum();
+    MAGIC.len() as u64 + 4 + records
+}
+
+fn u16_len(len: usize, path: &str) -> PakResult<u16> {
+    u16::try_from(len).map_err(|_| PakError::Invalid(format!("path too long: {path}")))
+}
+
+fn shared_prefix(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b).take_while(|(x, y)| x == y).count()
+}
+
+/// Encode a sorted index for storage with the given footer `flags`.
+pub(crate) fn encode(entries: &[Entry], flags: u32, zstd_level: i32) -> PakResult<Vec<u8>> {
+    let mut buf: Vec<u8> = Vec::new();
+    buf.extend_from_slice(&MAGIC);
+    buf.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+
+    let mut prev: &[u8] = &[];
+    for e in entries {
+        let p = e.path.as_bytes();
+        u16_len(p.len(), &e.path)?;
+        if flags & INDEX_FRONT_CODED != 0 {
+            let shared = shared_prefix(prev, p).min(u16::MAX as usize);
+            buf.extend_from_slice(&(shared as u16).to_le_bytes());
+            buf.extend_from_slice(&u16_len(p.len() - shared, &e.path)?.to_le_bytes());
+            buf.extend_from_slice(&p[shared..]);
+            prev = p;
+        } else {
+            buf.extend_from_slice(&(p.len() as u16).to_le_bytes());
+            buf.extend_from_slice(p);
+        }
+        buf.extend_from_slice(&e.payload_offset.to_le_bytes());
+        buf.extend_from_slice(&e.payload_len.to_le_bytes());
+        buf.extend_from_slice(&e.raw_len.to_le_bytes());
+        buf.push(e.payload_kind);
+        buf.extend_from_slice(&e.raw_hash);
+    }
+
+    if flags & INDEX_ZSTD != 0 {
+        #[cfg(feature = "zstd")]
+        {
+            buf = zstd::encode_all(&buf[..], zstd_level)?;
+        }
+        #[cfg(not(feature = "zstd"))]
+        {
+            let _ = zstd_level;
+            return Err(PakError::NoZstd);
+        }
+    }
+    Ok(buf)
+}
+
+fn parse_front_coded(index_buf: &[u8], limits: &ReadLimits) -> PakResult<Vec<Entry>> {
+    let mut cur = Cursor::new(index_buf);
+
+    let magic = read_exact::<8>(&mut cur)?;
+    if magic != MAGIC {
+        return Err(PakError::Invalid("bad index magic".into()));
+    }
+
+    let count = read_u32(&mut cur)?;
+    let mut out = with_capacity_for(count, &cur, FRONT_CODED_RECORD_MIN, limits)?;
+    let mut prev: Vec<u8> = Vec::new();
+
+    for _ in 0..count {
+        out.push(read_front_coded_entry(&mut cur, &mut prev)?);
+    }
+
+    check_sorted(&out)?;
+    Ok(out)
+}
+
+/// Decode one front-coded record at the cursor position. `prev` holds the
+/// previous record's path and is updated to this one's.
+pub(crate) fn read_front_coded_entry(r: &mut dyn Read, prev: &mut Vec<u8>) -> PakResult<Entry> {
+    let shared = read_u16(r)? as usize;
+    let suffix_len = read_u16(r)? as usize;
+    if shared > prev.len() {
+        return Err(PakError::Invalid(
+            "front-coded prefix longer than previous path".into(),
+        ));
+    }
+    if shared + suffix_len > u16::MAX as usize {
+        return Err(PakError::Invalid("path too long".into()));
+    }
+    let mut path_bytes = prev[..shared].to_vec();
+    path_bytes.resize(shared + suffix_len, 0);
+    r.read_exact(&mut path_bytes[shared..])?;
+    prev.clone_from(&path_bytes);
+
+    let path =
+        String::from_utf8(path_bytes).map_err(|_| PakError::Invalid("path is not utf8".into()))?;
+    read_entry_fields(r, path)
+}
+
+fn decompress(stored: &[u8], limits: &ReadLimits) -> PakResult<Vec<u8>> {
+    #[cfg(feature = "zstd")]
+    {
+        codec::decode(codec::ZSTD, stored, limits.max_index_len, "index")
+    }
+    #[cfg(not(feature = "zstd"))]
+    {
+        let _ = (stored, limits);
+        Err(PakError::NoZstd)
+    }
+}
+
+/// Decode a stored index block according to the footer `flags`.
+pub(crate) fn decode(stored: &[u8], flags: u32, limits: &ReadLimits) -> PakResult<Vec<Entry>> {
+    let unknown = flags & !(INDEX_FRONT_CODED | INDEX_ZSTD);
+    if unknown != 0 {
+        return Err(PakError::Invalid(format!(
+            "unsupported index flags {unknown:#x}"
+        )));
+    }
+
+    let block: Cow<[u8]> = if flags & INDEX_ZSTD != 0 {
+        Cow::Owned(decompress(stored, limits)?)
+    } else {
+        Cow::Borrowed(stored)
+    };
+
+    let entries = if flags & INDEX_FRONT_CODED != 0 {
+        parse_front_coded(&block, limits)?
+    } else {
+        parse_index(&block, limits)?
+    };
+
+    if let Some(e) = entries.iter().find(|e| e.raw_len > limits.max_raw_len) {
+        return Err(PakError::Invalid(format!(
+            "{} is {} bytes, over the limit of {}",
+            e.path, e.raw_len, limits.max_raw_len
+        )));
+    }
+    Ok(entries)
+}
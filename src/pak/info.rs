@@ -0,0 +1,123 @@
+#![forbid(unsafe_code)]
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::{Seek, SeekFrom};
+use std::path::Path;
+
+use crate::pak::codec;
+use crate::pak::error::PakResult;
+use crate::pak::format::{Entry, Footer, MAGIC};
+use crate::pak::index;
+use crate::pak::io::read_exact;
+use crate::pak::limits::ReadLimits;
+use crate::pak::read::{open_pak, read_footer};
+use crate::pak::stats::EntryStat;
+
+/// Header, footer and layout summary of a pak.
+#[derive(Debug, Clone, Serialize)]
+pub struct PakInfo {
+    pub file_len: u64,
+    /// Version byte of the header magic (`NEPAK\x01...` is v1).
+    pub format_version: u8,
+    pub footer: Footer,
+    pub entries: u64,
+    pub raw_bytes: u64,
+    pub payload_bytes: u64,
+    /// payload / raw over the whole pak (1.0 = stored).
+    pub ratio: f64,
+    /// Entry count per payload kind.
+    pub payload_kinds: BTreeMap<String, u64>,
+    pub smallest: Option<EntryStat>,
+    pub largest: Option<EntryStat>,
+    /// Bytes in the payload area not covered by any payload.
+    pub slack_bytes: u64,
+    /// Index encoding from the footer flags, e.g. `plain` or `front_coded+zstd`.
+    pub index_encoding: String,
+    /// Size the index would have in the plain encoding.
+    pub index_plain_len: u64,
+    /// `index_plain_len - footer.index_len` (negative if the encoding costs space).
+    pub index_saved_bytes: i64,
+}
+
+pub fn info(pak: &Path, limits: &ReadLimits) -> PakResult<PakInfo> {
+    let (mut f, entries) = open_pak(pak, limits)?;
+    let footer = read_footer(&mut f)?;
+    let file_len = f.metadata()?.len();
+    f.seek(SeekFrom::Start(0))?;
+    let header = read_exact::<8>(&mut f)?;
+
+    let raw_bytes = entries
+        .iter()
+        .map(|e| e.raw_len)
+        .fold(0, u64::saturating_add);
+    let payload_bytes = entries
+        .iter()
+        .map(|e| e.payload_len)
+        .fold(0, u64::saturating_add);
+
+    let mut payload_kinds: BTreeMap<String, u64> = BTreeMap::new();
+    for e in &entries {
+        *payload_kinds
+            .entry(codec::name_of(e.payload_kind))
+            .or_default() += 1;
+    }
+
+    let stat = |e: &Entry| EntryStat {
+        path: e.path.clone(),
+        raw: e.raw_len,
+        payload: e.payload_len,
+        ratio: if e.raw_len == 0 {
+            1.0
+        } else {
+            e.payload_len as f64 / e.raw_len as f64
+        },
+    };
+    let smallest = entries.iter().min_by_key(|e| e.raw_len).map(stat);
+    let largest = entries.iter().max_by_key(|e| e.raw_len).map(stat);
+
+    // Slack: everything between the header and the index that no payload covers.
+    let mut ranges: Vec<(u64, u64)> = entries
+        .iter()
+        .map(|e| {
+            (
+                e.payload_offset,
+                e.payload_offset.saturating_add(e.payload_len),
+            )
+        })
+        .collect();
+    ranges.sort_unstable();
+    let mut covered = 0u64;
+    let mut cursor = MAGIC.len() as u64;
+    for (start, end) in ranges {
+        let start = start.max(cursor);
+        let end = end.min(footer.index_offset);
+        if end > start {
+            covered += end - start;
+            cursor = end;
+        }
+    }
+    let payload_area = footer.index_offset.saturating_sub(MAGIC.len() as u64);
+    let index_plain_len = index::plain_len(&entries);
+
+    Ok(PakInfo {
+        file_len,
+        format_version: header[5],
+        footer,
+        entries: entries.len() as u64,
+        raw_bytes,
+        payload_bytes,
+        ratio: if raw_bytes == 0 {
+            1.0
+        } else {
+            payload_bytes as f64 / raw_bytes as f64
+        },
+        payload_kinds,
+        smallest,
+        largest,
+        slack_bytes: payload_area.saturating_sub(covered),
+        index_encoding: index::encoding_name(footer.flags),
+        index_plain_len,
+        index_saved_bytes: index_plain_len as i64 - footer.index_len as i64,
+    })
+}
@@ -0,0 +1,36 @@
+#![forbid(unsafe_code)]
+
+//! Resource limits for reading paks from untrusted sources.
+
+/// Upper bounds checked while reading a pak, before anything is allocated from
+/// sizes stored in the file.
+///
+/// The defaults accept any pak a normal build produces. Lower them for paks
+/// from untrusted sources, such as mods or downloads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadLimits {
+    /// Largest index block, both as stored and after zstd decompression.
+    pub max_index_len: u64,
+    /// Most index records.
+    pub max_entries: u32,
+    /// Largest decompressed entry. Entries whose `raw_len` is larger are
+    /// rejected when the index is read.
+    pub max_raw_len: u64,
+}
+
+impl Default for ReadLimits {
+    fn default() -> Self {
+        Self {
+            max_index_len: 256 << 20,
+            max_entries: 1 << 22,
+            max_raw_len: 4 << 30,
+        }
+    }
+}
+
+/// Capacity to reserve for `len` bytes declared by the file: buffers start at
+/// no more than this and grow only as real data arrives.
+pub(crate) fn initial_capacity(len: u64) -> usize {
+    const MAX: u64 = 1 << 20;
+    len.min(MAX) as usize
+}
@@ -1,16 +1,74 @@
 #![forbid(unsafe_code)]
 
+#[cfg(feature = "async")]
+mod async_read;
+mod atomic;
 mod build;
+mod buildscript;
+mod cache;
+mod cancel;
+mod check;
+mod codec;
+mod depfile;
+mod diff;
+mod embed;
 mod error;
+mod explain;
 mod format;
+#[cfg(fuzzing)]
+#[doc(hidden)]
+pub mod fuzz;
+mod index;
+mod info;
 mod io;
+mod limits;
 mod ops;
 mod path;
+mod portability;
+mod progress;
 mod read;
+mod reader;
+mod salvage;
+mod stats;
+mod transform;
+mod verify;
+#[cfg(feature = "watch")]
+mod watch;
 
-pub use build::{BuildProgress, BuildStage};
-
+#[cfg(feature = "async")]
+pub use async_read::{AsyncPak, EntryStream};
+pub use build::{BuildOptions, BuildSummary, InputRoot};
+pub use buildscript::{cargo_build, cargo_build_dir, cargo_build_manifest};
+pub use cache::BuildCache;
+pub use cancel::CancelToken;
+pub use check::{CheckReport, FirstDifference};
+pub use codec::{codec_by_id, codec_by_name, codecs, register_codec, Codec, CUSTOM_CODEC_IDS};
+pub use depfile::write_depfile;
+pub use diff::{DiffEntry, DiffKind, DiffTotals, PakDiff};
+pub use embed::EmbeddedPak;
 pub use error::{PakError, PakResult};
-pub use format::{EntryInfo, FOOTER_MAGIC, MAGIC};
+pub use explain::LayoutField;
+pub use format::{
+    EntryInfo, Footer, FOOTER_LEN, FOOTER_MAGIC, INDEX_FRONT_CODED, INDEX_ZSTD, MAGIC,
+};
+pub use info::PakInfo;
+pub use limits::ReadLimits;
+pub use portability::{PortabilityIssue, PortabilityKind, PortabilityMode};
+pub use progress::{Progress, Stage};
+pub use reader::PakFile;
+pub use salvage::{LostEntry, SalvageReport, SalvageSource, SalvagedEntry};
+pub use stats::{DuplicateGroup, EntryStat, GroupStats, PakStats, StatsOptions};
+pub use transform::{
+    builtin_transform, load_transforms, CommandTransform, CrlfToLf, JsonMinify, StripBom,
+    Transform, TransformError, TransformRule, BUILTIN_TRANSFORMS,
+};
+pub use verify::{VerifyIssue, VerifyIssueKind, VerifyReport};
+#[cfg(feature = "watch")]
+pub use watch::{watch, WatchEvent};
 
-pub use ops::{build, build_with_progress, entries, extract, list, verify};
\ No newline at end of file
+pub use ops::{
+    build, build_incremental, build_with_progress, check, diff, entries, entries_with_limits,
+    entry_bytes, entry_payload, explain, extract, extract_with_limits, extract_with_progress, info,
+    info_with_limits, salvage, salvage_rebuild, stats, verify, verify_with_limits,
+    verify_with_progress,
+};
@@ -1,41 +1,64 @@
 #![forbid(unsafe_code)]
 
-use blake3::Hasher;
 use std::fs::File;
-use std::io::{Read, Seek, SeekFrom};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use crate::pak::build::{build as build_impl, build_with_progress as build_with_progress_impl, BuildProgress};
+use crate::pak::build::{
+    build as build_impl, build_incremental as build_incremental_impl,
+    build_with_progress as build_with_progress_impl, BuildOptions, BuildSummary,
+};
+use crate::pak::cache::BuildCache;
+use crate::pak::cancel::CancelToken;
+use crate::pak::check::{check as check_impl, CheckReport};
+use crate::pak::diff::{diff as diff_impl, PakDiff};
 use crate::pak::error::{PakError, PakResult};
-use crate::pak::format::{EntryInfo, PayloadKind, MAGIC};
-use crate::pak::io::{hex32, read_exact};
-use crate::pak::read::read_index;
+use crate::pak::explain::{explain as explain_impl, LayoutField};
+use crate::pak::format::{Entry, EntryInfo, MAGIC};
+use crate::pak::info::{info as info_impl, PakInfo};
+use crate::pak::io::read_exact;
+use crate::pak::limits::ReadLimits;
+use crate::pak::path::safe_join_tracked;
+use crate::pak::progress::{Progress, Reporter, Stage};
+use crate::pak::read::{decode_payload, open_pak, read_index, read_payload};
+use crate::pak::salvage::{salvage as salvage_impl, SalvageReport};
+use crate::pak::stats::{stats as stats_impl, PakStats, StatsOptions};
+use crate::pak::verify::{verify as verify_impl, VerifyReport};
 
-pub fn build(
-    input: &Path,
-    output: &Path,
-    prefix: &str,
-    excludes: &[String],
-    compress: bool,
-    zstd_level: i32,
-) -> PakResult<()> {
-    build_impl(input, output, prefix, excludes, compress, zstd_level)
+pub fn build(opts: &BuildOptions, output: &Path) -> PakResult<BuildSummary> {
+    build_impl(opts, output)
 }
 
 pub fn build_with_progress(
-    input: &Path,
+    opts: &BuildOptions,
     output: &Path,
-    prefix: &str,
-    excludes: &[String],
-    compress: bool,
-    zstd_level: i32,
-    progress: impl FnMut(BuildProgress),
-) -> PakResult<()> {
-    build_with_progress_impl(input, output, prefix, excludes, compress, zstd_level, progress)
+    cancel: &CancelToken,
+    progress: impl FnMut(Progress),
+) -> PakResult<BuildSummary> {
+    build_with_progress_impl(opts, output, cancel, progress)
+}
+
+pub fn build_incremental(
+    opts: &BuildOptions,
+    output: &Path,
+    cache: &mut BuildCache,
+    cancel: &CancelToken,
+    progress: impl FnMut(Progress),
+) -> PakResult<BuildSummary> {
+    build_incremental_impl(opts, output, cache, cancel, progress)
+}
+
+/// Rebuild `opts` in memory and compare it with `existing` (reproducibility check).
+pub fn check(opts: &BuildOptions, existing: &Path) -> PakResult<CheckReport> {
+    check_impl(opts, existing)
 }
 
 /// Read pak index entries (without extracting payloads).
 pub fn entries(pak: &Path) -> PakResult<Vec<EntryInfo>> {
+    entries_with_limits(pak, &ReadLimits::default())
+}
+
+/// Like [`entries`], rejecting indexes that exceed `limits`.
+pub fn entries_with_limits(pak: &Path, limits: &ReadLimits) -> PakResult<Vec<EntryInfo>> {
     let mut f = File::open(pak)?;
 
     let head = read_exact::<8>(&mut f)?;
@@ -43,51 +66,117 @@ pub fn entries(pak: &Path) -> PakResult<Vec<EntryInfo>> {
         return Err(PakError::Invalid("bad header magic".into()));
     }
 
-    let entries = read_index(&mut f)?;
-    Ok(entries
-        .into_iter()
-        .map(|e| EntryInfo {
-            path: e.path,
-            payload_offset: e.payload_offset,
-            payload_len: e.payload_len,
-            raw_len: e.raw_len,
-            payload_kind: match e.payload_kind {
-                PayloadKind::Raw => "raw",
-                PayloadKind::Zstd => "zstd",
-            },
-            raw_hash_hex: hex32(&e.raw_hash),
-        })
-        .collect())
-}
-
-pub fn list(pak: &Path, verbose: bool) -> PakResult<()> {
-    let mut f = File::open(pak)?;
+    let entries = read_index(&mut f, limits)?;
+    Ok(entries.iter().map(Entry::info).collect())
+}
 
-    let head = read_exact::<8>(&mut f)?;
-    if head != MAGIC {
-        return Err(PakError::Invalid("bad header magic".into()));
-    }
+fn find_entry(pak: &Path, path: &str) -> PakResult<(File, Entry)> {
+    let (f, mut entries) = open_pak(pak, &ReadLimits::default())?;
+    let i = entries
+        .binary_search_by(|e| e.path.as_bytes().cmp(path.as_bytes()))
+        .map_err(|_| PakError::NotFound(path.to_string()))?;
+    Ok((f, entries.swap_remove(i)))
+}
 
-    let entries = read_index(&mut f)?;
-    for e in entries {
-        if verbose {
-            println!(
-                "{}  off={} len={} raw={} kind={:?} hash={}",
-                e.path,
-                e.payload_offset,
-                e.payload_len,
-                e.raw_len,
-                e.payload_kind,
-                hex32(&e.raw_hash)
-            );
-        } else {
-            println!("{}", e.path);
+/// Stored bytes of one entry, exactly as they are in the pak (possibly compressed).
+pub fn entry_payload(pak: &Path, path: &str) -> PakResult<Vec<u8>> {
+    let (mut f, e) = find_entry(pak, path)?;
+    read_payload(&mut f, &e)
+}
+
+/// Decompressed, hash-verified bytes of one entry.
+pub fn entry_bytes(pak: &Path, path: &str) -> PakResult<Vec<u8>> {
+    let (mut f, e) = find_entry(pak, path)?;
+    let payload = read_payload(&mut f, &e)?;
+    decode_payload(&e, payload)
+}
+
+pub fn extract(pak: &Path, output: &Path, filter: &[String]) -> PakResult<()> {
+    extract_with_progress(pak, output, filter, &CancelToken::default(), |_| {})
+}
+
+/// Like [`extract`], rejecting paks whose index or entries exceed `limits`
+/// before anything is written.
+pub fn extract_with_limits(
+    pak: &Path,
+    output: &Path,
+    filter: &[String],
+    limits: &ReadLimits,
+) -> PakResult<()> {
+    extract_limited(pak, output, filter, limits, &CancelToken::default(), |_| {})
+}
+
+/// Like [`extract`], reporting progress and stopping with [`PakError::Cancelled`]
+/// once `cancel` is set. Files and directories created by a cancelled or failed
+/// run are removed again, including missing parents of `output`; files that
+/// already existed are left as written, so they may have been overwritten.
+pub fn extract_with_progress(
+    pak: &Path,
+    output: &Path,
+    filter: &[String],
+    cancel: &CancelToken,
+    progress: impl FnMut(Progress),
+) -> PakResult<()> {
+    extract_limited(
+        pak,
+        output,
+        filter,
+        &ReadLimits::default(),
+        cancel,
+        progress,
+    )
+}
+
+fn extract_limited(
+    pak: &Path,
+    output: &Path,
+    filter: &[String],
+    limits: &ReadLimits,
+    cancel: &CancelToken,
+    progress: impl FnMut(Progress),
+) -> PakResult<()> {
+    // `output` and those of its parents that `create_dir_all` will create, deepest first.
+    let missing: Vec<PathBuf> = output
+        .ancestors()
+        .take_while(|p| !p.as_os_str().is_empty() && !p.exists())
+        .map(Path::to_path_buf)
+        .collect();
+    let mut created = Vec::new();
+    let mut progress = Reporter::new(progress);
+    let res = extract_entries(
+        pak,
+        output,
+        filter,
+        limits,
+        cancel,
+        &mut progress,
+        &mut created,
+    );
+    if res.is_err() {
+        for path in created.iter().rev() {
+            // Directories are only removed when empty: the user may have added files.
+            if path.is_dir() {
+                let _ = std::fs::remove_dir(path);
+            } else {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+        for dir in &missing {
+            let _ = std::fs::remove_dir(dir);
         }
     }
-    Ok(())
+    res
 }
 
-pub fn extract(pak: &Path, output: &Path, filter: &[String]) -> PakResult<()> {
+fn extract_entries(
+    pak: &Path,
+    output: &Path,
+    filter: &[String],
+    limits: &ReadLimits,
+    cancel: &CancelToken,
+    progress: &mut Reporter<impl FnMut(Progress)>,
+    created: &mut Vec<PathBuf>,
+) -> PakResult<()> {
     let mut f = File::open(pak)?;
 
     let head = read_exact::<8>(&mut f)?;
@@ -95,98 +184,86 @@ pub fn extract(pak: &Path, output: &Path, filter: &[String]) -> PakResult<()> {
         return Err(PakError::Invalid("bad header magic".into()));
     }
 
-    let entries = read_index(&mut f)?;
+    let entries: Vec<Entry> = read_index(&mut f, limits)?
+        .into_iter()
+        .filter(|e| filter.is_empty() || filter.iter().any(|s| e.path.contains(s)))
+        .collect();
     std::fs::create_dir_all(output)?;
 
+    progress.stage(
+        Stage::Extracting,
+        entries.len() as u64,
+        entries
+            .iter()
+            .map(|e| e.raw_len)
+            .fold(0, u64::saturating_add),
+    );
     for e in entries {
-        if !filter.is_empty() && !filter.iter().any(|s| e.path.contains(s)) {
-            continue;
-        }
-
-        f.seek(SeekFrom::Start(e.payload_offset))?;
-        let mut payload = vec![0u8; e.payload_len as usize];
-        f.read_exact(&mut payload)?;
-
-        let raw = match e.payload_kind {
-            PayloadKind::Raw => payload,
-            PayloadKind::Zstd => {
-                #[cfg(feature = "zstd")]
-                {
-                    zstd::decode_all(&payload[..])?
-                }
-                #[cfg(not(feature = "zstd"))]
-                {
-                    return Err(PakError::NoZstd);
-                }
-            }
-        };
+        cancel.check()?;
 
-        let mut hasher = Hasher::new();
-        hasher.update(&raw);
-        let got: [u8; 32] = hasher.finalize().into();
-        if got != e.raw_hash {
-            return Err(PakError::Invalid(format!("hash mismatch for {}", e.path)));
-        }
+        let payload = read_payload(&mut f, &e)?;
+        let raw = decode_payload(&e, payload)?;
 
-        let out_path = output.join(e.path.replace('/', &std::path::MAIN_SEPARATOR.to_string()));
-        if let Some(parent) = out_path.parent() {
-            std::fs::create_dir_all(parent)?;
+        let out_path = safe_join_tracked(output, &e.path, created)?;
+        if std::fs::symlink_metadata(&out_path).is_err() {
+            created.push(out_path.clone());
         }
         std::fs::write(&out_path, &raw)?;
+        progress.advance(Some(&e.path), e.raw_len);
     }
 
     Ok(())
 }
 
-pub fn verify(pak: &Path) -> PakResult<()> {
-    let mut f = File::open(pak)?;
+pub fn verify(pak: &Path) -> PakResult<VerifyReport> {
+    verify_with_limits(pak, &ReadLimits::default())
+}
 
-    let head = read_exact::<8>(&mut f)?;
-    if head != MAGIC {
-        return Err(PakError::Invalid("bad header magic".into()));
-    }
+/// Like [`verify`], failing with an error instead of a report when the index
+/// exceeds `limits`.
+pub fn verify_with_limits(pak: &Path, limits: &ReadLimits) -> PakResult<VerifyReport> {
+    verify_impl(pak, limits, &CancelToken::default(), |_| {})
+}
 
-    let entries = read_index(&mut f)?;
-    let file_len = f.metadata()?.len();
+/// Like [`verify`], reporting progress and stopping with [`PakError::Cancelled`]
+/// once `cancel` is set.
+pub fn verify_with_progress(
+    pak: &Path,
+    cancel: &CancelToken,
+    progress: impl FnMut(Progress),
+) -> PakResult<VerifyReport> {
+    verify_impl(pak, &ReadLimits::default(), cancel, progress)
+}
 
-    for e in &entries {
-        if e.payload_offset < 8 {
-            return Err(PakError::Invalid(format!("payload offset under header: {}", e.path)));
-        }
-        if e.payload_offset + e.payload_len > file_len {
-            return Err(PakError::Invalid(format!("payload outside file: {}", e.path)));
-        }
+pub fn salvage(pak: &Path, output: &Path) -> PakResult<SalvageReport> {
+    salvage_impl(pak, output, None)
+}
 
-        f.seek(SeekFrom::Start(e.payload_offset))?;
-        let mut payload = vec![0u8; e.payload_len as usize];
-        f.read_exact(&mut payload)?;
-
-        let raw = match e.payload_kind {
-            PayloadKind::Raw => payload,
-            PayloadKind::Zstd => {
-                #[cfg(feature = "zstd")]
-                {
-                    zstd::decode_all(&payload[..])?
-                }
-                #[cfg(not(feature = "zstd"))]
-                {
-                    return Err(PakError::NoZstd);
-                }
-            }
-        };
+/// Like [`salvage`], then write the verified entries to a new pak at `rebuilt`.
+/// Payloads are copied as stored, so each entry keeps its original codec; carved
+/// frames and other files under `output` are not included. Nothing is written
+/// when no entry could be verified; [`SalvageReport::rebuilt`] is then false.
+pub fn salvage_rebuild(pak: &Path, output: &Path, rebuilt: &Path) -> PakResult<SalvageReport> {
+    salvage_impl(pak, output, Some(rebuilt))
+}
 
-        if raw.len() as u64 != e.raw_len {
-            return Err(PakError::Invalid(format!("raw size mismatch: {}", e.path)));
-        }
+pub fn diff(old: &Path, new: &Path) -> PakResult<PakDiff> {
+    diff_impl(old, new)
+}
 
-        let mut hasher = Hasher::new();
-        hasher.update(&raw);
-        let got: [u8; 32] = hasher.finalize().into();
-        if got != e.raw_hash {
-            return Err(PakError::Invalid(format!("hash mismatch: {}", e.path)));
-        }
-    }
+pub fn stats(pak: &Path, opts: &StatsOptions) -> PakResult<PakStats> {
+    stats_impl(pak, opts)
+}
 
-    println!("ok: {} entries", entries.len());
-    Ok(())
-}
\ No newline at end of file
+pub fn info(pak: &Path) -> PakResult<PakInfo> {
+    info_impl(pak, &ReadLimits::default())
+}
+
+/// Like [`info`], rejecting indexes that exceed `limits`.
+pub fn info_with_limits(pak: &Path, limits: &ReadLimits) -> PakResult<PakInfo> {
+    info_impl(pak, limits)
+}
+
+pub fn explain(pak: &Path) -> PakResult<Vec<LayoutField>> {
+    explain_impl(pak)
+}
@@ -1,6 +1,6 @@
 #![forbid(unsafe_code)]
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crate::pak::error::{PakError, PakResult};
 
@@ -42,5 +42,229 @@ pub fn prefixed(prefix: &str, rel: &str) -> String {
 }
 
 pub fn should_exclude(norm_path: &str, excludes: &[String]) -> bool {
-    excludes.iter().any(|e| !e.is_empty() && norm_path.contains(e))
-}
\ No newline at end of file
+    excludes
+        .iter()
+        .any(|e| !e.is_empty() && norm_path.contains(e))
+}
+/// Windows device names that cannot be used as file names (with or without extension).
+const WINDOWS_DEVICE_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+pub fn is_windows_device_name(component: &str) -> bool {
+    let stem = component.split('.').next().unwrap_or(component).trim_end();
+    WINDOWS_DEVICE_NAMES
+        .iter()
+        .any(|d| d.eq_ignore_ascii_case(stem))
+}
+
+/// Reject entry paths that could escape the extraction root or address a device.
+pub fn validate_entry_path(path: &str) -> PakResult<()> {
+    let unsafe_path = |reason| {
+        Err(PakError::UnsafePath {
+            path: path.to_string(),
+            reason,
+        })
+    };
+
+    if path.is_empty() {
+        return unsafe_path("empty path");
+    }
+    if path.contains('\0') {
+        return unsafe_path("contains NUL byte");
+    }
+    if path.contains('\\') {
+        return unsafe_path("contains backslash");
+    }
+    if path.starts_with('/') {
+        return unsafe_path("absolute path");
+    }
+
+    for comp in path.split('/') {
+        match comp {
+            "" => return unsafe_path("empty path component"),
+            "." | ".." => return unsafe_path("relative path component"),
+            _ => {}
+        }
+        if comp.contains(':') {
+            return unsafe_path("drive letter or stream separator");
+        }
+        if is_windows_device_name(comp) {
+            return unsafe_path("device name");
+        }
+    }
+
+    Ok(())
+}
+
+/// Join a validated entry path onto `root`, creating parent directories.
+///
+/// Every directory between `root` and the target must be a real directory:
+/// symlinks (pre-existing or planted by an earlier entry) are refused.
+pub fn safe_join(root: &Path, path: &str) -> PakResult<PathBuf> {
+    safe_join_tracked(root, path, &mut Vec::new())
+}
+
+/// Like [`safe_join`], appending every directory it creates to `created`.
+pub fn safe_join_tracked(
+    root: &Path,
+    path: &str,
+    created: &mut Vec<PathBuf>,
+) -> PakResult<PathBuf> {
+    validate_entry_path(path)?;
+
+    let mut out = root.to_path_buf();
+    let mut comps = path.split('/').peekable();
+    while let Some(comp) = comps.next() {
+        out.push(comp);
+        let is_last = comps.peek().is_none();
+
+        match std::fs::symlink_metadata(&out) {
+            Ok(md) if md.file_type().is_symlink() => {
+                return Err(PakError::UnsafePath {
+                    path: path.to_string(),
+                    reason: "symlink in target path",
+                });
+            }
+            Ok(md) if !is_last && !md.is_dir() => {
+                return Err(PakError::UnsafePath {
+                    path: path.to_string(),
+                    reason: "parent is not a directory",
+                });
+            }
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                if !is_last {
+                    std::fs::create_dir(&out)?;
+                    created.push(out.clone());
+                }
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reason(path: &str) -> &'static str {
+        match validate_entry_path(path) {
+            Err(PakError::UnsafePath { reason, .. }) => reason,
+            other => panic!("{path:?} accepted: {other:?}"),
+        }
+    }
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("nepak-path-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn accepts_ordinary_paths() {
+        for path in [
+            "a",
+            "a/b.txt",
+            "dir.d/file..txt",
+            "COM10",
+            "console.txt",
+            "...x",
+        ] {
+            validate_entry_path(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn rejects_parent_and_current_dir_segments() {
+        for path in ["..", "../a", "a/../../b", "a/..", "./a", "a/./b"] {
+            assert_eq!(reason(path), "relative path component", "{path}");
+        }
+        assert_eq!(reason("a//b"), "empty path component");
+        assert_eq!(reason("a/"), "empty path component");
+    }
+
+    #[test]
+    fn rejects_absolute_drive_and_unc_paths() {
+        assert_eq!(reason("/etc/passwd"), "absolute path");
+        assert_eq!(reason("//server/share/a"), "absolute path");
+        for path in ["C:/Windows/a", "C:a", "c:", "a/D:/b", "file.txt:stream"] {
+            assert_eq!(reason(path), "drive letter or stream separator", "{path}");
+        }
+        for path in [r"\\server\share\a", r"\\?\C:\a", r"C:\a", r"a\..\b"] {
+            assert_eq!(reason(path), "contains backslash", "{path}");
+        }
+    }
+
+    #[test]
+    fn rejects_nul_bytes() {
+        assert_eq!(reason("a\0b"), "contains NUL byte");
+        assert_eq!(reason("dir/\0"), "contains NUL byte");
+        assert_eq!(reason(""), "empty path");
+    }
+
+    #[test]
+    fn rejects_reserved_device_names() {
+        for path in [
+            "CON",
+            "nul",
+            "aux.txt",
+            "dir/Lpt1.log",
+            "com9",
+            "PRN .txt",
+            "a/CON/b",
+        ] {
+            assert_eq!(reason(path), "device name", "{path}");
+        }
+    }
+
+    #[test]
+    fn safe_join_creates_parents_and_reports_them() {
+        let root = scratch("join");
+        let mut created = Vec::new();
+        let out = safe_join_tracked(&root, "a/b/c.txt", &mut created).unwrap();
+        assert_eq!(out, root.join("a/b/c.txt"));
+        assert_eq!(created, [root.join("a"), root.join("a/b")]);
+
+        // Existing directories are reused and not reported again.
+        created.clear();
+        safe_join_tracked(&root, "a/d.txt", &mut created).unwrap();
+        assert!(created.is_empty());
+
+        std::fs::write(root.join("file"), b"").unwrap();
+        assert!(matches!(
+            safe_join(&root, "file/x"),
+            Err(PakError::UnsafePath {
+                reason: "parent is not a directory",
+                ..
+            })
+        ));
+        assert!(safe_join(&root, "../x").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn safe_join_refuses_symlinked_parent() {
+        let root = scratch("symlink");
+        let outside = scratch("symlink-outside");
+        std::os::unix::fs::symlink(&outside, root.join("sub")).unwrap();
+
+        for path in ["sub/x.txt", "sub/new/x.txt", "sub"] {
+            assert!(
+                matches!(
+                    safe_join(&root, path),
+                    Err(PakError::UnsafePath {
+                        reason: "symlink in target path",
+                        ..
+                    })
+                ),
+                "{path}"
+            );
+        }
+        assert_eq!(std::fs::read_dir(&outside).unwrap().count(), 0);
+    }
+}
@@ -0,0 +1,218 @@
+#![forbid(unsafe_code)]
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use unicode_normalization::{is_nfc, UnicodeNormalization};
+
+use crate::pak::path::is_windows_device_name;
+
+/// What to do when a build finds paths that do not survive other platforms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PortabilityMode {
+    /// Report issues in the build summary and keep going.
+    #[default]
+    Warn,
+    /// Fail the build if any issue is found.
+    Error,
+}
+
+impl FromStr for PortabilityMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "warn" => Ok(Self::Warn),
+            "error" => Ok(Self::Error),
+            other => Err(format!(
+                "unknown portability mode '{other}' (expected warn|error)"
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PortabilityKind {
+    /// Another path differs from this one only by case.
+    CaseCollision { other: String },
+    /// A component is a Windows device name (`con`, `aux.json`, ...).
+    ReservedName,
+    /// A component contains a character Windows does not allow.
+    ReservedChar(char),
+    /// A component ends with '.' or ' ', which Windows strips silently.
+    TrailingDotOrSpace,
+    /// The path is not in Unicode NFC form.
+    NotNfc,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PortabilityIssue {
+    pub path: String,
+    pub kind: PortabilityKind,
+}
+
+impl fmt::Display for PortabilityIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            PortabilityKind::CaseCollision { other } => {
+                write!(f, "{}: differs only by case from {}", self.path, other)
+            }
+            PortabilityKind::ReservedName => write!(f, "{}: Windows reserved name", self.path),
+            PortabilityKind::ReservedChar(c) => {
+                write!(f, "{}: Windows reserved character {:?}", self.path, c)
+            }
+            PortabilityKind::TrailingDotOrSpace => {
+                write!(f, "{}: component ends with '.' or ' '", self.path)
+            }
+            PortabilityKind::NotNfc => write!(f, "{}: not NFC-normalized", self.path),
+        }
+    }
+}
+
+pub fn to_nfc(path: &str) -> String {
+    path.nfc().collect()
+}
+
+fn is_reserved_char(c: char) -> bool {
+    matches!(c, '<' | '>' | ':' | '"' | '|' | '?' | '*') || (c as u32) < 0x20
+}
+
+/// Check sorted logical paths for names that break on Windows, macOS or
+/// case-insensitive lookups.
+pub fn check_paths<'a>(paths: impl IntoIterator<Item = &'a str>) -> Vec<PortabilityIssue> {
+    let mut issues = Vec::new();
+    // Lowercased prefix (directories and files) -> first spelling seen.
+    let mut folded: HashMap<String, String> = HashMap::new();
+
+    for path in paths {
+        let mut push = |kind| {
+            issues.push(PortabilityIssue {
+                path: path.to_string(),
+                kind,
+            })
+        };
+
+        if !is_nfc(path) {
+            push(PortabilityKind::NotNfc);
+        }
+
+        for comp in path.split('/') {
+            if is_windows_device_name(comp) {
+                push(PortabilityKind::ReservedName);
+            }
+            if let Some(c) = comp.chars().find(|&c| is_reserved_char(c)) {
+                push(PortabilityKind::ReservedChar(c));
+            }
+            if comp.ends_with(['.', ' ']) {
+                push(PortabilityKind::TrailingDotOrSpace);
+            }
+        }
+
+        let mut end = 0;
+        for comp in path.split('/') {
+            end += comp.len();
+            let prefix = &path[..end];
+            end += 1;
+
+            let key = prefix.to_lowercase();
+            match folded.get(&key) {
+                Some(seen) if seen != prefix => {
+                    push(PortabilityKind::CaseCollision {
+                        other: seen.clone(),
+                    });
+                    break;
+                }
+                Some(_) => {}
+                None => {
+                    folded.insert(key, prefix.to_string());
+                }
+            }
+        }
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(paths: &[&str]) -> Vec<(String, PortabilityKind)> {
+        check_paths(paths.iter().copied())
+            .into_iter()
+            .map(|i| (i.path, i.kind))
+            .collect()
+    }
+
+    fn collision(path: &str, other: &str) -> (String, PortabilityKind) {
+        let other = other.to_string();
+        (path.to_string(), PortabilityKind::CaseCollision { other })
+    }
+
+    #[test]
+    fn portable_paths_have_no_issues() {
+        let paths = [
+            "a.txt",
+            "icon/x",
+            "dir/console.txt",
+            "dir/file.v2.png",
+            "é.txt",
+        ];
+        assert_eq!(kinds(&paths), []);
+    }
+
+    #[test]
+    fn case_collisions_between_files_and_directories() {
+        assert_eq!(
+            kinds(&["Foo.png", "foo.png"]),
+            [collision("foo.png", "Foo.png")]
+        );
+        // A file and a directory of another case; reported once per path.
+        assert_eq!(
+            kinds(&["TEX/a.png", "Tex", "tex/b.png", "tex/c.png"]),
+            [
+                collision("Tex", "TEX"),
+                collision("tex/b.png", "TEX"),
+                collision("tex/c.png", "TEX"),
+            ]
+        );
+    }
+
+    #[test]
+    fn windows_names_and_characters() {
+        assert_eq!(
+            kinds(&["aux.json", "dir/CON", "lpt1.tar.gz/x"]),
+            [
+                ("aux.json".into(), PortabilityKind::ReservedName),
+                ("dir/CON".into(), PortabilityKind::ReservedName),
+                ("lpt1.tar.gz/x".into(), PortabilityKind::ReservedName),
+            ]
+        );
+        for c in ['<', '>', ':', '"', '|', '?', '*', '\t'] {
+            let path = format!("dir/a{c}b");
+            assert_eq!(
+                kinds(&[&path]),
+                [(path.clone(), PortabilityKind::ReservedChar(c))]
+            );
+        }
+        assert_eq!(
+            kinds(&["a./b", "c/d "]),
+            [
+                ("a./b".into(), PortabilityKind::TrailingDotOrSpace),
+                ("c/d ".into(), PortabilityKind::TrailingDotOrSpace),
+            ]
+        );
+    }
+
+    #[test]
+    fn non_nfc_paths() {
+        let decomposed = "cafe\u{301}.txt";
+        assert_eq!(
+            kinds(&[decomposed]),
+            [(decomposed.into(), PortabilityKind::NotNfc)]
+        );
+        let composed = to_nfc(decomposed);
+        assert_eq!(composed, "caf\u{e9}.txt");
+        assert_eq!(kinds(&[&composed]), []);
+    }
+}
@@ -0,0 +1,121 @@
+#![forbid(unsafe_code)]
+
+use serde::Serialize;
+use std::time::Instant;
+
+/// Phase of a long-running operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Scanning,
+    WritingPayloads,
+    WritingIndex,
+    Finalizing,
+    Extracting,
+    Verifying,
+}
+
+impl Stage {
+    #[inline]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Stage::Scanning => "Scanning",
+            Stage::WritingPayloads => "Writing payloads",
+            Stage::WritingIndex => "Writing index",
+            Stage::Finalizing => "Finalizing",
+            Stage::Extracting => "Extracting",
+            Stage::Verifying => "Verifying",
+        }
+    }
+}
+
+/// Progress event shared by build, extract and verify.
+///
+/// `done`/`total` count entries. `bytes_done`/`bytes_total` count the bytes the
+/// stage processes: raw input for build and extract, stored payload for verify.
+/// Totals are 0 while they are still unknown (e.g. during scanning).
+#[derive(Debug, Clone, Serialize)]
+pub struct Progress {
+    pub stage: Stage,
+    pub done: u64,
+    pub total: u64,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    /// Entry that was just processed.
+    pub current: Option<String>,
+    /// Milliseconds since the operation started.
+    pub elapsed_ms: u64,
+    /// Average bytes per second of the current stage so far.
+    pub bytes_per_sec: f64,
+}
+
+/// Keeps the running counters of one operation and emits [`Progress`] events.
+pub(crate) struct Reporter<F: FnMut(Progress)> {
+    callback: F,
+    started: Instant,
+    stage_started: Instant,
+    stage: Stage,
+    done: u64,
+    total: u64,
+    bytes_done: u64,
+    bytes_total: u64,
+}
+
+impl<F: FnMut(Progress)> Reporter<F> {
+    pub(crate) fn new(callback: F) -> Self {
+        let now = Instant::now();
+        Self {
+            callback,
+            started: now,
+            stage_started: now,
+            stage: Stage::Scanning,
+            done: 0,
+            total: 0,
+            bytes_done: 0,
+            bytes_total: 0,
+        }
+    }
+
+    /// Enter `stage` with fresh counters and report it.
+    pub(crate) fn stage(&mut self, stage: Stage, total: u64, bytes_total: u64) {
+        self.stage = stage;
+        self.stage_started = Instant::now();
+        self.done = 0;
+        self.total = total;
+        self.bytes_done = 0;
+        self.bytes_total = bytes_total;
+        self.emit(None);
+    }
+
+    /// Count one finished entry of `bytes` bytes.
+    pub(crate) fn advance(&mut self, current: Option<&str>, bytes: u64) {
+        self.done += 1;
+        self.bytes_done = self.bytes_done.saturating_add(bytes);
+        self.emit(current);
+    }
+
+    /// Mark the current stage as complete.
+    pub(crate) fn finish_stage(&mut self) {
+        self.done = self.total;
+        self.bytes_done = self.bytes_total;
+        self.emit(None);
+    }
+
+    fn emit(&mut self, current: Option<&str>) {
+        let stage_secs = self.stage_started.elapsed().as_secs_f64();
+        (self.callback)(Progress {
+            stage: self.stage,
+            done: self.done,
+            total: self.total,
+            bytes_done: self.bytes_done,
+            bytes_total: self.bytes_total,
+            current: current.filter(|s| !s.is_empty()).map(str::to_string),
+            elapsed_ms: self.started.elapsed().as_millis() as u64,
+            bytes_per_sec: if stage_secs > 0.0 {
+                self.bytes_done as f64 / stage_secs
+            } else {
+                0.0
+            },
+        });
+    }
+}
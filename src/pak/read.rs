@@ -1,55 +1,237 @@
 #![forbid(unsafe_code)]
 
 use blake3::Hasher;
+use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::fs::File;
 use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
 
+use crate::pak::codec;
 use crate::pak::error::{PakError, PakResult};
-use crate::pak::format::{Entry, PayloadKind, FOOTER_MAGIC, MAGIC};
+use crate::pak::format::{Entry, Footer, FOOTER_LEN, FOOTER_MAGIC, MAGIC};
+use crate::pak::index;
 use crate::pak::io::{read_exact, read_u16, read_u32, read_u64, read_u8};
+use crate::pak::limits::{initial_capacity, ReadLimits};
 
-pub(crate) fn read_footer(file: &mut File) -> PakResult<(u64, u64, u32)> {
-    let size = file.metadata()?.len();
-    let footer_len = 8 + 8 + 8 + 4 + 4;
-    if size < footer_len {
+/// Open a pak, check its header magic and read the index.
+pub(crate) fn open_pak(pak: &Path, limits: &ReadLimits) -> PakResult<(File, Vec<Entry>)> {
+    let mut f = File::open(pak)?;
+
+    let head = read_exact::<8>(&mut f)?;
+    if head != MAGIC {
+        return Err(PakError::Invalid("bad header magic".into()));
+    }
+
+    let entries = read_index(&mut f, limits)?;
+    Ok((f, entries))
+}
+
+pub(crate) fn read_footer(file: &mut (impl Read + Seek)) -> PakResult<Footer> {
+    let size = file.seek(SeekFrom::End(0))?;
+    if size < FOOTER_LEN {
         return Err(PakError::Invalid("file too small".into()));
     }
-    file.seek(SeekFrom::End(-(footer_len as i64)))?;
+    file.seek(SeekFrom::End(-(FOOTER_LEN as i64)))?;
+    parse_footer(&read_exact::<{ FOOTER_LEN as usize }>(file)?)
+}
+
+/// Decode the fixed-size footer block (starting at its FOOTER_MAGIC).
+pub(crate) fn parse_footer(buf: &[u8; FOOTER_LEN as usize]) -> PakResult<Footer> {
+    let mut cur = std::io::Cursor::new(&buf[..]);
 
-    let magic = read_exact::<8>(file)?;
+    let magic = read_exact::<8>(&mut cur)?;
     if magic != FOOTER_MAGIC {
         return Err(PakError::Invalid("bad footer magic".into()));
     }
 
-    let index_offset = read_u64(file)?;
-    let index_len = read_u64(file)?;
-    let index_hash = read_u32(file)?;
-    let _reserved = read_u32(file)?;
+    let index_offset = read_u64(&mut cur)?;
+    let index_len = read_u64(&mut cur)?;
+    let index_hash = read_u32(&mut cur)?;
+    let flags = read_u32(&mut cur)?;
+
+    Ok(Footer {
+        index_offset,
+        index_len,
+        index_hash,
+        flags,
+    })
+}
+
+/// Check the footer's truncated blake3 against the stored index bytes, decode
+/// them, and reject entries whose payload lies outside the payload area.
+pub(crate) fn parse_checked_index(
+    index_buf: &[u8],
+    footer: &Footer,
+    limits: &ReadLimits,
+) -> PakResult<Vec<Entry>> {
+    let entries = parse_hashed_index(index_buf, footer, limits)?;
+    check_payload_ranges(&entries, footer.index_offset)?;
+    Ok(entries)
+}
+
+/// Like [`parse_checked_index`], but payload ranges are left for the caller
+/// (`verify` reports them per entry).
+fn parse_hashed_index(
+    index_buf: &[u8],
+    footer: &Footer,
+    limits: &ReadLimits,
+) -> PakResult<Vec<Entry>> {
+    check_index_len(footer.index_len, limits)?;
+    let mut hasher = Hasher::new();
+    hasher.update(index_buf);
+    let full: [u8; 32] = hasher.finalize().into();
+    let got_u32 = u32::from_le_bytes([full[0], full[1], full[2], full[3]]);
+    // A fuzzer can't forge blake3; skip the check so its mutations reach the decoder.
+    if got_u32 != footer.index_hash && !cfg!(fuzzing) {
+        return Err(PakError::Invalid("index hash mismatch".into()));
+    }
 
-    Ok((index_offset, index_len, index_hash))
+    index::decode(index_buf, footer.flags, limits)
 }
 
-pub(crate) fn read_index(file: &mut File) -> PakResult<Vec<Entry>> {
-    let (index_offset, index_len, index_hash_u32) = read_footer(file)?;
-    let file_len = file.metadata()?.len();
+pub(crate) fn check_index_len(index_len: u64, limits: &ReadLimits) -> PakResult<()> {
+    if index_len > limits.max_index_len {
+        return Err(PakError::Invalid(format!(
+            "index of {index_len} bytes is over the limit of {}",
+            limits.max_index_len
+        )));
+    }
+    Ok(())
+}
 
-    if index_offset + index_len > file_len {
+/// Check that the index lies between the header and the footer of a
+/// `file_len`-byte pak, without overflowing on hostile offsets.
+pub(crate) fn check_index_range(footer: &Footer, file_len: u64) -> PakResult<()> {
+    let inside = footer.index_offset >= MAGIC.len() as u64
+        && footer
+            .index_offset
+            .checked_add(footer.index_len)
+            .is_some_and(|end| end <= file_len.saturating_sub(FOOTER_LEN));
+    if !inside {
         return Err(PakError::Invalid("index outside file".into()));
     }
+    Ok(())
+}
+
+/// Whether `e`'s payload lies between the header and the index, computed
+/// without overflowing on hostile offsets.
+pub(crate) fn in_payload_area(e: &Entry, index_offset: u64) -> bool {
+    e.payload_offset >= MAGIC.len() as u64
+        && e.payload_offset
+            .checked_add(e.payload_len)
+            .is_some_and(|end| end <= index_offset)
+}
+
+fn check_payload_ranges(entries: &[Entry], index_offset: u64) -> PakResult<()> {
+    match entries.iter().find(|e| !in_payload_area(e, index_offset)) {
+        Some(e) => Err(PakError::Invalid(format!(
+            "payload of {} lies outside the payload area",
+            e.path
+        ))),
+        None => Ok(()),
+    }
+}
 
-    file.seek(SeekFrom::Start(index_offset))?;
-    let mut index_buf = vec![0u8; index_len as usize];
+pub(crate) fn read_index(
+    file: &mut (impl Read + Seek),
+    limits: &ReadLimits,
+) -> PakResult<Vec<Entry>> {
+    let (footer, entries) = read_index_unranged(file, limits)?;
+    check_payload_ranges(&entries, footer.index_offset)?;
+    Ok(entries)
+}
+
+/// Like [`read_index`], but entries whose payload lies outside the payload
+/// area are kept, for `verify` to report.
+pub(crate) fn read_index_unranged(
+    file: &mut (impl Read + Seek),
+    limits: &ReadLimits,
+) -> PakResult<(Footer, Vec<Entry>)> {
+    let file_len = file.seek(SeekFrom::End(0))?;
+    let footer = read_footer(file)?;
+    check_index_range(&footer, file_len)?;
+    check_index_len(footer.index_len, limits)?;
+
+    file.seek(SeekFrom::Start(footer.index_offset))?;
+    let mut index_buf = vec![0u8; footer.index_len as usize];
     file.read_exact(&mut index_buf)?;
 
+    let entries = parse_hashed_index(&index_buf, &footer, limits)?;
+    Ok((footer, entries))
+}
+
+/// Stored bytes of one entry. The buffer grows with the data actually read, so
+/// a bogus `payload_len` fails with "truncated" instead of a huge allocation.
+pub(crate) fn read_payload(f: &mut (impl Read + Seek), e: &Entry) -> PakResult<Vec<u8>> {
+    f.seek(SeekFrom::Start(e.payload_offset))?;
+    let mut payload = Vec::with_capacity(initial_capacity(e.payload_len));
+    f.take(e.payload_len).read_to_end(&mut payload)?;
+    if payload.len() as u64 != e.payload_len {
+        return Err(PakError::Invalid(format!(
+            "truncated payload for {}",
+            e.path
+        )));
+    }
+    Ok(payload)
+}
+
+/// Decompress a stored payload and check it against the entry's length and hash.
+pub(crate) fn decode_payload(e: &Entry, payload: Vec<u8>) -> PakResult<Vec<u8>> {
+    decode_payload_cow(e, Cow::Owned(payload)).map(Cow::into_owned)
+}
+
+/// Like [`decode_payload`], but raw payloads are passed through without a copy.
+pub(crate) fn decode_payload_cow<'a>(
+    e: &Entry,
+    payload: Cow<'a, [u8]>,
+) -> PakResult<Cow<'a, [u8]>> {
+    let raw = match e.payload_kind {
+        codec::RAW => payload,
+        kind => Cow::Owned(codec::decode(kind, &payload, e.raw_len, &e.path)?),
+    };
+
     let mut hasher = Hasher::new();
-    hasher.update(&index_buf);
-    let full: [u8; 32] = hasher.finalize().into();
-    let got_u32 = u32::from_le_bytes([full[0], full[1], full[2], full[3]]);
-    if got_u32 != index_hash_u32 {
-        return Err(PakError::Invalid("index hash mismatch".into()));
+    hasher.update(&raw);
+    let got: [u8; 32] = hasher.finalize().into();
+    if got != e.raw_hash || raw.len() as u64 != e.raw_len {
+        return Err(PakError::Invalid(format!("hash mismatch for {}", e.path)));
     }
+    Ok(raw)
+}
+
+/// Decode one plain index record at the cursor position.
+pub(crate) fn read_entry(r: &mut dyn Read) -> PakResult<Entry> {
+    let path_len = read_u16(r)? as usize;
+    let mut path_bytes = vec![0u8; path_len];
+    r.read_exact(&mut path_bytes)?;
+    let path =
+        String::from_utf8(path_bytes).map_err(|_| PakError::Invalid("path is not utf8".into()))?;
+    read_entry_fields(r, path)
+}
+
+/// Decode the fixed fields that follow a record's path (shared by all index encodings).
+pub(crate) fn read_entry_fields(r: &mut dyn Read, path: String) -> PakResult<Entry> {
+    let payload_offset = read_u64(r)?;
+    let payload_len = read_u64(r)?;
+    let raw_len = read_u64(r)?;
+    // Any id is accepted here; entries are only rejected when a missing codec
+    // is needed to decode them.
+    let kind = read_u8(r)?;
+    let raw_hash = read_exact::<32>(r)?;
+
+    Ok(Entry {
+        path,
+        payload_offset,
+        payload_len,
+        raw_len,
+        raw_hash,
+        payload_kind: kind,
+    })
+}
 
+/// Decode a complete index block (starting at its MAGIC).
+pub(crate) fn parse_index(index_buf: &[u8], limits: &ReadLimits) -> PakResult<Vec<Entry>> {
     let mut cur = std::io::Cursor::new(index_buf);
 
     let magic = read_exact::<8>(&mut cur)?;
@@ -57,41 +239,23 @@ pub(crate) fn read_index(file: &mut File) -> PakResult<Vec<Entry>> {
         return Err(PakError::Invalid("bad index magic".into()));
     }
 
-    let count = read_u32(&mut cur)? as usize;
-    let mut out: Vec<Entry> = Vec::with_capacity(count);
+    let count = read_u32(&mut cur)?;
+    let mut out: Vec<Entry> =
+        index::with_capacity_for(count, &cur, index::PLAIN_RECORD_MIN, limits)?;
 
     for _ in 0..count {
-        let path_len = read_u16(&mut cur)? as usize;
-        let mut path_bytes = vec![0u8; path_len];
-        cur.read_exact(&mut path_bytes)?;
-        let path = String::from_utf8(path_bytes)
-            .map_err(|_| PakError::Invalid("path is not utf8".into()))?;
-
-        let payload_offset = read_u64(&mut cur)?;
-        let payload_len = read_u64(&mut cur)?;
-        let raw_len = read_u64(&mut cur)?;
-        let kind = match read_u8(&mut cur)? {
-            0 => PayloadKind::Raw,
-            1 => PayloadKind::Zstd,
-            other => return Err(PakError::Invalid(format!("unknown payload kind {other}"))),
-        };
-        let raw_hash = read_exact::<32>(&mut cur)?;
-
-        out.push(Entry {
-            path,
-            payload_offset,
-            payload_len,
-            raw_len,
-            raw_hash,
-            payload_kind: kind,
-        });
+        out.push(read_entry(&mut cur)?);
     }
 
-    for w in out.windows(2) {
+    check_sorted(&out)?;
+    Ok(out)
+}
+
+pub(crate) fn check_sorted(entries: &[Entry]) -> PakResult<()> {
+    for w in entries.windows(2) {
         if w[0].path.as_bytes().cmp(w[1].path.as_bytes()) == Ordering::Greater {
             return Err(PakError::Invalid("index is not sorted".into()));
         }
     }
-
-    Ok(out)
-}
\ No newline at end of file
+    Ok(())
+}
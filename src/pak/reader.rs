@@ -0,0 +1,54 @@
+#![forbid(unsafe_code)]
+
+//! Sync reader that keeps a pak open between reads.
+
+use std::fs::File;
+use std::path::Path;
+
+use crate::pak::error::{PakError, PakResult};
+use crate::pak::format::{Entry, EntryInfo};
+use crate::pak::limits::ReadLimits;
+use crate::pak::read::{decode_payload, open_pak, read_payload};
+
+/// An opened pak whose index has been read and checked.
+///
+/// Unlike [`entry_bytes`](crate::pak::entry_bytes), which opens the file and
+/// parses the index on every call, reads go through one file handle. Reads take
+/// `&mut self`; open one `PakFile` per thread for concurrent access.
+#[derive(Debug)]
+pub struct PakFile {
+    file: File,
+    entries: Vec<Entry>,
+}
+
+impl PakFile {
+    /// Open a pak, check its header magic and read the index.
+    pub fn open(pak: impl AsRef<Path>) -> PakResult<Self> {
+        Self::open_with_limits(pak, &ReadLimits::default())
+    }
+
+    /// [`open`](Self::open) with custom limits, for paks from untrusted sources.
+    pub fn open_with_limits(pak: impl AsRef<Path>, limits: &ReadLimits) -> PakResult<Self> {
+        let (file, entries) = open_pak(pak.as_ref(), limits)?;
+        Ok(Self { file, entries })
+    }
+
+    /// Index entries, sorted by path.
+    pub fn entries(&self) -> Vec<EntryInfo> {
+        self.entries.iter().map(Entry::info).collect()
+    }
+
+    fn find(&self, path: &str) -> PakResult<&Entry> {
+        self.entries
+            .binary_search_by(|e| e.path.as_bytes().cmp(path.as_bytes()))
+            .map(|i| &self.entries[i])
+            .map_err(|_| PakError::NotFound(path.to_string()))
+    }
+
+    /// Decompressed, hash-verified bytes of one entry.
+    pub fn read(&mut self, path: &str) -> PakResult<Vec<u8>> {
+        let e = self.find(path)?.clone();
+        let payload = read_payload(&mut self.file, &e)?;
+        decode_payload(&e, payload)
+    }
+}
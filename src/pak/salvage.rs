@@ -0,0 +1,385 @@
+#![forbid(unsafe_code)]
+
+use blake3::Hasher;
+use serde::Serialize;
+use std::borrow::Cow;
+#[cfg(feature = "zstd")]
+use std::io::Read;
+use std::io::{Cursor, Write};
+use std::path::Path;
+
+use crate::pak::atomic::AtomicFile;
+use crate::pak::build::{write_footer, write_index};
+use crate::pak::codec;
+use crate::pak::error::{PakError, PakResult};
+use crate::pak::format::{self, Entry, FOOTER_MAGIC, MAGIC};
+use crate::pak::index::read_front_coded_entry;
+use crate::pak::io::read_u32;
+use crate::pak::limits::ReadLimits;
+use crate::pak::path::safe_join;
+use crate::pak::read::{in_payload_area, parse_checked_index, parse_footer, read_entry};
+
+const FOOTER_LEN: usize = format::FOOTER_LEN as usize;
+
+#[cfg(feature = "zstd")]
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+/// How the salvaged entry list was obtained, from most to least trustworthy.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "snake_case", tag = "method")]
+pub enum SalvageSource {
+    /// The regular footer at the end of the file.
+    Footer,
+    /// A footer found elsewhere in the file (e.g. trailing garbage after it).
+    AlternateFooter { footer_offset: u64 },
+    /// An index block found by scanning for MAGIC (or for a zstd frame that
+    /// decodes to one); may be cut short.
+    IndexScan { index_offset: u64, declared: u32 },
+    /// No index: zstd frames carved out of the payload area, without names or hashes.
+    Carved,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SalvagedEntry {
+    pub path: String,
+    pub payload_offset: u64,
+    pub raw_len: u64,
+    /// True when the bytes matched the indexed blake3 hash (never for carved frames).
+    pub verified: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LostEntry {
+    pub path: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SalvageReport {
+    pub source: SalvageSource,
+    pub recovered: Vec<SalvagedEntry>,
+    pub lost: Vec<LostEntry>,
+    /// True when a rebuilt pak was written. Rebuilding is skipped when no
+    /// entry could be verified, e.g. when only carved frames were found.
+    pub rebuilt: bool,
+}
+
+fn find_all(hay: &[u8], needle: &[u8]) -> Vec<usize> {
+    hay.windows(needle.len())
+        .enumerate()
+        .filter(|(_, w)| *w == needle)
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Try a footer at `at`; returns the entries if it points at an intact index.
+fn try_footer(data: &[u8], at: usize) -> Option<Vec<Entry>> {
+    let footer = parse_footer(data.get(at..at + FOOTER_LEN)?.try_into().ok()?).ok()?;
+
+    let start = usize::try_from(footer.index_offset).ok()?;
+    let end = start.checked_add(usize::try_from(footer.index_len).ok()?)?;
+    let index = data.get(start..end)?;
+    parse_checked_index(index, &footer, &ReadLimits::default()).ok()
+}
+
+/// Decode as many records as possible from an index block (starting at its
+/// MAGIC) that may be truncated. The footer, and with it the encoding flags, is
+/// usually lost, so both record layouts are tried. `index_offset` is where the
+/// block starts in the file.
+fn parse_partial_index(block: &[u8], index_offset: u64) -> Option<(u32, Vec<Entry>)> {
+    [false, true]
+        .into_iter()
+        .filter_map(|front_coded| parse_partial_records(block, index_offset, front_coded))
+        .max_by_key(|(_, entries)| entries.len())
+}
+
+fn parse_partial_records(
+    block: &[u8],
+    index_offset: u64,
+    front_coded: bool,
+) -> Option<(u32, Vec<Entry>)> {
+    let mut cur = Cursor::new(block.strip_prefix(&MAGIC)?);
+    let declared = read_u32(&mut cur).ok()?;
+
+    let mut out: Vec<Entry> = Vec::new();
+    let mut prev_path = Vec::new();
+    for _ in 0..declared {
+        let e = if front_coded {
+            read_front_coded_entry(&mut cur, &mut prev_path)
+        } else {
+            read_entry(&mut cur)
+        };
+        let Ok(e) = e else { break };
+        // Payloads always precede the index; anything else means this MAGIC
+        // was a false hit inside a payload (or the wrong record layout).
+        let plausible = in_payload_area(&e, index_offset);
+        let sorted = out
+            .last()
+            .is_none_or(|prev| prev.path.as_bytes() <= e.path.as_bytes());
+        if !plausible || !sorted {
+            return None;
+        }
+        out.push(e);
+    }
+
+    if out.is_empty() {
+        None
+    } else {
+        Some((declared, out))
+    }
+}
+
+fn locate_entries(data: &[u8]) -> Option<(SalvageSource, Vec<Entry>)> {
+    if data.len() >= FOOTER_LEN {
+        let at = data.len() - FOOTER_LEN;
+        if data[at..at + 8] == FOOTER_MAGIC {
+            if let Some(entries) = try_footer(data, at) {
+                return Some((SalvageSource::Footer, entries));
+            }
+        }
+    }
+
+    for at in find_all(data, &FOOTER_MAGIC).into_iter().rev() {
+        if let Some(entries) = try_footer(data, at) {
+            return Some((
+                SalvageSource::AlternateFooter {
+                    footer_offset: at as u64,
+                },
+                entries,
+            ));
+        }
+    }
+
+    // Header MAGIC at 0 is not an index; keep the candidate with the most records.
+    let plain = find_all(data, &MAGIC)
+        .into_iter()
+        .filter(|&at| at != 0)
+        .map(|at| (at, Cow::Borrowed(&data[at..])));
+    let compressed = zstd_index_blocks(data)
+        .into_iter()
+        .map(|(at, block)| (at, Cow::Owned(block)));
+    plain
+        .chain(compressed)
+        .filter_map(|(at, block)| {
+            parse_partial_index(&block, at as u64).map(|(declared, e)| (at, declared, e))
+        })
+        .max_by_key(|(_, _, e)| e.len())
+        .map(|(at, declared, entries)| {
+            (
+                SalvageSource::IndexScan {
+                    index_offset: at as u64,
+                    declared,
+                },
+                entries,
+            )
+        })
+}
+
+fn decode(e: &Entry, payload: &[u8]) -> Result<Vec<u8>, String> {
+    match e.payload_kind {
+        codec::RAW => Ok(payload.to_vec()),
+        kind => {
+            // Recovered records are unchecked; never trust raw_len past the default limit.
+            let max_len = e.raw_len.min(ReadLimits::default().max_raw_len);
+            codec::decode(kind, payload, max_len, &e.path).map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// zstd frames (`INDEX_ZSTD` index blocks) that decode to something starting
+/// with MAGIC, decoded as far as the data allows: a frame that is cut off still
+/// yields its complete blocks.
+#[cfg(feature = "zstd")]
+fn zstd_index_blocks(data: &[u8]) -> Vec<(usize, Vec<u8>)> {
+    let max_len = ReadLimits::default().max_index_len;
+    let decode_prefix = |frame: &[u8]| {
+        let mut decoder = zstd::stream::read::Decoder::new(frame).ok()?.take(max_len);
+        // Peek first, so payload frames are not decoded in full.
+        let mut block = vec![0u8; MAGIC.len()];
+        decoder.read_exact(&mut block).ok()?;
+        if block != MAGIC {
+            return None;
+        }
+        // An error here is where the frame was cut off; keep what came before.
+        let _ = decoder.read_to_end(&mut block);
+        Some(block)
+    };
+    find_all(data, &ZSTD_MAGIC)
+        .into_iter()
+        .filter(|&at| at >= MAGIC.len())
+        .filter_map(|at| Some((at, decode_prefix(&data[at..])?)))
+        .collect()
+}
+
+#[cfg(not(feature = "zstd"))]
+fn zstd_index_blocks(_data: &[u8]) -> Vec<(usize, Vec<u8>)> {
+    Vec::new()
+}
+
+/// Carve complete zstd frames out of `data[MAGIC.len()..]`, skipping index
+/// frames.
+#[cfg(feature = "zstd")]
+fn carve_frames(data: &[u8]) -> Vec<(u64, Vec<u8>)> {
+    let max_len = ReadLimits::default().max_raw_len;
+    let decode_frame = |frame: &[u8]| codec::decode(codec::ZSTD, frame, max_len, "frame").ok();
+    let mut out = Vec::new();
+    let mut at = MAGIC.len();
+    while at + ZSTD_MAGIC.len() <= data.len() {
+        if data[at..at + 4] != ZSTD_MAGIC {
+            at += 1;
+            continue;
+        }
+        let frame_len = zstd::zstd_safe::find_frame_compressed_size(&data[at..]).ok();
+        match frame_len.and_then(|len| Some((len, decode_frame(&data[at..at + len])?))) {
+            Some((len, raw)) => {
+                // An `INDEX_ZSTD` index block, not a file.
+                if !raw.starts_with(&MAGIC) {
+                    out.push((at as u64, raw));
+                }
+                at += len;
+            }
+            None => at += 1,
+        }
+    }
+    out
+}
+
+#[cfg(not(feature = "zstd"))]
+fn carve_frames(_data: &[u8]) -> Vec<(u64, Vec<u8>)> {
+    Vec::new()
+}
+
+/// Recover whatever can be trusted from a damaged pak into `output`. With
+/// `rebuilt`, also write the verified entries to a fresh pak there (see
+/// [`rebuild`]), unless there are none.
+///
+/// The whole file is loaded into memory: salvage is a one-off repair tool.
+pub fn salvage(pak: &Path, output: &Path, rebuilt: Option<&Path>) -> PakResult<SalvageReport> {
+    let data = std::fs::read(pak)?;
+    if data.len() < MAGIC.len() || data[..MAGIC.len()] != MAGIC {
+        return Err(PakError::Invalid("bad header magic".into()));
+    }
+    std::fs::create_dir_all(output)?;
+
+    let Some((source, entries)) = locate_entries(&data) else {
+        let mut recovered = Vec::new();
+        for (i, (offset, raw)) in carve_frames(&data).into_iter().enumerate() {
+            let path = format!("carved/{i:06}_at_{offset}.bin");
+            std::fs::write(safe_join(output, &path)?, &raw)?;
+            recovered.push(SalvagedEntry {
+                path,
+                payload_offset: offset,
+                raw_len: raw.len() as u64,
+                verified: false,
+            });
+        }
+        return Ok(SalvageReport {
+            source: SalvageSource::Carved,
+            recovered,
+            lost: Vec::new(),
+            rebuilt: false,
+        });
+    };
+
+    let mut report = SalvageReport {
+        source,
+        recovered: Vec::new(),
+        lost: Vec::new(),
+        rebuilt: false,
+    };
+    if let SalvageSource::IndexScan { declared, .. } = report.source {
+        if (declared as usize) > entries.len() {
+            report.lost.push(LostEntry {
+                path: "<index tail>".into(),
+                reason: format!(
+                    "{} record(s) cut off in the index",
+                    declared as usize - entries.len()
+                ),
+            });
+        }
+    }
+
+    let mut kept = Vec::new();
+    for e in entries {
+        let mut lose = |reason: String| {
+            report.lost.push(LostEntry {
+                path: e.path.clone(),
+                reason,
+            })
+        };
+
+        let range = usize::try_from(e.payload_offset)
+            .ok()
+            .zip(usize::try_from(e.payload_len).ok())
+            .and_then(|(start, len)| data.get(start..start.checked_add(len)?));
+        let Some(payload) = range else {
+            lose("payload truncated".into());
+            continue;
+        };
+
+        let raw = match decode(&e, payload) {
+            Ok(raw) => raw,
+            Err(err) => {
+                lose(format!("decode failed: {err}"));
+                continue;
+            }
+        };
+
+        let mut hasher = Hasher::new();
+        hasher.update(&raw);
+        let got: [u8; 32] = hasher.finalize().into();
+        if got != e.raw_hash || raw.len() as u64 != e.raw_len {
+            lose("hash mismatch".into());
+            continue;
+        }
+
+        let out_path = match safe_join(output, &e.path) {
+            Ok(p) => p,
+            Err(err) => {
+                lose(err.to_string());
+                continue;
+            }
+        };
+        std::fs::write(out_path, &raw)?;
+
+        report.recovered.push(SalvagedEntry {
+            path: e.path.clone(),
+            payload_offset: e.payload_offset,
+            raw_len: e.raw_len,
+            verified: true,
+        });
+        kept.push(e);
+    }
+
+    if let Some(rebuilt) = rebuilt.filter(|_| !kept.is_empty()) {
+        rebuild(&data, &kept, rebuilt)?;
+        report.rebuilt = true;
+    }
+    Ok(report)
+}
+
+/// Write a pak holding exactly `entries`, whose payloads are copied from `data`
+/// as stored: every entry keeps its codec and hash. Carved frames have no name
+/// or hash and are never included; neither is anything else in the salvage
+/// output directory. The index is written in the plain encoding.
+fn rebuild(data: &[u8], entries: &[Entry], rebuilt: &Path) -> PakResult<()> {
+    let mut pak = Cursor::new(MAGIC.to_vec());
+    pak.set_position(MAGIC.len() as u64);
+    let mut index = Vec::with_capacity(entries.len());
+    for e in entries {
+        // Recovered entries were read from these exact bytes.
+        let start = e.payload_offset as usize;
+        let payload = &data[start..start + e.payload_len as usize];
+        index.push(Entry {
+            payload_offset: pak.position(),
+            ..e.clone()
+        });
+        pak.write_all(payload)?;
+    }
+    let footer = write_index(&mut pak, &index, 0, 0)?;
+    write_footer(&mut pak, &footer)?;
+
+    let mut out = AtomicFile::create(rebuilt)?;
+    out.file_mut().write_all(pak.get_ref())?;
+    out.commit()
+}
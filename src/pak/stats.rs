@@ -0,0 +1,212 @@
+#![forbid(unsafe_code)]
+
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use crate::pak::error::PakResult;
+use crate::pak::format::EntryInfo;
+use crate::pak::ops::entries;
+
+#[derive(Debug, Clone)]
+pub struct StatsOptions {
+    /// Number of leading directory components used for the directory breakdown.
+    pub depth: usize,
+    /// Length of the largest / worst-compressed lists.
+    pub top: usize,
+    /// Also aggregate every directory prefix (`du`-style).
+    pub tree: bool,
+}
+
+impl Default for StatsOptions {
+    fn default() -> Self {
+        Self {
+            depth: 1,
+            top: 10,
+            tree: false,
+        }
+    }
+}
+
+/// Aggregated sizes of a set of entries. `ratio` is payload / raw (1.0 = stored).
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GroupStats {
+    pub key: String,
+    pub entries: u64,
+    pub raw: u64,
+    pub payload: u64,
+    pub ratio: f64,
+}
+
+impl GroupStats {
+    fn new(key: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            ..Default::default()
+        }
+    }
+
+    fn add(&mut self, e: &EntryInfo) {
+        self.entries += 1;
+        self.raw = self.raw.saturating_add(e.raw_len);
+        self.payload = self.payload.saturating_add(e.payload_len);
+        self.ratio = ratio(self.raw, self.payload);
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EntryStat {
+    pub path: String,
+    pub raw: u64,
+    pub payload: u64,
+    pub ratio: f64,
+}
+
+/// Entries with identical raw bytes.
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateGroup {
+    pub raw_hash_hex: String,
+    pub raw: u64,
+    pub paths: Vec<String>,
+    /// Payload bytes that a shared payload would save.
+    pub wasted: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PakStats {
+    pub total: GroupStats,
+    pub by_extension: Vec<GroupStats>,
+    pub by_directory: Vec<GroupStats>,
+    pub largest: Vec<EntryStat>,
+    pub worst_compressed: Vec<EntryStat>,
+    pub duplicates: Vec<DuplicateGroup>,
+    /// Every directory prefix, sorted by path; empty unless `tree` was requested.
+    pub tree: Vec<GroupStats>,
+}
+
+fn ratio(raw: u64, payload: u64) -> f64 {
+    if raw == 0 {
+        1.0
+    } else {
+        payload as f64 / raw as f64
+    }
+}
+
+fn extension(path: &str) -> String {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => ext.to_ascii_lowercase(),
+        _ => String::new(),
+    }
+}
+
+/// Parent directory components of `path`, without the file name.
+fn dirs(path: &str) -> impl Iterator<Item = &str> {
+    let parent = path.rsplit_once('/').map_or("", |(dir, _)| dir);
+    parent.split('/').filter(|c| !c.is_empty())
+}
+
+fn dir_prefix(path: &str, depth: usize) -> String {
+    dirs(path).take(depth).collect::<Vec<_>>().join("/")
+}
+
+fn entry_stat(e: &EntryInfo) -> EntryStat {
+    EntryStat {
+        path: e.path.clone(),
+        raw: e.raw_len,
+        payload: e.payload_len,
+        ratio: ratio(e.raw_len, e.payload_len),
+    }
+}
+
+fn sorted_groups(map: BTreeMap<String, GroupStats>) -> Vec<GroupStats> {
+    let mut v: Vec<_> = map.into_values().collect();
+    v.sort_by(|a, b| b.payload.cmp(&a.payload).then_with(|| a.key.cmp(&b.key)));
+    v
+}
+
+fn stats_entries(list: &[EntryInfo], opts: &StatsOptions) -> PakStats {
+    let mut total = GroupStats::new("total");
+    let mut by_ext: BTreeMap<String, GroupStats> = BTreeMap::new();
+    let mut by_dir: BTreeMap<String, GroupStats> = BTreeMap::new();
+    let mut tree: BTreeMap<String, GroupStats> = BTreeMap::new();
+    let mut by_hash: HashMap<&str, Vec<&EntryInfo>> = HashMap::new();
+
+    for e in list {
+        total.add(e);
+
+        let ext = extension(&e.path);
+        by_ext
+            .entry(ext.clone())
+            .or_insert_with(|| GroupStats::new(ext))
+            .add(e);
+
+        let dir = dir_prefix(&e.path, opts.depth);
+        by_dir
+            .entry(dir.clone())
+            .or_insert_with(|| GroupStats::new(dir))
+            .add(e);
+
+        if opts.tree {
+            tree.entry(String::new())
+                .or_insert_with(|| GroupStats::new(""))
+                .add(e);
+            let mut prefix = String::new();
+            for comp in dirs(&e.path) {
+                if !prefix.is_empty() {
+                    prefix.push('/');
+                }
+                prefix.push_str(comp);
+                tree.entry(prefix.clone())
+                    .or_insert_with(|| GroupStats::new(prefix.clone()))
+                    .add(e);
+            }
+        }
+
+        by_hash.entry(&e.raw_hash_hex).or_default().push(e);
+    }
+
+    let mut largest: Vec<_> = list.iter().collect();
+    largest.sort_by(|a, b| b.raw_len.cmp(&a.raw_len).then_with(|| a.path.cmp(&b.path)));
+
+    let mut worst: Vec<_> = list.iter().filter(|e| e.raw_len > 0).collect();
+    worst.sort_by(|a, b| {
+        ratio(b.raw_len, b.payload_len)
+            .total_cmp(&ratio(a.raw_len, a.payload_len))
+            .then_with(|| b.raw_len.cmp(&a.raw_len))
+    });
+
+    let mut duplicates: Vec<_> = by_hash
+        .into_iter()
+        .filter(|(_, group)| group.len() > 1)
+        .map(|(hash, group)| DuplicateGroup {
+            raw_hash_hex: hash.to_string(),
+            raw: group[0].raw_len,
+            paths: group.iter().map(|e| e.path.clone()).collect(),
+            wasted: group[1..]
+                .iter()
+                .map(|e| e.payload_len)
+                .fold(0, u64::saturating_add),
+        })
+        .collect();
+    duplicates.sort_by(|a, b| b.wasted.cmp(&a.wasted).then_with(|| a.paths.cmp(&b.paths)));
+
+    // Component-wise order keeps children directly under their parent.
+    let mut tree: Vec<_> = tree.into_values().collect();
+    tree.sort_by(|a, b| a.key.split('/').cmp(b.key.split('/')));
+
+    PakStats {
+        total,
+        by_extension: sorted_groups(by_ext),
+        by_directory: sorted_groups(by_dir),
+        largest: largest.into_iter().take(opts.top).map(entry_stat).collect(),
+        worst_compressed: worst.into_iter().take(opts.top).map(entry_stat).collect(),
+        duplicates,
+        tree,
+    }
+}
+
+/// Size breakdown of a pak, computed from its index only.
+pub fn stats(pak: &Path, opts: &StatsOptions) -> PakResult<PakStats> {
+    Ok(stats_entries(&entries(pak)?, opts))
+}
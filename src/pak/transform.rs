@@ -0,0 +1,467 @@
+#![forbid(unsafe_code)]
+
+//! Build-time asset transforms: a file's bytes (and possibly its logical path)
+//! are rewritten before it is hashed and packed.
+//!
+//! Rules select transforms by glob on the logical path. Every matching rule
+//! runs, in order; each glob is matched against the path as renamed by the
+//! rules before it. Renames depend on the path alone, so they are applied while
+//! scanning and collisions are reported before any file is read.
+
+use blake3::Hasher;
+use globset::{GlobBuilder, GlobMatcher};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use crate::pak::build::BuildOptions;
+use crate::pak::error::{PakError, PakResult};
+use crate::pak::io::hex32;
+
+/// Error returned by [`Transform::apply`].
+pub type TransformError = Box<dyn std::error::Error + Send + Sync>;
+
+/// One step of the transform pipeline.
+pub trait Transform: Send + Sync {
+    /// Short name used in errors, e.g. `json-minify`.
+    fn name(&self) -> &str;
+
+    /// Identifies what this transform produces: cached outputs are reused while
+    /// it stays the same. Include every setting that changes the output.
+    fn cache_key(&self) -> String {
+        self.name().to_string()
+    }
+
+    /// Logical path the output is stored under. Defaults to `path`.
+    fn rename(&self, path: &str) -> String {
+        path.to_string()
+    }
+
+    /// Transform the bytes of the file at logical `path` (before this step's rename).
+    fn apply(&self, path: &str, bytes: Vec<u8>) -> Result<Vec<u8>, TransformError>;
+}
+
+/// A transform and the files it applies to.
+#[derive(Clone)]
+pub struct TransformRule {
+    /// Glob on logical paths; `*` does not cross `/`, `**` does.
+    pub glob: String,
+    pub transform: Arc<dyn Transform>,
+}
+
+impl TransformRule {
+    pub fn new(glob: impl Into<String>, transform: impl Transform + 'static) -> Self {
+        Self {
+            glob: glob.into(),
+            transform: Arc::new(transform),
+        }
+    }
+}
+
+impl std::fmt::Debug for TransformRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TransformRule")
+            .field("glob", &self.glob)
+            .field("transform", &self.transform.name())
+            .finish()
+    }
+}
+
+/// Removes whitespace outside strings. Fails on invalid JSON; numbers and
+/// strings are kept byte for byte.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonMinify;
+
+impl Transform for JsonMinify {
+    fn name(&self) -> &str {
+        "json-minify"
+    }
+
+    fn apply(&self, _path: &str, bytes: Vec<u8>) -> Result<Vec<u8>, TransformError> {
+        serde_json::from_slice::<serde::de::IgnoredAny>(&bytes)?;
+
+        let mut out = Vec::with_capacity(bytes.len());
+        let (mut in_string, mut escaped) = (false, false);
+        for b in bytes {
+            if in_string {
+                if escaped {
+                    escaped = false;
+                } else if b == b'\\' {
+                    escaped = true;
+                } else if b == b'"' {
+                    in_string = false;
+                }
+            } else if matches!(b, b' ' | b'\t' | b'\n' | b'\r') {
+                continue;
+            } else if b == b'"' {
+                in_string = true;
+            }
+            out.push(b);
+        }
+        Ok(out)
+    }
+}
+
+/// Rewrites CRLF line endings to LF. Lone CRs are kept.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CrlfToLf;
+
+impl Transform for CrlfToLf {
+    fn name(&self) -> &str {
+        "crlf-to-lf"
+    }
+
+    fn apply(&self, _path: &str, bytes: Vec<u8>) -> Result<Vec<u8>, TransformError> {
+        let mut out = Vec::with_capacity(bytes.len());
+        for (i, &b) in bytes.iter().enumerate() {
+            if !(b == b'\r' && bytes.get(i + 1) == Some(&b'\n')) {
+                out.push(b);
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// Removes a leading UTF-8 byte order mark.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StripBom;
+
+impl Transform for StripBom {
+    fn name(&self) -> &str {
+        "strip-bom"
+    }
+
+    fn apply(&self, _path: &str, mut bytes: Vec<u8>) -> Result<Vec<u8>, TransformError> {
+        if bytes.starts_with(b"\xEF\xBB\xBF") {
+            bytes.drain(..3);
+        }
+        Ok(bytes)
+    }
+}
+
+/// Names accepted by [`builtin_transform`] and by `"builtin"` in rule files.
+pub const BUILTIN_TRANSFORMS: &[&str] = &["json-minify", "crlf-to-lf", "strip-bom"];
+
+/// A built-in transform by name.
+pub fn builtin_transform(name: &str) -> Option<Arc<dyn Transform>> {
+    Some(match name {
+        "json-minify" => Arc::new(JsonMinify),
+        "crlf-to-lf" => Arc::new(CrlfToLf),
+        "strip-bom" => Arc::new(StripBom),
+        _ => return None,
+    })
+}
+
+/// Runs a local executable with the file on stdin and packs what it writes to
+/// stdout. The logical path is passed in `NEPAK_PATH`. A non-zero exit fails
+/// the build with the command's stderr.
+#[derive(Debug)]
+pub struct CommandTransform {
+    program: PathBuf,
+    args: Vec<String>,
+    /// `(from, to)`: paths ending in `from` get that suffix replaced by `to`.
+    rename: Option<(String, String)>,
+    /// Hash of the program file, when `program` is a path to one, with the
+    /// file's `(len, mtime)` it was computed for. Rehashed when those change,
+    /// so a watch build sees edits to the script.
+    program_hash: Mutex<Option<(FileStamp, String)>>,
+}
+
+type FileStamp = (u64, Option<SystemTime>);
+
+impl CommandTransform {
+    pub fn new(program: impl Into<PathBuf>, args: Vec<String>) -> Self {
+        Self {
+            program: program.into(),
+            args,
+            rename: None,
+            program_hash: Mutex::new(None),
+        }
+    }
+
+    /// Store outputs of paths ending in `from` under that suffix replaced by
+    /// `to`, e.g. `(".glsl", ".spv")`.
+    pub fn with_rename(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.rename = Some((from.into(), to.into()));
+        self
+    }
+
+    /// Hash of the program file, or `None` if it cannot be read (e.g. the
+    /// program is looked up on `PATH`).
+    fn program_hash(&self) -> Option<String> {
+        let meta = std::fs::metadata(&self.program).ok()?;
+        let stamp = (meta.len(), meta.modified().ok());
+        let mut cached = self.program_hash.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((at, hash)) = &*cached {
+            if *at == stamp {
+                return Some(hash.clone());
+            }
+        }
+        let bytes = std::fs::read(&self.program).ok()?;
+        let hash = hex32(blake3::hash(&bytes).as_bytes());
+        *cached = Some((stamp, hash.clone()));
+        Some(hash)
+    }
+}
+
+impl Transform for CommandTransform {
+    fn name(&self) -> &str {
+        self.program.to_str().unwrap_or("command")
+    }
+
+    /// The command line plus the program file's contents, so editing a
+    /// transform script invalidates its cached outputs.
+    fn cache_key(&self) -> String {
+        format!(
+            "command {:?} {:?} {:?} {:?}",
+            self.program,
+            self.args,
+            self.rename,
+            self.program_hash()
+        )
+    }
+
+    fn rename(&self, path: &str) -> String {
+        match &self.rename {
+            Some((from, to)) => match path.strip_suffix(from.as_str()) {
+                Some(stem) => format!("{stem}{to}"),
+                None => path.to_string(),
+            },
+            None => path.to_string(),
+        }
+    }
+
+    fn apply(&self, path: &str, bytes: Vec<u8>) -> Result<Vec<u8>, TransformError> {
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .env("NEPAK_PATH", path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("cannot run {}: {e}", self.program.display()))?;
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+
+        // Feed stdin from another thread: a command may start writing before it
+        // has read everything, and both pipes must keep moving.
+        let (fed, output) = std::thread::scope(|s| {
+            let feeder = s.spawn(move || stdin.write_all(&bytes));
+            let output = child.wait_with_output();
+            (feeder.join().expect("stdin feeder panicked"), output)
+        });
+        let output = output?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("{}: {}", output.status, stderr.trim()).into());
+        }
+        // A command may legitimately stop reading early; only its exit status counts.
+        if let Err(e) = fed {
+            if e.kind() != std::io::ErrorKind::BrokenPipe {
+                return Err(e.into());
+            }
+        }
+        Ok(output.stdout)
+    }
+}
+
+/// One rule as written in a rules file or a build-script manifest.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RuleSpec {
+    glob: String,
+    builtin: Option<String>,
+    /// Program and arguments.
+    command: Option<Vec<String>>,
+    /// `[from, to]` suffix rename, for `command` rules.
+    rename: Option<(String, String)>,
+}
+
+impl RuleSpec {
+    /// The command's program file, when it is given as a path (relative to
+    /// `base`) rather than a bare name looked up in `PATH`.
+    pub(crate) fn program_file(&self, base: &Path) -> Option<PathBuf> {
+        let program = self.command.as_ref()?.first()?;
+        (Path::new(program).components().count() > 1).then(|| base.join(program))
+    }
+
+    /// Build the rule; command programs given as a path are relative to `base`.
+    pub(crate) fn into_rule(self, base: &Path) -> PakResult<TransformRule> {
+        let invalid =
+            |msg: String| PakError::Invalid(format!("transform rule {}: {msg}", self.glob));
+        let transform: Arc<dyn Transform> = match (&self.builtin, &self.command) {
+            (Some(name), None) => {
+                if self.rename.is_some() {
+                    return Err(invalid("\"rename\" needs a \"command\"".into()));
+                }
+                builtin_transform(name).ok_or_else(|| {
+                    invalid(format!(
+                        "unknown builtin {name:?} (expected one of {})",
+                        BUILTIN_TRANSFORMS.join(", ")
+                    ))
+                })?
+            }
+            (None, Some(command)) => {
+                let Some((program, args)) = command.split_first() else {
+                    return Err(invalid("empty \"command\"".into()));
+                };
+                let program = self.program_file(base).unwrap_or_else(|| program.into());
+                let mut t = CommandTransform::new(program, args.to_vec());
+                if let Some((from, to)) = &self.rename {
+                    t = t.with_rename(from, to);
+                }
+                Arc::new(t)
+            }
+            _ => {
+                return Err(invalid(
+                    "needs exactly one of \"builtin\" and \"command\"".into(),
+                ))
+            }
+        };
+        Ok(TransformRule {
+            glob: self.glob,
+            transform,
+        })
+    }
+}
+
+/// Read transform rules from a JSON file holding an array of rules:
+///
+/// ```json
+/// [
+///   { "glob": "**/*.json", "builtin": "json-minify" },
+///   { "glob": "shaders/**/*.glsl", "command": ["tools/strip-comments", "--glsl"] },
+///   { "glob": "**/*.vert", "command": ["glslc", "-", "-o", "-"], "rename": [".vert", ".spv"] }
+/// ]
+/// ```
+///
+/// Built-ins are listed in [`BUILTIN_TRANSFORMS`]. Program paths are relative
+/// to the file's directory.
+pub fn load_transforms(path: impl AsRef<Path>) -> PakResult<Vec<TransformRule>> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path)?;
+    let specs: Vec<RuleSpec> = serde_json::from_str(&text).map_err(|e| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("{}: {e}", path.display()),
+        )
+    })?;
+    let base = path.parent().unwrap_or(Path::new(""));
+    specs.into_iter().map(|s| s.into_rule(base)).collect()
+}
+
+/// Identifies a rule list for the incremental build cache.
+pub(crate) fn fingerprint(rules: &[TransformRule]) -> String {
+    rules
+        .iter()
+        .map(|r| format!("{}\0{}\0", r.glob, r.transform.cache_key()))
+        .collect()
+}
+
+/// Compiled rules for one build. Remembers the path each renamed file had
+/// before its renames, so the right rules run on its bytes later.
+pub(crate) struct Pipeline {
+    rules: Vec<(GlobMatcher, Arc<dyn Transform>)>,
+    cache_dir: Option<PathBuf>,
+    /// Logical path after renames -> path before, for renamed files only.
+    sources: HashMap<String, String>,
+}
+
+impl Pipeline {
+    pub(crate) fn new(opts: &BuildOptions) -> PakResult<Self> {
+        let rules = opts
+            .transforms
+            .iter()
+            .map(|r| {
+                let glob = GlobBuilder::new(&r.glob)
+                    .literal_separator(true)
+                    .build()
+                    .map_err(|e| PakError::Invalid(format!("bad glob '{}': {e}", r.glob)))?;
+                Ok((glob.compile_matcher(), Arc::clone(&r.transform)))
+            })
+            .collect::<PakResult<_>>()?;
+        Ok(Self {
+            rules,
+            cache_dir: opts.transform_cache.clone(),
+            sources: HashMap::new(),
+        })
+    }
+
+    /// Matching transforms for `path`, each with the path it sees, and the final path.
+    fn plan(&self, path: &str) -> (Vec<(&dyn Transform, String)>, String) {
+        let mut steps = Vec::new();
+        let mut path = path.to_string();
+        for (glob, t) in &self.rules {
+            if glob.is_match(&path) {
+                let renamed = t.rename(&path);
+                steps.push((t.as_ref(), std::mem::replace(&mut path, renamed)));
+            }
+        }
+        (steps, path)
+    }
+
+    /// Logical path a scanned file is stored under.
+    pub(crate) fn rename(&mut self, logical: String) -> String {
+        let (_, renamed) = self.plan(&logical);
+        if renamed == logical {
+            self.sources.remove(&renamed);
+        } else {
+            self.sources.insert(renamed.clone(), logical);
+        }
+        renamed
+    }
+
+    /// Run the transforms for the file stored under `logical` on its bytes.
+    pub(crate) fn apply(&self, logical: &str, bytes: Vec<u8>) -> PakResult<Vec<u8>> {
+        let source = self.sources.get(logical).map_or(logical, String::as_str);
+        let (steps, _) = self.plan(source);
+        if steps.is_empty() {
+            return Ok(bytes);
+        }
+
+        let cached = self.cache_dir.as_ref().map(|dir| {
+            let mut hasher = Hasher::new();
+            hasher.update(b"nepak-transform-v1\0");
+            for (t, path) in &steps {
+                hasher.update(t.cache_key().as_bytes());
+                hasher.update(b"\0");
+                hasher.update(path.as_bytes());
+                hasher.update(b"\0");
+            }
+            hasher.update(&bytes);
+            dir.join(hex32(hasher.finalize().as_bytes()))
+        });
+        if let Some(hit) = cached.as_ref().and_then(|p| std::fs::read(p).ok()) {
+            return Ok(hit);
+        }
+
+        let mut bytes = bytes;
+        for (t, path) in steps {
+            bytes = t.apply(&path, bytes).map_err(|e| PakError::Transform {
+                path: path.clone(),
+                transform: t.name().to_string(),
+                message: e.to_string(),
+            })?;
+        }
+
+        if let Some(entry) = cached {
+            store(&entry, &bytes)?;
+        }
+        Ok(bytes)
+    }
+}
+
+/// Write a cache entry via a temp file, so readers never see a partial one.
+fn store(entry: &Path, bytes: &[u8]) -> PakResult<()> {
+    if let Some(dir) = entry.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let tmp = entry.with_extension(format!("{}.tmp", std::process::id()));
+    std::fs::write(&tmp, bytes)?;
+    std::fs::rename(&tmp, entry)?;
+    Ok(())
+}
@@ -0,0 +1,254 @@
+#![forbid(unsafe_code)]
+
+use blake3::Hasher;
+use serde::Serialize;
+use std::fmt;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::pak::cancel::CancelToken;
+use crate::pak::codec;
+use crate::pak::error::{PakError, PakResult};
+use crate::pak::format::{Entry, MAGIC};
+use crate::pak::io::read_exact;
+use crate::pak::limits::ReadLimits;
+use crate::pak::path::validate_entry_path;
+use crate::pak::progress::{Progress, Reporter, Stage};
+use crate::pak::read::{in_payload_area, read_index_unranged};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerifyIssueKind {
+    /// Decoded bytes do not match `raw_hash`.
+    HashMismatch,
+    /// Decoded length does not match `raw_len` (the hash is then not checked).
+    SizeMismatch,
+    /// Payload range is under the header or past the payload area.
+    OutOfBounds,
+    /// Payload range overlaps the previous payload.
+    Overlap,
+    /// Bytes in the payload area that no entry references.
+    Gap,
+    /// Payload could not be decoded (corrupt stream, codec unavailable).
+    DecodeFailure,
+    /// Entry path is unsafe to extract.
+    InvalidPath,
+}
+
+impl VerifyIssueKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            VerifyIssueKind::HashMismatch => "hash_mismatch",
+            VerifyIssueKind::SizeMismatch => "size_mismatch",
+            VerifyIssueKind::OutOfBounds => "out_of_bounds",
+            VerifyIssueKind::Overlap => "overlap",
+            VerifyIssueKind::Gap => "gap",
+            VerifyIssueKind::DecodeFailure => "decode_failure",
+            VerifyIssueKind::InvalidPath => "invalid_path",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifyIssue {
+    /// Owning entry, `None` for gaps.
+    pub path: Option<String>,
+    pub offset: u64,
+    pub len: u64,
+    pub kind: VerifyIssueKind,
+    pub detail: String,
+}
+
+impl fmt::Display for VerifyIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at {}+{}: {}",
+            self.kind.as_str(),
+            self.offset,
+            self.len,
+            self.path.as_deref().unwrap_or("<none>")
+        )?;
+        if !self.detail.is_empty() {
+            write!(f, " ({})", self.detail)?;
+        }
+        Ok(())
+    }
+}
+
+/// Everything wrong with a pak whose index could be read.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct VerifyReport {
+    pub entries: u64,
+    pub issues: Vec<VerifyIssue>,
+}
+
+impl VerifyReport {
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Check every entry instead of stopping at the first bad one.
+///
+/// Only a missing header, footer or index is a hard error; everything else
+/// ends up in the report.
+pub fn verify(
+    pak: &Path,
+    limits: &ReadLimits,
+    cancel: &CancelToken,
+    progress: impl FnMut(Progress),
+) -> PakResult<VerifyReport> {
+    let mut f = File::open(pak)?;
+
+    let head = read_exact::<8>(&mut f)?;
+    if head != MAGIC {
+        return Err(PakError::Invalid("bad header magic".into()));
+    }
+
+    let (footer, entries) = read_index_unranged(&mut f, limits)?;
+    let index_offset = footer.index_offset;
+
+    let mut progress = Reporter::new(progress);
+    progress.stage(
+        Stage::Verifying,
+        entries.len() as u64,
+        entries
+            .iter()
+            .map(|e| e.payload_len)
+            .fold(0, u64::saturating_add),
+    );
+
+    let mut report = VerifyReport {
+        entries: entries.len() as u64,
+        issues: Vec::new(),
+    };
+    let mut issue = |e: Option<&str>, offset, len, kind, detail: String| {
+        report.issues.push(VerifyIssue {
+            path: e.map(str::to_string),
+            offset,
+            len,
+            kind,
+            detail,
+        })
+    };
+
+    let in_payload_area = |e: &Entry| in_payload_area(e, index_offset);
+
+    for e in &entries {
+        cancel.check()?;
+        'entry: {
+            let path = Some(e.path.as_str());
+            if let Err(err) = validate_entry_path(&e.path) {
+                issue(
+                    path,
+                    e.payload_offset,
+                    e.payload_len,
+                    VerifyIssueKind::InvalidPath,
+                    err.to_string(),
+                );
+            }
+
+            if !in_payload_area(e) {
+                issue(
+                    path,
+                    e.payload_offset,
+                    e.payload_len,
+                    VerifyIssueKind::OutOfBounds,
+                    format!("payload area is {}..{}", MAGIC.len(), index_offset),
+                );
+                break 'entry;
+            }
+
+            f.seek(SeekFrom::Start(e.payload_offset))?;
+            let mut payload = vec![0u8; e.payload_len as usize];
+            f.read_exact(&mut payload)?;
+
+            let raw = match e.payload_kind {
+                codec::RAW => payload,
+                kind => match codec::decode(kind, &payload, e.raw_len, &e.path) {
+                    Ok(raw) => raw,
+                    Err(err) => {
+                        issue(
+                            path,
+                            e.payload_offset,
+                            e.payload_len,
+                            VerifyIssueKind::DecodeFailure,
+                            err.to_string(),
+                        );
+                        break 'entry;
+                    }
+                },
+            };
+
+            if raw.len() as u64 != e.raw_len {
+                issue(
+                    path,
+                    e.payload_offset,
+                    e.payload_len,
+                    VerifyIssueKind::SizeMismatch,
+                    format!("expected {} bytes, decoded {}", e.raw_len, raw.len()),
+                );
+                // Bytes of the wrong length cannot match the hash either.
+                break 'entry;
+            }
+
+            let mut hasher = Hasher::new();
+            hasher.update(&raw);
+            let got: [u8; 32] = hasher.finalize().into();
+            if got != e.raw_hash {
+                issue(
+                    path,
+                    e.payload_offset,
+                    e.payload_len,
+                    VerifyIssueKind::HashMismatch,
+                    String::new(),
+                );
+            }
+        }
+        progress.advance(Some(&e.path), e.payload_len);
+    }
+
+    // Layout pass: walk payloads in file order looking for overlaps and holes.
+    let mut by_offset: Vec<_> = entries.iter().filter(|e| in_payload_area(e)).collect();
+    by_offset.sort_by_key(|e| (e.payload_offset, e.payload_len));
+
+    let mut cursor = MAGIC.len() as u64;
+    let mut prev: Option<&str> = None;
+    for e in by_offset {
+        if e.payload_offset < cursor {
+            issue(
+                Some(&e.path),
+                e.payload_offset,
+                e.payload_len,
+                VerifyIssueKind::Overlap,
+                format!("overlaps {}", prev.unwrap_or("<header>")),
+            );
+        } else if e.payload_offset > cursor {
+            issue(
+                None,
+                cursor,
+                e.payload_offset - cursor,
+                VerifyIssueKind::Gap,
+                String::new(),
+            );
+        }
+        let end = e.payload_offset.saturating_add(e.payload_len);
+        if end > cursor {
+            cursor = end;
+            prev = Some(&e.path);
+        }
+    }
+    if index_offset > cursor {
+        issue(
+            None,
+            cursor,
+            index_offset - cursor,
+            VerifyIssueKind::Gap,
+            "before index".into(),
+        );
+    }
+
+    Ok(report)
+}
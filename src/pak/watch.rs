@@ -0,0 +1,146 @@
+#![forbid(unsafe_code)]
+
+//! Watch mode (feature `watch`): rebuild a pak whenever its input tree changes.
+
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use crate::pak::atomic::is_own_file;
+use crate::pak::build::{build_incremental, BuildOptions, BuildSummary};
+use crate::pak::cache::BuildCache;
+use crate::pak::cancel::CancelToken;
+use crate::pak::error::{PakError, PakResult};
+use crate::pak::progress::Progress;
+
+/// How often an idle watcher looks at its cancel token.
+const POLL: Duration = Duration::from_millis(100);
+
+/// What [`watch`] reports to its caller.
+#[derive(Debug)]
+pub enum WatchEvent {
+    /// A build is starting. `changed` lists the filesystem paths that triggered
+    /// it; it is empty for the initial build and after a watcher error.
+    Rebuilding { changed: Vec<PathBuf> },
+    /// The pak was rebuilt. `summary.changes` holds the entries that changed.
+    Built {
+        summary: BuildSummary,
+        elapsed: Duration,
+    },
+    /// The build failed. The previous pak is left in place and the next change retries.
+    Failed { error: PakError, elapsed: Duration },
+}
+
+/// Build `output`, then rebuild it every time something under the input roots
+/// changes, until `cancel` is set.
+///
+/// Changes are debounced: a rebuild starts once no new change has been seen for
+/// `debounce`. Rebuilds are incremental (see [`build_incremental`]). Build errors
+/// are reported as [`WatchEvent::Failed`] and do not stop watching; cancelling
+/// (also mid-build) returns `Ok(())`.
+pub fn watch(
+    opts: &BuildOptions,
+    output: &Path,
+    debounce: Duration,
+    cancel: &CancelToken,
+    mut progress: impl FnMut(Progress),
+    mut on_event: impl FnMut(WatchEvent),
+) -> PakResult<()> {
+    let (tx, rx) = channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    for root in &opts.inputs {
+        watcher.watch(&root.dir, RecursiveMode::Recursive)?;
+    }
+
+    let mut cache = BuildCache::new();
+    let mut changed = BTreeSet::new();
+    loop {
+        on_event(WatchEvent::Rebuilding {
+            changed: std::mem::take(&mut changed).into_iter().collect(),
+        });
+        let started = Instant::now();
+        match build_incremental(opts, output, &mut cache, cancel, &mut progress) {
+            Ok(summary) => on_event(WatchEvent::Built {
+                summary,
+                elapsed: started.elapsed(),
+            }),
+            Err(PakError::Cancelled) => return Ok(()),
+            Err(error) => on_event(WatchEvent::Failed {
+                error,
+                elapsed: started.elapsed(),
+            }),
+        }
+
+        // Block until the first relevant change, then wait for things to settle.
+        if !wait_for_change(&rx, output, cancel, None, &mut changed)? {
+            return Ok(());
+        }
+        if !wait_for_change(&rx, output, cancel, Some(debounce), &mut changed)? {
+            return Ok(());
+        }
+    }
+}
+
+/// Collect changes into `changed`. Without `quiet`, returns after the first
+/// relevant event; with it, returns once no event arrived for that long.
+/// Returns `false` when cancelled.
+fn wait_for_change(
+    rx: &Receiver<notify::Result<Event>>,
+    output: &Path,
+    cancel: &CancelToken,
+    quiet: Option<Duration>,
+    changed: &mut BTreeSet<PathBuf>,
+) -> PakResult<bool> {
+    let mut last_change = Instant::now();
+    loop {
+        if cancel.is_cancelled() {
+            return Ok(false);
+        }
+        let timeout = match quiet {
+            Some(quiet) => {
+                let left = quiet.saturating_sub(last_change.elapsed());
+                if left.is_zero() {
+                    return Ok(true);
+                }
+                left.min(POLL)
+            }
+            None => POLL,
+        };
+
+        let relevant = match rx.recv_timeout(timeout) {
+            Ok(Ok(event)) => collect(event, output, changed),
+            // Events were lost (e.g. queue overflow): rebuild to be safe;
+            // unchanged files are still detected by their stamps.
+            Ok(Err(_)) => true,
+            Err(RecvTimeoutError::Timeout) => false,
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err(PakError::Io(std::io::Error::other("file watcher stopped")));
+            }
+        };
+        if relevant {
+            if quiet.is_none() {
+                return Ok(true);
+            }
+            last_change = Instant::now();
+        }
+    }
+}
+
+/// Add the paths of `event` to `changed`, skipping accesses and the output's
+/// own files (rewriting them must not trigger another rebuild). Returns whether
+/// anything relevant happened.
+fn collect(event: Event, output: &Path, changed: &mut BTreeSet<PathBuf>) -> bool {
+    if matches!(event.kind, EventKind::Access(_)) {
+        return false;
+    }
+    let mut relevant = false;
+    for path in event.paths {
+        if !is_own_file(output, &path) {
+            changed.insert(path);
+            relevant = true;
+        }
+    }
+    relevant
+}
@@ -4,24 +4,6 @@ use crate::pak;
 use inquire::{Confirm, Text};
 use std::path::{Path, PathBuf};
 
-fn validate_dir(p: &str) -> Result<(), String> {
-    let pb = PathBuf::from(p);
-    if !pb.exists() {
-        return Err("Path does not exist".to_string());
-    }
-    if !pb.is_dir() {
-        return Err("Path is not a directory".to_string());
-    }
-    Ok(())
-}
-
-fn validate_output(p: &str) -> Result<(), String> {
-    if p.trim().is_empty() {
-        return Err("Output path is empty".to_string());
-    }
-    Ok(())
-}
-
 fn normalize_prefix(mut s: String) -> String {
     s = s.trim().replace('\\', "/");
     if s == "." {
@@ -58,16 +40,14 @@ pub fn run() -> pak::PakResult<()> {
 
     let input = Text::new("Input directory")
         .with_default("./assets")
-        //.with_validator(validate_dir)
         .prompt()
         .map(PathBuf::from)
-        .map_err(|e| pak::PakError::Io(std::io::Error::new(std::io::ErrorKind::Other, e)))?;
+        .map_err(|e| pak::PakError::Io(std::io::Error::other(e)))?;
 
     let output_raw = Text::new("Output .pak file")
         .with_default("./assets.pak")
-        //.with_validator(validate_output)
         .prompt()
-        .map_err(|e| pak::PakError::Io(std::io::Error::new(std::io::ErrorKind::Other, e)))?;
+        .map_err(|e| pak::PakError::Io(std::io::Error::other(e)))?;
 
     let output = ensure_pak_ext(Path::new(&output_raw));
 
@@ -75,24 +55,24 @@ pub fn run() -> pak::PakResult<()> {
         .with_default("assets")
         .prompt()
         .map(normalize_prefix)
-        .map_err(|e| pak::PakError::Io(std::io::Error::new(std::io::ErrorKind::Other, e)))?;
+        .map_err(|e| pak::PakError::Io(std::io::Error::other(e)))?;
 
     let excludes_raw = Text::new("Excludes (comma-separated substrings, optional)")
         .with_default(".git,target")
         .prompt()
-        .map_err(|e| pak::PakError::Io(std::io::Error::new(std::io::ErrorKind::Other, e)))?;
+        .map_err(|e| pak::PakError::Io(std::io::Error::other(e)))?;
     let excludes = split_excludes(&excludes_raw);
 
     let compress = Confirm::new("Enable zstd compression?")
         .with_default(true)
         .prompt()
-        .map_err(|e| pak::PakError::Io(std::io::Error::new(std::io::ErrorKind::Other, e)))?;
+        .map_err(|e| pak::PakError::Io(std::io::Error::other(e)))?;
 
     let zstd_level = if compress {
         let lvl = Text::new("Zstd level (1..=22)")
             .with_default("6")
             .prompt()
-            .map_err(|e| pak::PakError::Io(std::io::Error::new(std::io::ErrorKind::Other, e)))?;
+            .map_err(|e| pak::PakError::Io(std::io::Error::other(e)))?;
         lvl.trim().parse::<i32>().unwrap_or(6).clamp(1, 22)
     } else {
         0
@@ -109,11 +89,22 @@ pub fn run() -> pak::PakResult<()> {
     }
 
     let proceed = Confirm::new("Proceed?").with_default(true).prompt().map_err(|e| {
-        pak::PakError::Io(std::io::Error::new(std::io::ErrorKind::Other, e))
+        pak::PakError::Io(std::io::Error::other(e))
     })?;
     if !proceed {
         return Ok(());
     }
 
-    pak::build(&input, &output, &prefix, &excludes, compress, zstd_level)
+    let opts = pak::BuildOptions {
+        inputs: vec![pak::InputRoot::new(input, prefix)],
+        excludes,
+        compress,
+        zstd_level,
+        ..Default::default()
+    };
+    let summary = pak::build(&opts, &output)?;
+    for issue in &summary.portability {
+        println!("warning: {issue}");
+    }
+    Ok(())
 }
@@ -0,0 +1,108 @@
+//! `AsyncPak` reads the same entries and bytes as the sync reader, for every
+//! index encoding and payload codec.
+#![cfg(feature = "async")]
+
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use nepak::pak::{self, AsyncPak};
+use tokio::io::AsyncReadExt;
+
+const FILES: &[(&str, &[u8])] = &[
+    ("a.txt", b"alpha alpha alpha alpha"),
+    ("b/b.bin", &[0, 1, 2, 3, 0, 1, 2, 3]),
+    ("b/c/deep.txt", b"charlie"),
+    ("empty", b""),
+];
+
+/// `(front_code_index, compress_index, compress)` for every combination
+/// this build can write.
+fn layouts() -> Vec<(bool, bool, bool)> {
+    let zstd = cfg!(feature = "zstd");
+    let mut out = Vec::new();
+    for front in [false, true] {
+        for index in [false, zstd] {
+            for payload in [false, zstd] {
+                out.push((front, index, payload));
+            }
+        }
+    }
+    out.dedup();
+    out
+}
+
+fn build(dir: &Path, (front, index, payload): (bool, bool, bool)) -> PathBuf {
+    let input = dir.join("in");
+    common::write_files(&input, FILES);
+    let mut opts = common::options(&input);
+    opts.front_code_index = front;
+    opts.compress_index = index;
+    opts.compress = payload;
+    let pak_path = dir.join(format!(
+        "f{}-i{}-p{}.pak",
+        front as u8, index as u8, payload as u8
+    ));
+    pak::build(&opts, &pak_path).unwrap();
+    pak_path
+}
+
+async fn stream(pak: &AsyncPak, path: &str) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    pak.reader(path)
+        .await
+        .unwrap()
+        .read_to_end(&mut bytes)
+        .await
+        .unwrap();
+    bytes
+}
+
+#[tokio::test]
+async fn async_reader_matches_sync_reader() {
+    let dir = common::scratch("async-parity");
+    for layout in layouts() {
+        let pak_path = build(&dir, layout);
+        let sync = pak::entries(&pak_path).unwrap();
+        let asynk = AsyncPak::open(&pak_path).await.unwrap();
+        assert_eq!(
+            serde_json::to_value(asynk.entries()).unwrap(),
+            serde_json::to_value(&sync).unwrap(),
+            "{layout:?}"
+        );
+        assert_eq!(sync.len(), FILES.len());
+
+        for (path, raw) in FILES {
+            let bytes = pak::entry_bytes(&pak_path, path).unwrap();
+            assert_eq!(bytes, *raw, "{layout:?} {path}");
+            assert_eq!(asynk.read(path).await.unwrap(), bytes, "{layout:?} {path}");
+            assert_eq!(stream(&asynk, path).await, bytes, "{layout:?} {path}");
+        }
+        assert!(asynk.read("missing").await.is_err());
+    }
+}
+
+#[tokio::test]
+async fn both_readers_reject_a_corrupt_payload() {
+    let dir = common::scratch("async-corrupt");
+    let pak_path = build(&dir, (true, false, false));
+    let a = pak::entries(&pak_path).unwrap().remove(0);
+    let mut bytes = std::fs::read(&pak_path).unwrap();
+    bytes[a.payload_offset as usize] ^= 0xff;
+    std::fs::write(&pak_path, bytes).unwrap();
+
+    let sync = pak::entry_bytes(&pak_path, &a.path).unwrap_err();
+    let asynk = AsyncPak::open(&pak_path).await.unwrap();
+    let err = asynk.read(&a.path).await.unwrap_err();
+    assert_eq!(err.to_string(), sync.to_string());
+
+    // A truncated file fails to open in both.
+    let len = std::fs::metadata(&pak_path).unwrap().len();
+    let f = std::fs::OpenOptions::new()
+        .write(true)
+        .open(&pak_path)
+        .unwrap();
+    f.set_len(len - 1).unwrap();
+    assert!(pak::entries(&pak_path).is_err());
+    assert!(AsyncPak::open(&pak_path).await.is_err());
+}
@@ -0,0 +1,103 @@
+//! `nepak cat`: entry bytes, `--raw-payload`, and the not-found exit code.
+
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use nepak::pak;
+
+const FILES: &[(&str, &[u8])] = &[
+    ("a.txt", b"alpha"),
+    ("sprites/heroes.png", b"heroes"),
+    ("sprites/hero.jpg", b"jpg"),
+    ("textures/hero.png", b"hero"),
+    ("ui/HERO.png", b"ui hero"),
+    ("zzz/other.bin", b"other"),
+];
+
+fn build(dir: &Path, compress: bool) -> PathBuf {
+    let input = dir.join("in");
+    common::write_files(&input, FILES);
+    let pak_path = dir.join("cat.pak");
+    let opts = pak::BuildOptions {
+        compress,
+        ..common::options(&input)
+    };
+    pak::build(&opts, &pak_path).unwrap();
+    pak_path
+}
+
+fn cat(pak: &Path, args: &[&str]) -> std::process::Output {
+    let mut all = vec!["cat", pak.to_str().unwrap()];
+    all.extend(args);
+    common::nepak(&all)
+}
+
+#[test]
+fn prints_entry_bytes() {
+    let pak_path = build(&common::scratch("cat-bytes"), false);
+    for (path, bytes) in FILES {
+        let out = cat(&pak_path, &[path]);
+        assert!(out.status.success(), "{path}");
+        assert_eq!(out.stdout, *bytes, "{path}");
+    }
+}
+
+#[test]
+fn missing_entry_exits_3_with_closest_paths() {
+    let pak_path = build(&common::scratch("cat-missing"), false);
+    let out = cat(&pak_path, &["sprites/hero.png"]);
+    assert_eq!(out.status.code(), Some(3));
+    assert!(out.stdout.is_empty());
+
+    // Same file name first (case-insensitive), then by edit distance
+    // and path; at most five, so a.txt is left out.
+    let stderr = String::from_utf8(out.stderr).unwrap();
+    let expected = format!(
+        "error: no entry 'sprites/hero.png' in {}\n\
+         closest matches:\n  \
+           textures/hero.png\n  \
+           ui/HERO.png\n  \
+           sprites/hero.jpg\n  \
+           sprites/heroes.png\n  \
+           zzz/other.bin\n",
+        pak_path.display()
+    );
+    assert_eq!(stderr, expected);
+
+    // Other errors keep the generic exit code.
+    let out = common::nepak(&["cat", "missing.pak", "a.txt"]);
+    assert_eq!(out.status.code(), Some(1));
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn raw_payload_dumps_stored_bytes() {
+    let pak_path = build(&common::scratch("cat-raw-payload"), true);
+    let out = cat(&pak_path, &["a.txt", "--raw-payload"]);
+    assert!(out.status.success());
+    assert_ne!(out.stdout, b"alpha");
+    assert_eq!(out.stdout, pak::entry_payload(&pak_path, "a.txt").unwrap());
+    assert_eq!(zstd::decode_all(&out.stdout[..]).unwrap(), b"alpha");
+}
+
+#[test]
+fn raw_payload_skips_the_hash_check() {
+    let pak_path = build(&common::scratch("cat-corrupt"), false);
+    let e = pak::entries(&pak_path)
+        .unwrap()
+        .into_iter()
+        .find(|e| e.path == "a.txt")
+        .unwrap();
+    let mut bytes = std::fs::read(&pak_path).unwrap();
+    bytes[e.payload_offset as usize] = b'A';
+    std::fs::write(&pak_path, bytes).unwrap();
+
+    let out = cat(&pak_path, &["a.txt"]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(out.stdout.is_empty());
+
+    let out = cat(&pak_path, &["a.txt", "--raw-payload"]);
+    assert!(out.status.success());
+    assert_eq!(out.stdout, b"Alpha");
+}
@@ -0,0 +1,43 @@
+//! `pak::check` (`nepak build --check`): rebuild in memory and compare.
+
+mod common;
+
+use nepak::pak::{self, DiffKind};
+
+#[test]
+fn check_skips_output_inside_input_root() {
+    let dir = common::scratch("check-inside");
+    let input = dir.join("in3");
+    common::write_files(&input, &[("a.txt", b"a"), ("sub/b.txt", b"b")]);
+    let opts = common::options(&input);
+
+    let output = input.join("out.pak");
+    pak::build(&opts, &output).unwrap();
+    // A second build must not pick up the first pak (or its lock file) either.
+    pak::build(&opts, &output).unwrap();
+
+    let report = pak::check(&opts, &output).unwrap();
+    assert!(report.is_identical(), "{:?}", report.changes);
+    assert_eq!(report.changes.totals.new_entries, 2);
+}
+
+#[test]
+fn check_reports_changed_inputs() {
+    let dir = common::scratch("check-changed");
+    let input = dir.join("in");
+    common::write_files(&input, &[("a.txt", b"a"), ("b.txt", b"b")]);
+    let opts = common::options(&input);
+    let output = dir.join("out.pak");
+    pak::build(&opts, &output).unwrap();
+
+    common::write_files(&input, &[("b.txt", b"changed")]);
+    let report = pak::check(&opts, &output).unwrap();
+    assert!(!report.is_identical());
+    let changes: Vec<_> = report
+        .changes
+        .changes
+        .iter()
+        .map(|c| (c.kind, c.path.as_str()))
+        .collect();
+    assert_eq!(changes, [(DiffKind::Modified, "b.txt")]);
+}
@@ -0,0 +1,160 @@
+//! Custom payload codecs: registration rules and a round trip through a build.
+//!
+//! The registry is process-wide, so every test uses its own ids and names.
+
+mod common;
+
+use std::io::{self, Cursor, Read, Write};
+
+use nepak::pak::{self, codec_by_id, codec_by_name, register_codec, Codec, PakError};
+
+/// Stores bytes XORed with a key; easy to tell apart from raw.
+struct Xor {
+    id: u8,
+    name: &'static str,
+}
+
+impl Codec for Xor {
+    fn id(&self) -> u8 {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn compress(&self, raw: &mut dyn Read, out: &mut dyn Write, _level: i32) -> io::Result<()> {
+        let mut bytes = Vec::new();
+        raw.read_to_end(&mut bytes)?;
+        out.write_all(&bytes.iter().map(|b| b ^ 0x5a).collect::<Vec<_>>())
+    }
+
+    fn decompress<'a>(&self, mut stored: Box<dyn Read + 'a>) -> io::Result<Box<dyn Read + 'a>> {
+        let mut bytes = Vec::new();
+        stored.read_to_end(&mut bytes)?;
+        bytes.iter_mut().for_each(|b| *b ^= 0x5a);
+        Ok(Box::new(Cursor::new(bytes)))
+    }
+}
+
+fn refusal(result: pak::PakResult<()>) -> String {
+    match result {
+        Err(PakError::CodecRegistration { reason, .. }) => reason,
+        other => panic!("registration not refused: {other:?}"),
+    }
+}
+
+#[test]
+fn registered_codec_is_found_by_id_and_name() {
+    register_codec(Xor {
+        id: 200,
+        name: "xor-200",
+    })
+    .unwrap();
+    // Registering the same codec again is a no-op.
+    register_codec(Xor {
+        id: 200,
+        name: "xor-200",
+    })
+    .unwrap();
+
+    assert_eq!(codec_by_id(200).unwrap().name(), "xor-200");
+    assert_eq!(codec_by_name("xor-200").unwrap().id(), 200);
+    assert!(pak::codecs().iter().any(|c| c.id() == 200));
+}
+
+#[test]
+fn reserved_ids_and_taken_names_are_refused() {
+    for id in [0, 1, 2, 127] {
+        let reason = refusal(register_codec(Xor {
+            id,
+            name: "xor-reserved",
+        }));
+        assert!(reason.contains("reserved"), "{id}: {reason}");
+    }
+    assert!(codec_by_name("xor-reserved").is_none());
+
+    for name in ["raw", "zstd"] {
+        let reason = refusal(register_codec(Xor { id: 210, name }));
+        assert!(reason.contains("name"), "{name}: {reason}");
+    }
+
+    register_codec(Xor {
+        id: 211,
+        name: "xor-211",
+    })
+    .unwrap();
+    let reason = refusal(register_codec(Xor {
+        id: 212,
+        name: "xor-211",
+    }));
+    assert!(reason.contains("name"), "{reason}");
+    let reason = refusal(register_codec(Xor {
+        id: 211,
+        name: "xor-other",
+    }));
+    assert!(reason.contains("xor-211"), "{reason}");
+}
+
+#[test]
+fn concurrent_registrations_claim_a_name_once() {
+    let ids = 220..236;
+    let start = std::sync::Barrier::new(ids.len());
+    let ok = std::thread::scope(|s| {
+        let threads: Vec<_> = ids
+            .map(|id| {
+                let start = &start;
+                s.spawn(move || {
+                    start.wait();
+                    register_codec(Xor {
+                        id,
+                        name: "xor-race",
+                    })
+                    .is_ok()
+                })
+            })
+            .collect();
+        threads
+            .into_iter()
+            .map(|t| t.join().unwrap())
+            .filter(|&ok| ok)
+            .count()
+    });
+    assert_eq!(ok, 1);
+    let winner = codec_by_name("xor-race").unwrap().id();
+    let named = pak::codecs()
+        .iter()
+        .filter(|c| c.name() == "xor-race")
+        .count();
+    assert_eq!(named, 1, "winner {winner}");
+}
+
+#[test]
+fn custom_codec_round_trips_through_build_and_read() {
+    register_codec(Xor {
+        id: 240,
+        name: "xor-240",
+    })
+    .unwrap();
+
+    let dir = common::scratch("codec-round-trip");
+    let input = dir.join("in");
+    let files: &[(&str, &[u8])] = &[("a.txt", b"hello codec"), ("d/b.bin", &[0, 1, 2, 0x5a])];
+    common::write_files(&input, files);
+    let mut opts = common::options(&input);
+    opts.codec = Some("xor-240".into());
+    let pak_path = dir.join("xor.pak");
+    pak::build(&opts, &pak_path).unwrap();
+
+    for (e, (path, raw)) in pak::entries(&pak_path).unwrap().iter().zip(files) {
+        assert_eq!(e.payload_kind, "xor-240");
+        let stored = pak::entry_payload(&pak_path, path).unwrap();
+        assert_eq!(stored, raw.iter().map(|b| b ^ 0x5a).collect::<Vec<_>>());
+        assert_eq!(pak::entry_bytes(&pak_path, path).unwrap(), *raw);
+    }
+    assert!(pak::verify(&pak_path).unwrap().is_ok());
+
+    let out = dir.join("out");
+    pak::extract(&pak_path, &out, &[]).unwrap();
+    assert_eq!(common::read_tree(&out), common::read_tree(&input));
+}
@@ -0,0 +1,107 @@
+//! Helpers shared by the integration tests. Each test crate uses a subset.
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+use std::process::Output;
+
+use nepak::pak::{BuildOptions, InputRoot, FOOTER_MAGIC, MAGIC};
+
+/// Empty scratch directory, unique to this process and `name`.
+pub fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("nepak-test-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Write `(relative path, bytes)` pairs under `root`.
+pub fn write_files(root: &Path, files: &[(&str, &[u8])]) {
+    for (path, bytes) in files {
+        let file = root.join(path);
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        std::fs::write(file, bytes).unwrap();
+    }
+}
+
+/// Options building `dir` mounted at the pak root.
+pub fn options(dir: &Path) -> BuildOptions {
+    BuildOptions {
+        inputs: vec![InputRoot::new(dir, "")],
+        ..Default::default()
+    }
+}
+
+/// Every file under `root` as (relative `/` path, bytes), sorted.
+pub fn read_tree(root: &Path) -> Vec<(String, Vec<u8>)> {
+    let mut out: Vec<_> = walk(root)
+        .into_iter()
+        .map(|file| {
+            let rel = file.strip_prefix(root).unwrap();
+            let rel = rel.to_str().unwrap().replace('\\', "/");
+            (rel, std::fs::read(&file).unwrap())
+        })
+        .collect();
+    out.sort();
+    out
+}
+
+fn walk(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            files.extend(walk(&path));
+        } else {
+            files.push(path);
+        }
+    }
+    files
+}
+
+/// A pak whose payload area is `area` and whose plain index holds `records`
+/// verbatim, however inconsistent. Records are raw (uncompressed)
+/// `(path, payload_offset, payload_len, raw)`, where `raw` sets `raw_len` and
+/// `raw_hash`. The index hash is valid.
+pub fn craft(area: &[u8], records: &[(&str, u64, u64, &[u8])]) -> Vec<u8> {
+    let mut index = MAGIC.to_vec();
+    index.extend_from_slice(&(records.len() as u32).to_le_bytes());
+    for (path, offset, len, raw) in records {
+        index.extend_from_slice(&(path.len() as u16).to_le_bytes());
+        index.extend_from_slice(path.as_bytes());
+        index.extend_from_slice(&offset.to_le_bytes());
+        index.extend_from_slice(&len.to_le_bytes());
+        index.extend_from_slice(&(raw.len() as u64).to_le_bytes());
+        index.push(0); // raw
+        index.extend_from_slice(blake3::hash(raw).as_bytes());
+    }
+
+    let mut pak = MAGIC.to_vec();
+    pak.extend_from_slice(area);
+    let index_offset = pak.len() as u64;
+    pak.extend_from_slice(&index);
+    pak.extend_from_slice(&FOOTER_MAGIC);
+    pak.extend_from_slice(&index_offset.to_le_bytes());
+    pak.extend_from_slice(&(index.len() as u64).to_le_bytes());
+    pak.extend_from_slice(&blake3::hash(&index).as_bytes()[..4]);
+    pak.extend_from_slice(&0u32.to_le_bytes());
+    pak
+}
+
+/// Run the `nepak` binary with `args`.
+pub fn nepak(args: &[&str]) -> Output {
+    std::process::Command::new(env!("CARGO_BIN_EXE_nepak"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// Stdout of a `nepak` run that must succeed.
+pub fn nepak_ok(args: &[&str]) -> String {
+    let out = nepak(args);
+    assert!(
+        out.status.success(),
+        "nepak {args:?}: {}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    String::from_utf8(out.stdout).unwrap()
+}
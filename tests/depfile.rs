@@ -0,0 +1,103 @@
+//! `--depfile` output and `--stamp`.
+
+mod common;
+
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use nepak::pak::{self, BuildOptions};
+
+fn setup(name: &str) -> (std::path::PathBuf, BuildOptions) {
+    let dir = common::scratch(name);
+    let input = dir.join("in");
+    common::write_files(&input, &[("a.txt", b"a"), ("sub/b.txt", b"b")]);
+    let opts = common::options(&input);
+    (dir, opts)
+}
+
+fn display(path: &Path) -> String {
+    path.display().to_string()
+}
+
+#[test]
+fn depfile_lists_dirs_and_sources_with_phony_rules() {
+    let (dir, opts) = setup("depfile-rules");
+    let input = &opts.inputs[0].dir;
+    common::write_files(input, &[("sub/x/dot.txt", b"y")]);
+    let pak_path = dir.join("out.pak");
+    let summary = pak::build(&opts, &pak_path).unwrap();
+
+    let depfile = dir.join("out.d");
+    pak::write_depfile(&depfile, &pak_path, &summary).unwrap();
+    let text = std::fs::read_to_string(&depfile).unwrap();
+
+    let sources = ["a.txt", "sub/b.txt", "sub/x/dot.txt"].map(|p| display(&input.join(p)));
+    let dirs = [
+        display(input),
+        display(&input.join("sub")),
+        display(&input.join("sub/x")),
+    ];
+    let mut expected = format!("{}:", display(&pak_path));
+    for dep in dirs.iter().chain(&sources) {
+        expected += &format!(" \\\n  {dep}");
+    }
+    expected.push('\n');
+    for source in &sources {
+        expected += &format!("\n{source}:\n");
+    }
+    assert_eq!(text, expected);
+}
+
+#[cfg(unix)]
+#[test]
+fn depfile_escapes_make_syntax() {
+    let dir = common::scratch("depfile-escape");
+    let input = dir.join("my in");
+    common::write_files(&input, &[("a #1.txt", b"a"), ("b:c$.txt", b"b")]);
+    let pak_path = dir.join("out.pak");
+    let summary = pak::build(&common::options(&input), &pak_path).unwrap();
+
+    let depfile = dir.join("out.d");
+    pak::write_depfile(&depfile, &pak_path, &summary).unwrap();
+    let text = std::fs::read_to_string(&depfile).unwrap();
+    let input = display(&input).replace(' ', "\\ ");
+    assert!(
+        text.contains(&format!("\n{input}/a\\ \\#1.txt:\n")),
+        "{text}"
+    );
+    assert!(
+        text.contains(&format!("\n{input}/b\\:c$$.txt:\n")),
+        "{text}"
+    );
+}
+
+/// Set `file`'s mtime an hour back, so a rewrite shows even with coarse mtimes.
+fn backdate(file: &Path) -> SystemTime {
+    let old = SystemTime::now() - Duration::from_secs(3600);
+    let f = std::fs::File::options().write(true).open(file).unwrap();
+    f.set_modified(old).unwrap();
+    old
+}
+
+#[test]
+fn stamp_leaves_identical_output_untouched() {
+    let (dir, mut opts) = setup("depfile-stamp");
+    opts.stamp = true;
+    let pak_path = dir.join("out.pak");
+    let mtime = || std::fs::metadata(&pak_path).unwrap().modified().unwrap();
+    assert!(!pak::build(&opts, &pak_path).unwrap().unchanged);
+
+    let old = backdate(&pak_path);
+    assert!(pak::build(&opts, &pak_path).unwrap().unchanged);
+    assert_eq!(mtime(), old);
+
+    common::write_files(&opts.inputs[0].dir, &[("a.txt", b"changed")]);
+    assert!(!pak::build(&opts, &pak_path).unwrap().unchanged);
+    assert_ne!(mtime(), old);
+
+    // Without `stamp` the output is always rewritten.
+    opts.stamp = false;
+    let old = backdate(&pak_path);
+    assert!(!pak::build(&opts, &pak_path).unwrap().unchanged);
+    assert_ne!(mtime(), old);
+}
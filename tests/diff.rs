@@ -0,0 +1,111 @@
+//! `pak::diff`: index-only comparison, rename detection and totals.
+
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use nepak::pak::{self, DiffKind};
+
+fn build(dir: &Path, name: &str, files: &[(&str, &[u8])]) -> PathBuf {
+    let input = dir.join(name);
+    common::write_files(&input, files);
+    let pak_path = dir.join(format!("{name}.pak"));
+    pak::build(&common::options(&input), &pak_path).unwrap();
+    pak_path
+}
+
+fn total_len(files: &[(&str, &[u8])]) -> u64 {
+    files.iter().map(|(_, b)| b.len() as u64).sum()
+}
+
+const OLD: &[(&str, &[u8])] = &[
+    ("a.txt", b"same"),
+    ("b.txt", b"modify me"),
+    ("c.txt", b"move me"),
+    ("dup1", b"dup"),
+    ("dup2", b"dup"),
+    ("gone.txt", b"bye"),
+];
+
+const NEW: &[(&str, &[u8])] = &[
+    ("a.txt", b"same"),
+    ("b.txt", b"modified!!"),
+    ("moved/c.txt", b"move me"),
+    ("new.txt", b"fresh"),
+    ("x/dup", b"dup"),
+];
+
+#[test]
+fn changes_and_renames_are_classified() {
+    let dir = common::scratch("diff-kinds");
+    let old = build(&dir, "old", OLD);
+    let new = build(&dir, "new", NEW);
+
+    let diff = pak::diff(&old, &new).unwrap();
+    let changes: Vec<_> = diff
+        .changes
+        .iter()
+        .map(|c| (c.kind, c.path.as_str(), c.old_path.as_deref()))
+        .collect();
+    // Two removed copies of `dup`, one new: the first (by path) is renamed.
+    assert_eq!(
+        changes,
+        [
+            (DiffKind::Modified, "b.txt", None),
+            (DiffKind::Removed, "dup2", None),
+            (DiffKind::Removed, "gone.txt", None),
+            (DiffKind::Renamed, "moved/c.txt", Some("c.txt")),
+            (DiffKind::Added, "new.txt", None),
+            (DiffKind::Renamed, "x/dup", Some("dup1")),
+        ]
+    );
+
+    let modified = &diff.changes[0];
+    assert_eq!((modified.old_raw_len, modified.new_raw_len), (9, 10));
+    assert_eq!((modified.raw_delta, modified.payload_delta), (1, 1));
+    let removed = &diff.changes[2];
+    assert_eq!(
+        (removed.old_raw_len, removed.new_raw_len, removed.raw_delta),
+        (3, 0, -3)
+    );
+    let added = &diff.changes[4];
+    assert_eq!(
+        (added.old_raw_len, added.new_raw_len, added.raw_delta),
+        (0, 5, 5)
+    );
+    let renamed = &diff.changes[3];
+    assert_eq!(renamed.raw_delta, 0);
+}
+
+#[test]
+fn totals_cover_both_sides() {
+    let dir = common::scratch("diff-totals");
+    let old = build(&dir, "old", OLD);
+    let new = build(&dir, "new", NEW);
+
+    let totals = pak::diff(&old, &new).unwrap().totals;
+    assert_eq!(totals.old_entries, 6);
+    assert_eq!(totals.new_entries, 5);
+    assert_eq!(totals.unchanged, 1);
+    assert_eq!(totals.old_raw, total_len(OLD));
+    assert_eq!(totals.new_raw, total_len(NEW));
+    assert_eq!(
+        totals.raw_delta,
+        total_len(NEW) as i64 - total_len(OLD) as i64
+    );
+    // Stored uncompressed: payload sizes equal raw sizes.
+    assert_eq!(totals.old_payload, totals.old_raw);
+    assert_eq!(totals.new_payload, totals.new_raw);
+    assert_eq!(totals.payload_delta, totals.raw_delta);
+}
+
+#[test]
+fn identical_paks_have_no_changes() {
+    let dir = common::scratch("diff-same");
+    let old = build(&dir, "old", OLD);
+    let new = build(&dir, "new", OLD);
+
+    let diff = pak::diff(&old, &new).unwrap();
+    assert!(diff.is_empty());
+    assert_eq!(diff.totals.unchanged, 6);
+}
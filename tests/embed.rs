@@ -0,0 +1,113 @@
+//! Round trip through the build-script helpers and the embedded reader:
+//! build a pak into `OUT_DIR`, load it as `&'static [u8]` (what `include_bytes!`
+//! yields) and read every file back.
+
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+use std::sync::Once;
+
+use nepak::pak::{self, EmbeddedPak};
+
+/// Sorted by path, like the index.
+const FILES: &[(&str, &[u8])] = &[
+    ("a.txt", b"hello pak\n"),
+    ("empty", b""),
+    (
+        "sub/c.txt",
+        b"repeated repeated repeated repeated repeated\n",
+    ),
+    ("sub/deep/b.bin", &[0, 1, 2, 3, 255, 254, 253, 0, 0, 0]),
+];
+
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("nepak-embed-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Build scripts get `OUT_DIR` from Cargo; set it once for all tests.
+fn out_dir() -> PathBuf {
+    static INIT: Once = Once::new();
+    let dir = std::env::temp_dir().join(format!("nepak-embed-{}-out", std::process::id()));
+    INIT.call_once(|| {
+        std::fs::create_dir_all(&dir).unwrap();
+        std::env::set_var("OUT_DIR", &dir);
+    });
+    dir
+}
+
+fn write_inputs(root: &Path) {
+    for (path, bytes) in FILES {
+        let file = root.join(path);
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        std::fs::write(file, bytes).unwrap();
+    }
+}
+
+fn embed(pak: &Path) -> EmbeddedPak {
+    let bytes: &'static [u8] = Box::leak(std::fs::read(pak).unwrap().into_boxed_slice());
+    EmbeddedPak::open(bytes).unwrap()
+}
+
+fn assert_round_trip(pak: &EmbeddedPak, borrowed: bool) {
+    let paths: Vec<String> = pak.entries().into_iter().map(|e| e.path).collect();
+    let expected: Vec<&str> = FILES.iter().map(|(p, _)| *p).collect();
+    assert_eq!(paths, expected);
+
+    for (path, bytes) in FILES {
+        let got = pak.read(path).unwrap();
+        assert_eq!(&got[..], *bytes, "{path}");
+        assert_eq!(matches!(got, Cow::Borrowed(_)), borrowed, "{path}");
+    }
+    assert!(!pak.contains("missing"));
+    assert!(matches!(
+        pak.read("missing"),
+        Err(pak::PakError::NotFound(_))
+    ));
+}
+
+#[test]
+fn dir_round_trip() {
+    let input = scratch("dir");
+    write_inputs(&input);
+    out_dir();
+
+    let output = pak::cargo_build_dir(&input, "dir.pak").unwrap();
+    assert_round_trip(&embed(&output), true);
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn manifest_round_trip() {
+    let root = scratch("manifest");
+    write_inputs(&root.join("assets"));
+    std::fs::write(root.join("assets/skip.tmp"), b"excluded").unwrap();
+    let manifest = root.join("pak.json");
+    std::fs::write(
+        &manifest,
+        r#"{ "inputs": ["assets"], "exclude": [".tmp"], "compress": true }"#,
+    )
+    .unwrap();
+    out_dir();
+
+    let output = pak::cargo_build_manifest(&manifest, "manifest.pak").unwrap();
+    assert_round_trip(&embed(&output), false);
+}
+
+#[test]
+fn rejects_corrupt_index() {
+    let input = scratch("corrupt");
+    write_inputs(&input);
+    out_dir();
+
+    let output = pak::cargo_build_dir(&input, "corrupt.pak").unwrap();
+    let mut bytes = std::fs::read(output).unwrap();
+    let index_at = bytes.len() - pak::FOOTER_LEN as usize - 1;
+    bytes[index_at] ^= 0xff;
+    let bytes: &'static [u8] = Box::leak(bytes.into_boxed_slice());
+    assert!(matches!(
+        EmbeddedPak::open(bytes),
+        Err(pak::PakError::Invalid(_))
+    ));
+}
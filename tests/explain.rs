@@ -0,0 +1,209 @@
+//! `pak::explain`: every byte of the file is described, field by field, and
+//! `nepak explain` filters the rows by `--range` and `--entry`.
+
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use nepak::pak::{self, LayoutField};
+
+const FILES: &[(&str, &[u8])] = &[("dir/a.txt", b"alpha"), ("dir/b.txt", b"bravo!")];
+
+fn build(dir: &Path, front_coded: bool, compress_index: bool) -> PathBuf {
+    let input = dir.join("in");
+    common::write_files(&input, FILES);
+    let mut opts = common::options(&input);
+    opts.front_code_index = front_coded;
+    opts.compress_index = compress_index;
+    let pak_path = dir.join(format!(
+        "f{}-z{}.pak",
+        front_coded as u8, compress_index as u8
+    ));
+    pak::build(&opts, &pak_path).unwrap();
+    pak_path
+}
+
+/// Fields tile the file: each starts where the previous one ended.
+fn assert_contiguous(fields: &[LayoutField], file_len: u64) {
+    let mut at = 0;
+    for f in fields {
+        assert_eq!(f.offset, at, "{} starts at {}", f.name, f.offset);
+        at += f.len;
+    }
+    assert_eq!(at, file_len);
+}
+
+fn field<'a>(fields: &'a [LayoutField], name: &str) -> &'a LayoutField {
+    fields.iter().find(|f| f.name == name).unwrap()
+}
+
+/// The little-endian u16 stored at a field's offset.
+fn stored_u16(bytes: &[u8], f: &LayoutField) -> u16 {
+    let at = f.offset as usize;
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+#[test]
+fn plain_index_is_dumped_per_field() {
+    let dir = common::scratch("explain-plain");
+    let pak_path = build(&dir, false, false);
+    let fields = pak::explain(&pak_path).unwrap();
+    assert_contiguous(&fields, std::fs::metadata(&pak_path).unwrap().len());
+
+    let record = |i: usize, path: &'static str| {
+        [
+            ("path_len", 2),
+            ("path", 9),
+            ("payload_offset", 8),
+            ("payload_len", 8),
+            ("raw_len", 8),
+            ("payload_kind", 1),
+            ("raw_hash", 32),
+        ]
+        .map(|(name, len)| (format!("index[{i}].{name}"), len, Some(path)))
+    };
+    let mut expected = vec![
+        ("header.magic".to_string(), 8, None),
+        ("payload".to_string(), 5, Some("dir/a.txt")),
+        ("payload".to_string(), 6, Some("dir/b.txt")),
+        ("index.magic".to_string(), 8, None),
+        ("index.entry_count".to_string(), 4, None),
+    ];
+    expected.extend(record(0, "dir/a.txt"));
+    expected.extend(record(1, "dir/b.txt"));
+    for name in ["magic", "index_offset", "index_len"] {
+        expected.push((format!("footer.{name}"), 8, None));
+    }
+    for name in ["index_hash", "flags"] {
+        expected.push((format!("footer.{name}"), 4, None));
+    }
+    let got: Vec<_> = fields
+        .iter()
+        .map(|f| (f.name.clone(), f.len, f.path.as_deref()))
+        .collect();
+    assert_eq!(got, expected);
+
+    assert_eq!(field(&fields, "index[1].payload_offset").value, "13");
+    assert_eq!(field(&fields, "footer.index_offset").value, "19");
+    assert_eq!(field(&fields, "footer.flags").value, "0x0 (plain)");
+}
+
+#[test]
+fn front_coded_index_is_dumped_per_field() {
+    let dir = common::scratch("explain-front-coded");
+    let pak_path = build(&dir, true, false);
+    let bytes = std::fs::read(&pak_path).unwrap();
+    let fields = pak::explain(&pak_path).unwrap();
+    assert_contiguous(&fields, bytes.len() as u64);
+
+    let names: Vec<_> = fields
+        .iter()
+        .filter(|f| f.name.starts_with("index[1]."))
+        .map(|f| (f.name.as_str(), f.value.as_str()))
+        .collect();
+    assert_eq!(
+        names[..3],
+        [
+            ("index[1].shared", "4"),
+            ("index[1].suffix_len", "5"),
+            ("index[1].suffix", "\"b.txt\""),
+        ]
+    );
+    assert_eq!(names.len(), 8);
+    assert_eq!(stored_u16(&bytes, field(&fields, "index[1].shared")), 4);
+    assert_eq!(stored_u16(&bytes, field(&fields, "index[0].suffix_len")), 9);
+    assert_eq!(field(&fields, "index[1].payload_len").value, "6");
+    assert_eq!(field(&fields, "footer.flags").value, "0x1 (front_coded)");
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn compressed_index_is_one_block() {
+    let dir = common::scratch("explain-zstd-index");
+    for front_coded in [false, true] {
+        let pak_path = build(&dir, front_coded, true);
+        let fields = pak::explain(&pak_path).unwrap();
+        assert_contiguous(&fields, std::fs::metadata(&pak_path).unwrap().len());
+        assert!(fields.iter().all(|f| !f.name.starts_with("index[")));
+        let block = field(&fields, "index.block");
+        assert!(block.value.contains("2 entries"), "{}", block.value);
+    }
+}
+
+/// `(offset, field)` rows printed by `nepak explain <pak> <args>`.
+fn explain_rows(pak: &Path, args: &[&str]) -> Vec<(u64, String)> {
+    let mut all = vec!["explain", pak.to_str().unwrap()];
+    all.extend(args);
+    common::nepak_ok(&all)
+        .lines()
+        .skip(1)
+        .map(|line| {
+            let cols: Vec<_> = line.split_whitespace().collect();
+            let offset = u64::from_str_radix(cols[0].trim_start_matches("0x"), 16).unwrap();
+            (offset, cols[2].to_string())
+        })
+        .collect()
+}
+
+fn rows(expected: &[(u64, &str)]) -> Vec<(u64, String)> {
+    expected
+        .iter()
+        .map(|(offset, name)| (*offset, name.to_string()))
+        .collect()
+}
+
+#[test]
+fn range_keeps_fields_overlapping_it() {
+    let dir = common::scratch("explain-range");
+    let pak_path = build(&dir, false, false);
+    let explain = |range: &str| explain_rows(&pak_path, &["--range", range]);
+
+    // END is exclusive: a field ending at START or starting at END is out.
+    assert_eq!(explain("0..8"), rows(&[(0, "header.magic")]));
+    assert_eq!(explain("12..13"), rows(&[(8, "payload")]));
+    assert_eq!(explain("12..14"), rows(&[(8, "payload"), (13, "payload")]));
+    assert_eq!(explain("13..13"), rows(&[]));
+    assert_eq!(
+        explain("0x13..0x1f"),
+        rows(&[(19, "index.magic"), (27, "index.entry_count")])
+    );
+
+    // An open END runs to the end of the file.
+    assert_eq!(
+        explain("0xbf.."),
+        rows(&[(191, "footer.index_hash"), (195, "footer.flags")])
+    );
+    assert_eq!(explain("198.."), rows(&[(195, "footer.flags")]));
+    assert_eq!(explain("199.."), rows(&[]));
+
+    let out = common::nepak(&["explain", pak_path.to_str().unwrap(), "--range", "9..3"]);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("range end 3 is before start 9"));
+}
+
+#[test]
+fn entry_keeps_its_payload_and_index_record() {
+    let dir = common::scratch("explain-entry");
+    let pak_path = build(&dir, false, false);
+
+    let b = explain_rows(&pak_path, &["--entry", "dir/b.txt"]);
+    assert_eq!(b.len(), 1 + 7);
+    assert_eq!(b[0], (13, "payload".to_string()));
+    assert!(b[1..].iter().all(|(_, name)| name.starts_with("index[1].")));
+
+    // Repeated entries add up; other fields stay hidden.
+    let both = explain_rows(&pak_path, &["--entry", "dir/a.txt", "--entry", "dir/b.txt"]);
+    assert_eq!(both.len(), 2 * 8);
+    assert!(explain_rows(&pak_path, &["--entry", "dir"]).is_empty());
+
+    // With --range, a field has to pass both filters.
+    assert_eq!(
+        explain_rows(&pak_path, &["--entry", "dir/b.txt", "--range", "100..120"]),
+        rows(&[
+            (99, "index[1].path_len"),
+            (101, "index[1].path"),
+            (110, "index[1].payload_offset"),
+            (118, "index[1].payload_len"),
+        ])
+    );
+}
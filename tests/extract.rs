@@ -0,0 +1,161 @@
+//! Extraction into existing directories, and cleanup after a failed run.
+
+mod common;
+
+use std::path::Path;
+
+use nepak::pak::{self, ReadLimits};
+
+/// Build `files` into `<dir>/in.pak`, then corrupt the payload of `bad`.
+fn corrupt_pak(dir: &Path, files: &[(&str, &[u8])], bad: &str) -> std::path::PathBuf {
+    let input = dir.join("in");
+    common::write_files(&input, files);
+    let pak_path = dir.join("in.pak");
+    pak::build(&common::options(&input), &pak_path).unwrap();
+
+    let entry = pak::entries(&pak_path)
+        .unwrap()
+        .into_iter()
+        .find(|e| e.path == bad)
+        .unwrap();
+    let mut bytes = std::fs::read(&pak_path).unwrap();
+    bytes[entry.payload_offset as usize] ^= 0xff;
+    std::fs::write(&pak_path, bytes).unwrap();
+    pak_path
+}
+
+#[test]
+fn failed_extract_keeps_preexisting_files() {
+    let dir = common::scratch("extract-cleanup");
+    let pak_path = corrupt_pak(
+        &dir,
+        &[
+            ("a.txt", b"from pak"),
+            ("new/b.txt", b"b"),
+            ("old/c.txt", b"c"),
+            ("z.txt", b"broken"),
+        ],
+        "z.txt",
+    );
+
+    let out = dir.join("out");
+    common::write_files(
+        &out,
+        &[
+            ("a.txt", b"mine"),
+            ("old/mine.txt", b"keep"),
+            ("mine.txt", b"keep"),
+        ],
+    );
+
+    let err = pak::extract(&pak_path, &out, &[]).unwrap_err();
+    assert!(err.to_string().contains("z.txt"), "{err}");
+
+    // a.txt was overwritten (extraction got that far) but is not deleted; what
+    // the run created (new/, new/b.txt, old/c.txt) is gone again.
+    assert_eq!(
+        common::read_tree(&out),
+        [
+            ("a.txt".to_string(), b"from pak".to_vec()),
+            ("mine.txt".to_string(), b"keep".to_vec()),
+            ("old/mine.txt".to_string(), b"keep".to_vec()),
+        ]
+    );
+    assert!(!out.join("new").exists());
+}
+
+#[test]
+fn failed_extract_into_new_directory_leaves_nothing() {
+    let dir = common::scratch("extract-new");
+    let pak_path = corrupt_pak(&dir, &[("a/b.txt", b"b"), ("z.txt", b"z")], "z.txt");
+
+    let out = dir.join("fresh/out");
+    std::fs::create_dir_all(out.parent().unwrap()).unwrap();
+    assert!(pak::extract(&pak_path, &out, &[]).is_err());
+    assert!(!out.exists());
+    assert!(dir.join("fresh").exists(), "an existing parent stays");
+
+    // Missing parents of the output directory are removed along with it.
+    let out = dir.join("new/nested/out");
+    assert!(pak::extract(&pak_path, &out, &[]).is_err());
+    assert!(!dir.join("new").exists());
+}
+
+#[test]
+fn extract_round_trips() {
+    let dir = common::scratch("extract-ok");
+    let files: &[(&str, &[u8])] = &[("a.txt", b"a"), ("d/e/f.bin", &[0, 1, 2]), ("empty", b"")];
+    let input = dir.join("in");
+    common::write_files(&input, files);
+    let pak_path = dir.join("in.pak");
+    pak::build(&common::options(&input), &pak_path).unwrap();
+
+    let out = dir.join("out");
+    pak::extract(&pak_path, &out, &[]).unwrap();
+    assert_eq!(common::read_tree(&out), common::read_tree(&input));
+}
+
+#[test]
+fn extract_with_limits_enforces_them() {
+    let dir = common::scratch("extract-limits");
+    let input = dir.join("in");
+    common::write_files(&input, &[("a.txt", b"a"), ("big.bin", &[7; 100])]);
+    let pak_path = dir.join("in.pak");
+    pak::build(&common::options(&input), &pak_path).unwrap();
+
+    let out = dir.join("out");
+    let limits = ReadLimits {
+        max_raw_len: 99,
+        ..Default::default()
+    };
+    let err = pak::extract_with_limits(&pak_path, &out, &[], &limits).unwrap_err();
+    assert!(err.to_string().contains("big.bin is 100 bytes"), "{err}");
+    assert!(!out.exists());
+
+    let limits = ReadLimits {
+        max_entries: 1,
+        ..Default::default()
+    };
+    let err = pak::extract_with_limits(&pak_path, &out, &[], &limits).unwrap_err();
+    assert!(err.to_string().contains("entries"), "{err}");
+    assert!(!out.exists());
+
+    let limits = ReadLimits {
+        max_entries: 2,
+        max_raw_len: 100,
+        ..Default::default()
+    };
+    pak::extract_with_limits(&pak_path, &out, &[], &limits).unwrap();
+    assert_eq!(common::read_tree(&out), common::read_tree(&input));
+
+    // The other read operations take the same limits.
+    let limits = ReadLimits {
+        max_entries: 1,
+        ..Default::default()
+    };
+    assert!(pak::entries_with_limits(&pak_path, &limits).is_err());
+    assert!(pak::verify_with_limits(&pak_path, &limits).is_err());
+    assert!(pak::info_with_limits(&pak_path, &limits).is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn extract_refuses_symlinked_parent_directory() {
+    let dir = common::scratch("extract-symlink");
+    let input = dir.join("in");
+    common::write_files(&input, &[("a.txt", b"a"), ("sub/evil.txt", b"evil")]);
+    let pak_path = dir.join("in.pak");
+    pak::build(&common::options(&input), &pak_path).unwrap();
+
+    let out = dir.join("out");
+    let outside = dir.join("outside");
+    std::fs::create_dir_all(&out).unwrap();
+    std::fs::create_dir_all(&outside).unwrap();
+    std::os::unix::fs::symlink(&outside, out.join("sub")).unwrap();
+
+    let err = pak::extract(&pak_path, &out, &[]).unwrap_err();
+    assert!(err.to_string().contains("symlink"), "{err}");
+    assert_eq!(std::fs::read_dir(&outside).unwrap().count(), 0);
+    // The planted link is not the run's to remove.
+    assert!(out.join("sub").symlink_metadata().unwrap().is_symlink());
+}
@@ -0,0 +1,75 @@
+//! Crafted paks whose index declares impossible payload ranges. Every reader
+//! must reject them (or, for `verify`, report them) instead of panicking on
+//! overflowing arithmetic.
+
+mod common;
+
+use std::path::Path;
+
+use nepak::pak::{self, EmbeddedPak, PakFile, StatsOptions, VerifyIssueKind};
+
+/// `common::craft` with `(path, payload_offset, payload_len)` records claiming `x`.
+fn craft(area: &[u8], records: &[(&str, u64, u64)]) -> Vec<u8> {
+    let records: Vec<_> = records
+        .iter()
+        .map(|&(path, offset, len)| (path, offset, len, &b"x"[..]))
+        .collect();
+    common::craft(area, &records)
+}
+
+fn write(dir: &Path, name: &str, bytes: &[u8]) -> std::path::PathBuf {
+    let path = dir.join(name);
+    std::fs::write(&path, bytes).unwrap();
+    path
+}
+
+fn assert_rejected<T: std::fmt::Debug>(what: &str, result: pak::PakResult<T>) {
+    let err = result.expect_err(what).to_string();
+    assert!(err.contains("outside the payload area"), "{what}: {err}");
+}
+
+#[test]
+fn overflowing_payload_lengths_are_rejected() {
+    let dir = common::scratch("hostile-overflow");
+    let huge = u64::MAX / 2 + 10;
+    let bytes = craft(b"x", &[("a", 8, huge), ("b", 8, huge)]);
+    let pak = write(&dir, "huge.pak", &bytes);
+    let good = write(&dir, "good.pak", &craft(b"x", &[("a", 8, 1)]));
+
+    assert_rejected("info", pak::info(&pak));
+    assert_rejected("stats", pak::stats(&pak, &StatsOptions::default()));
+    assert_rejected("diff", pak::diff(&good, &pak));
+    assert_rejected("entries", pak::entries(&pak));
+    assert_rejected("PakFile", PakFile::open(&pak));
+    let leaked: &'static [u8] = Box::leak(bytes.into_boxed_slice());
+    assert_rejected("EmbeddedPak", EmbeddedPak::open(leaked));
+
+    // verify reports what it can instead of failing outright; the one real
+    // payload byte is covered by no entry.
+    let report = pak::verify(&pak).unwrap();
+    let kinds: Vec<_> = report.issues.iter().map(|i| i.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            VerifyIssueKind::OutOfBounds,
+            VerifyIssueKind::OutOfBounds,
+            VerifyIssueKind::Gap
+        ]
+    );
+}
+
+#[test]
+fn payloads_must_stay_between_header_and_index() {
+    let dir = common::scratch("hostile-ranges");
+    let cases: [(&str, u64, u64); 3] = [
+        ("into-index", 8, 2),
+        ("over-header", 0, 1),
+        ("wrapping", u64::MAX, 2),
+    ];
+    for (name, offset, len) in cases {
+        let pak = write(&dir, name, &craft(b"x", &[("a", offset, len)]));
+        assert_rejected(name, pak::info(&pak));
+    }
+    let pak = write(&dir, "ok", &craft(b"x", &[("a", 8, 1)]));
+    assert_eq!(pak::info(&pak).unwrap().entries, 1);
+}
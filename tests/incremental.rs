@@ -0,0 +1,155 @@
+//! `pak::build_incremental`: reused payloads give the same bytes as a full
+//! build, and only unchanged files are reused.
+
+mod common;
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use nepak::pak::{self, BuildCache, BuildOptions, BuildSummary, CancelToken, DiffKind};
+
+const FILES: &[(&str, &[u8])] = &[
+    ("a.txt", b"alpha alpha alpha"),
+    ("b.txt", b"bravo bravo"),
+    ("c/d.json", b"{ \"d\": 1 }"),
+];
+
+/// Set the mtime of `file` to `age` ago, outside the cache's racy window.
+fn age(file: &Path, age: Duration) {
+    let f = std::fs::File::options().write(true).open(file).unwrap();
+    f.set_modified(SystemTime::now() - age).unwrap();
+}
+
+fn age_all(input: &Path) {
+    for (path, _) in FILES {
+        age(&input.join(path), Duration::from_secs(3600));
+    }
+}
+
+fn setup(name: &str) -> (PathBuf, PathBuf) {
+    let dir = common::scratch(name);
+    let input = dir.join("in");
+    common::write_files(&input, FILES);
+    (input, dir.join("out.pak"))
+}
+
+fn rebuild(opts: &BuildOptions, output: &Path, cache: &mut BuildCache) -> BuildSummary {
+    let summary = pak::build_incremental(opts, output, cache, &CancelToken::new(), |_| {}).unwrap();
+    // Byte-identical to an in-memory full build of the same inputs.
+    let check = pak::check(opts, output).unwrap();
+    assert!(check.is_identical(), "{:?}", check.first_difference);
+    summary
+}
+
+fn changes(summary: &BuildSummary) -> Vec<(DiffKind, &str)> {
+    let diff = summary.changes.as_ref().unwrap();
+    diff.changes
+        .iter()
+        .map(|c| (c.kind, c.path.as_str()))
+        .collect()
+}
+
+#[test]
+fn reused_payloads_match_a_full_build() {
+    let (input, output) = setup("incremental-same");
+    age_all(&input);
+    let full = output.with_file_name("full.pak");
+
+    for compress in [false, cfg!(feature = "zstd")] {
+        let mut opts = common::options(&input);
+        opts.compress = compress;
+        let mut cache = BuildCache::new();
+
+        let first = rebuild(&opts, &output, &mut cache);
+        assert_eq!((first.reused, first.changes.is_none()), (0, true));
+        let first_bytes = std::fs::read(&output).unwrap();
+
+        let second = rebuild(&opts, &output, &mut cache);
+        assert_eq!(second.reused, 3);
+        assert!(changes(&second).is_empty());
+        assert_eq!(std::fs::read(&output).unwrap(), first_bytes);
+
+        pak::build(&opts, &full).unwrap();
+        assert_eq!(std::fs::read(&full).unwrap(), first_bytes);
+    }
+}
+
+#[test]
+fn edits_invalidate_only_their_entry() {
+    let (input, output) = setup("incremental-edits");
+    age_all(&input);
+    let opts = common::options(&input);
+    let mut cache = BuildCache::new();
+    rebuild(&opts, &output, &mut cache);
+
+    std::fs::write(input.join("b.txt"), b"bravo, edited").unwrap();
+    age(&input.join("b.txt"), Duration::from_secs(1800));
+    let summary = rebuild(&opts, &output, &mut cache);
+    assert_eq!(summary.reused, 2);
+    assert_eq!(changes(&summary), [(DiffKind::Modified, "b.txt")]);
+
+    // Same size, different bytes and mtime.
+    std::fs::write(input.join("a.txt"), b"ALPHA alpha alpha").unwrap();
+    age(&input.join("a.txt"), Duration::from_secs(1800));
+    let summary = rebuild(&opts, &output, &mut cache);
+    assert_eq!(summary.reused, 2);
+    assert_eq!(changes(&summary), [(DiffKind::Modified, "a.txt")]);
+
+    common::write_files(&input, &[("c/new.txt", b"new")]);
+    age(&input.join("c/new.txt"), Duration::from_secs(1800));
+    let summary = rebuild(&opts, &output, &mut cache);
+    assert_eq!(summary.reused, 3);
+    assert_eq!(changes(&summary), [(DiffKind::Added, "c/new.txt")]);
+
+    std::fs::remove_file(input.join("a.txt")).unwrap();
+    let summary = rebuild(&opts, &output, &mut cache);
+    assert_eq!(summary.reused, 3);
+    assert_eq!(changes(&summary), [(DiffKind::Removed, "a.txt")]);
+}
+
+#[test]
+fn payload_settings_bypass_reuse() {
+    let (input, output) = setup("incremental-settings");
+    age_all(&input);
+    let base = common::options(&input);
+
+    let mut variants: Vec<(&str, BuildOptions)> = Vec::new();
+    let mut opts = base.clone();
+    opts.zstd_level += 1;
+    variants.push(("zstd_level", opts));
+    let mut opts = base.clone();
+    opts.codec = Some("raw".into());
+    variants.push(("codec", opts));
+    let mut opts = base.clone();
+    opts.transforms = vec![pak::TransformRule::new("**/*.json", pak::JsonMinify)];
+    variants.push(("transforms", opts));
+    if cfg!(feature = "zstd") {
+        let mut opts = base.clone();
+        opts.compress = true;
+        variants.push(("compress", opts));
+    }
+
+    for (what, changed) in &variants {
+        let mut cache = BuildCache::new();
+        rebuild(&base, &output, &mut cache);
+        assert_eq!(rebuild(&base, &output, &mut cache).reused, 3, "{what}");
+
+        assert_eq!(rebuild(changed, &output, &mut cache).reused, 0, "{what}");
+        assert_eq!(rebuild(changed, &output, &mut cache).reused, 3, "{what}");
+    }
+}
+
+#[test]
+fn files_in_the_racy_window_are_read_again() {
+    let (input, output) = setup("incremental-racy");
+    let opts = common::options(&input);
+    let mut cache = BuildCache::new();
+
+    // Just written: an edit within the mtime granularity could go unseen.
+    rebuild(&opts, &output, &mut cache);
+    assert_eq!(rebuild(&opts, &output, &mut cache).reused, 0);
+
+    age_all(&input);
+    assert_eq!(rebuild(&opts, &output, &mut cache).reused, 0);
+    assert_eq!(rebuild(&opts, &output, &mut cache).reused, 3);
+}
@@ -0,0 +1,99 @@
+//! `pak::info`: slack bytes, index savings, format version and extreme entries.
+
+mod common;
+
+use nepak::pak::{self, BuildOptions, PakInfo, FOOTER_LEN};
+
+/// `(path, raw, payload)` of the smallest and largest entries.
+fn extremes(info: &PakInfo) -> [(&str, u64, u64); 2] {
+    [&info.smallest, &info.largest].map(|s| {
+        let s = s.as_ref().unwrap();
+        (s.path.as_str(), s.raw, s.payload)
+    })
+}
+
+#[test]
+fn slack_counts_gaps_once_despite_overlaps() {
+    let dir = common::scratch("info-slack");
+    let pak_path = dir.join("gaps.pak");
+    // Payload area 8..48: a and b overlap on 13..18, c starts after a
+    // 5-byte gap and is followed by 10 unused bytes.
+    let bytes = common::craft(
+        &[0; 40],
+        &[
+            ("a", 8, 10, b"aaaaaaaaaa"),
+            ("b", 13, 5, b"bbbbbbbbbbbbbbbbbbbb"),
+            ("c", 23, 15, b"c"),
+        ],
+    );
+    std::fs::write(&pak_path, &bytes).unwrap();
+
+    let info = pak::info(&pak_path).unwrap();
+    assert_eq!(info.file_len, bytes.len() as u64);
+    assert_eq!(info.format_version, 1);
+    assert_eq!(info.footer.index_offset, 48);
+    assert_eq!(info.entries, 3);
+    assert_eq!((info.raw_bytes, info.payload_bytes), (31, 30));
+    assert_eq!(info.slack_bytes, 15);
+    assert_eq!(extremes(&info), [("c", 1, 15), ("b", 20, 5)]);
+
+    assert_eq!(info.index_encoding, "plain");
+    assert_eq!(info.index_plain_len, info.footer.index_len);
+    assert_eq!(info.index_saved_bytes, 0);
+}
+
+#[test]
+fn front_coded_index_savings_and_trailing_slack() {
+    let dir = common::scratch("info-front-coded");
+    let input = dir.join("in");
+    common::write_files(
+        &input,
+        &[
+            ("data/a.txt", b"alpha"),
+            ("data/levels/one.map", b"one"),
+            ("data/levels/two.map", b"0123456789"),
+        ],
+    );
+    let pak_path = dir.join("fc.pak");
+    let opts = BuildOptions {
+        front_code_index: true,
+        ..common::options(&input)
+    };
+    pak::build(&opts, &pak_path).unwrap();
+
+    // Insert 7 unused bytes between the last payload and the index. Only the
+    // footer's index offset changes; the index and its hash stay valid.
+    let mut bytes = std::fs::read(&pak_path).unwrap();
+    let footer = bytes.len() - FOOTER_LEN as usize;
+    let field = footer + 8..footer + 16;
+    let index_offset = u64::from_le_bytes(bytes[field.clone()].try_into().unwrap());
+    assert_eq!(index_offset, 8 + 5 + 3 + 10);
+    let index_offset = index_offset as usize;
+    bytes.splice(index_offset..index_offset, [0; 7]);
+    let field = field.start + 7..field.end + 7;
+    bytes[field].copy_from_slice(&(index_offset as u64 + 7).to_le_bytes());
+    std::fs::write(&pak_path, &bytes).unwrap();
+
+    let info = pak::info(&pak_path).unwrap();
+    assert_eq!(info.file_len, bytes.len() as u64);
+    assert_eq!(info.format_version, 1);
+    assert_eq!(info.slack_bytes, 7);
+    assert_eq!(
+        extremes(&info),
+        [
+            ("data/levels/one.map", 3, 3),
+            ("data/levels/two.map", 10, 10)
+        ]
+    );
+
+    // Plain records are `u16 len + path + 57`, front-coded ones
+    // `u16 shared + u16 suffix_len + suffix + 57`: each record saves
+    // `shared - 2` bytes, with shared prefixes of 0, 5 and 12 bytes.
+    assert_eq!(info.index_encoding, "front_coded");
+    assert_eq!(info.index_plain_len, 8 + 4 + 3 * (2 + 57) + 10 + 19 + 19);
+    assert_eq!(info.index_saved_bytes, -2 + 3 + 10);
+    assert_eq!(
+        info.footer.index_len as i64,
+        info.index_plain_len as i64 - info.index_saved_bytes
+    );
+}
@@ -0,0 +1,91 @@
+//! Several input roots: `dir[:mount]` specs, collisions and override order.
+
+mod common;
+
+use std::path::Path;
+
+use nepak::pak::{self, BuildOptions, InputRoot, PakError};
+
+fn parse(spec: &str) -> (String, String) {
+    let root = InputRoot::parse(spec, "default");
+    (root.dir.display().to_string(), root.mount)
+}
+
+fn pair(dir: &str, mount: &str) -> (String, String) {
+    (dir.to_string(), mount.to_string())
+}
+
+#[test]
+fn parse_splits_on_the_last_colon() {
+    assert_eq!(parse("./assets"), pair("./assets", "default"));
+    assert_eq!(parse("./assets:game"), pair("./assets", "game"));
+    assert_eq!(parse("../a:b/c"), pair("../a", "b/c"));
+    assert_eq!(parse("./gen:"), pair("./gen", ""));
+    assert_eq!(parse(":game"), pair(":game", "default"));
+}
+
+#[test]
+fn parse_keeps_drive_letters() {
+    assert_eq!(parse(r"C:\assets"), pair(r"C:\assets", "default"));
+    assert_eq!(parse("d:/assets"), pair("d:/assets", "default"));
+    assert_eq!(parse(r"C:\assets:game"), pair(r"C:\assets", "game"));
+}
+
+#[test]
+fn parse_reads_doubled_colons_as_literal() {
+    assert_eq!(parse("./a::b"), pair("./a:b", "default"));
+    assert_eq!(parse("./a::b:game"), pair("./a:b", "game"));
+    assert_eq!(parse("./a:::game"), pair("./a:", "game"));
+    assert_eq!(parse("./a:x::y"), pair("./a", "x:y"));
+}
+
+fn two_roots(dir: &Path, allow_override: bool) -> BuildOptions {
+    let (base, patch) = (dir.join("base"), dir.join("patch"));
+    common::write_files(&base, &[("a.txt", b"base a"), ("b.txt", b"base b")]);
+    common::write_files(&patch, &[("x/a.txt", b"patch a"), ("c.txt", b"patch c")]);
+    BuildOptions {
+        inputs: vec![InputRoot::new(&base, "x"), InputRoot::new(&patch, "")],
+        allow_override,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn collision_names_both_sources() {
+    let dir = common::scratch("inputs-collision");
+    let opts = two_roots(&dir, false);
+    let pak_path = dir.join("out.pak");
+    let err = pak::build(&opts, &pak_path).unwrap_err();
+    match &err {
+        PakError::Collision {
+            path,
+            first,
+            second,
+        } => {
+            assert_eq!(path, "x/a.txt");
+            assert_eq!(first, &dir.join("base").join("a.txt"));
+            assert_eq!(second, &dir.join("patch").join("x/a.txt"));
+        }
+        other => panic!("not a collision: {other:?}"),
+    }
+    let msg = err.to_string();
+    assert!(msg.contains("base") && msg.contains("patch"), "{msg}");
+    assert!(!pak_path.exists());
+}
+
+#[test]
+fn later_roots_win_with_allow_override() {
+    let dir = common::scratch("inputs-override");
+    let pak_path = dir.join("out.pak");
+    let summary = pak::build(&two_roots(&dir, true), &pak_path).unwrap();
+    assert_eq!(summary.entries, 3);
+    assert_eq!(pak::entry_bytes(&pak_path, "x/a.txt").unwrap(), b"patch a");
+    assert_eq!(pak::entry_bytes(&pak_path, "x/b.txt").unwrap(), b"base b");
+    assert_eq!(pak::entry_bytes(&pak_path, "c.txt").unwrap(), b"patch c");
+
+    // Swapping the roots swaps the winner.
+    let mut opts = two_roots(&dir, true);
+    opts.inputs.reverse();
+    pak::build(&opts, &pak_path).unwrap();
+    assert_eq!(pak::entry_bytes(&pak_path, "x/a.txt").unwrap(), b"base a");
+}
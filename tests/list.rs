@@ -0,0 +1,141 @@
+//! `nepak list`: output formats, `--fields`, `--sort`/`--reverse` and `--glob`.
+
+mod common;
+
+use std::path::{Path, PathBuf};
+
+/// A pak whose path, size, payload and offset orders all differ:
+///
+/// | path      | offset | payload | raw |
+/// |-----------|--------|---------|-----|
+/// | a.txt     | 13     | 3       | 30  |
+/// | b/c.bin   | 16     | 20      | 10  |
+/// | b/d/e.png | 8      | 5       | 20  |
+fn pak(name: &str) -> PathBuf {
+    let dir = common::scratch(name);
+    let pak_path = dir.join("list.pak");
+    let bytes = common::craft(
+        &[0; 28],
+        &[
+            ("a.txt", 13, 3, &[b'a'; 30]),
+            ("b/c.bin", 16, 20, &[b'c'; 10]),
+            ("b/d/e.png", 8, 5, &[b'e'; 20]),
+        ],
+    );
+    std::fs::write(&pak_path, bytes).unwrap();
+    pak_path
+}
+
+/// `nepak list --pak <pak> <args>`, one stdout line per item.
+fn list(pak: &Path, args: &[&str]) -> Vec<String> {
+    let mut all = vec!["list", "--pak", pak.to_str().unwrap()];
+    all.extend(args);
+    common::nepak_ok(&all).lines().map(str::to_string).collect()
+}
+
+#[test]
+fn sorts_by_each_key_and_reverses() {
+    let pak = pak("list-sort");
+    for (sort, expected) in [
+        ("path", ["a.txt", "b/c.bin", "b/d/e.png"]),
+        ("size", ["b/c.bin", "b/d/e.png", "a.txt"]),
+        ("payload", ["a.txt", "b/d/e.png", "b/c.bin"]),
+        ("offset", ["b/d/e.png", "a.txt", "b/c.bin"]),
+    ] {
+        assert_eq!(list(&pak, &["--sort", sort]), expected, "{sort}");
+
+        let mut reversed = expected;
+        reversed.reverse();
+        assert_eq!(
+            list(&pak, &["--sort", sort, "--reverse"]),
+            reversed,
+            "{sort}"
+        );
+    }
+}
+
+#[test]
+fn star_stays_in_one_directory_and_double_star_crosses() {
+    let pak = pak("list-glob");
+    for (glob, expected) in [
+        ("*", &["a.txt"][..]),
+        ("*.*", &["a.txt"]),
+        ("b/*", &["b/c.bin"]),
+        ("b/**", &["b/c.bin", "b/d/e.png"]),
+        ("**/*.png", &["b/d/e.png"]),
+        ("**", &["a.txt", "b/c.bin", "b/d/e.png"]),
+        ("c.bin", &[]),
+    ] {
+        assert_eq!(list(&pak, &["--glob", glob]), expected, "{glob}");
+    }
+
+    // Repeated globs match any of them.
+    assert_eq!(
+        list(&pak, &["--glob", "*.txt", "--glob", "**/*.png"]),
+        ["a.txt", "b/d/e.png"]
+    );
+
+    let out = common::nepak(&["list", "--pak", pak.to_str().unwrap(), "--glob", "a/[b"]);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("bad glob 'a/[b'"));
+}
+
+#[test]
+fn fields_keep_the_requested_order() {
+    let pak = pak("list-fields");
+    assert_eq!(
+        list(&pak, &["--fields", "raw_len,path", "--glob", "b/**"]),
+        ["10\tb/c.bin", "20\tb/d/e.png"]
+    );
+    assert_eq!(
+        list(&pak, &["--format", "csv", "--fields", "payload_len,path"]),
+        ["payload_len,path", "3,a.txt", "20,b/c.bin", "5,b/d/e.png"]
+    );
+    assert_eq!(
+        list(
+            &pak,
+            &[
+                "--format",
+                "ndjson",
+                "--fields",
+                "raw_len,path",
+                "--glob",
+                "*"
+            ]
+        ),
+        [r#"{"raw_len":30,"path":"a.txt"}"#]
+    );
+}
+
+#[test]
+fn json_ndjson_and_csv_hold_every_field_by_default() {
+    let pak = pak("list-formats");
+    let hash = blake3::hash(&[b'a'; 30]).to_hex().to_string();
+    let a = serde_json::json!({
+        "path": "a.txt",
+        "payload_offset": 13,
+        "payload_len": 3,
+        "raw_len": 30,
+        "payload_kind": "raw",
+        "raw_hash_hex": hash,
+    });
+
+    let json: serde_json::Value =
+        serde_json::from_str(&list(&pak, &["--format", "json"]).join("\n")).unwrap();
+    let json = json.as_array().unwrap();
+    assert_eq!(json.len(), 3);
+    assert_eq!(json[0], a);
+
+    let ndjson = list(&pak, &["--format", "ndjson", "--sort", "size"]);
+    assert_eq!(ndjson.len(), 3);
+    assert_eq!(ndjson[2], a.to_string());
+
+    let csv = list(&pak, &["--format", "csv", "--glob", "*"]);
+    assert_eq!(
+        csv,
+        [
+            "path,payload_offset,payload_len,raw_len,payload_kind,raw_hash_hex".to_string(),
+            format!("a.txt,13,3,30,raw,{hash}"),
+        ]
+    );
+}
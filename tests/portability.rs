@@ -0,0 +1,86 @@
+//! Build-time portability policy and `--nfc` normalization.
+//!
+//! The offending names can only be created where the filesystem stores them
+//! as written and keeps case apart, so these tests run on Linux only.
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use nepak::pak::{self, PakError, PortabilityKind, PortabilityMode};
+
+const DECOMPOSED: &str = "cafe\u{301}.txt";
+const COMPOSED: &str = "caf\u{e9}.txt";
+
+#[test]
+fn warn_mode_reports_issues_and_builds() {
+    let dir = common::scratch("portability-warn");
+    let input = dir.join("in");
+    common::write_files(
+        &input,
+        &[("Readme", b"1"), ("readme", b"2"), ("aux.json", b"{}")],
+    );
+    let pak_path = dir.join("out.pak");
+
+    let summary = pak::build(&common::options(&input), &pak_path).unwrap();
+    let issues: Vec<_> = summary
+        .portability
+        .iter()
+        .map(|i| (i.path.as_str(), &i.kind))
+        .collect();
+    assert_eq!(
+        issues,
+        [
+            ("aux.json", &PortabilityKind::ReservedName),
+            (
+                "readme",
+                &PortabilityKind::CaseCollision {
+                    other: "Readme".into()
+                }
+            ),
+        ]
+    );
+    assert_eq!(pak::entries(&pak_path).unwrap().len(), 3);
+}
+
+#[test]
+fn error_mode_fails_the_build() {
+    let dir = common::scratch("portability-error");
+    let input = dir.join("in");
+    common::write_files(&input, &[("ok.txt", b"ok"), ("bad:name.txt", b"x")]);
+    let pak_path = dir.join("out.pak");
+    let mut opts = common::options(&input);
+    opts.portability = PortabilityMode::Error;
+
+    match pak::build(&opts, &pak_path) {
+        Err(PakError::Portability(issues)) => {
+            assert_eq!(issues.len(), 1);
+            assert_eq!(issues[0].path, "bad:name.txt");
+            assert_eq!(issues[0].kind, PortabilityKind::ReservedChar(':'));
+        }
+        other => panic!("build not refused: {other:?}"),
+    }
+    assert!(!pak_path.exists());
+
+    std::fs::remove_file(input.join("bad:name.txt")).unwrap();
+    assert!(pak::build(&opts, &pak_path).unwrap().portability.is_empty());
+}
+
+#[test]
+fn nfc_normalizes_entry_paths() {
+    let dir = common::scratch("portability-nfc");
+    let input = dir.join("in");
+    common::write_files(&input, &[(DECOMPOSED, b"coffee")]);
+    let pak_path = dir.join("out.pak");
+
+    let mut opts = common::options(&input);
+    let summary = pak::build(&opts, &pak_path).unwrap();
+    assert_eq!(summary.portability.len(), 1);
+    assert_eq!(summary.portability[0].kind, PortabilityKind::NotNfc);
+    assert_eq!(pak::entries(&pak_path).unwrap()[0].path, DECOMPOSED);
+
+    opts.nfc = true;
+    let summary = pak::build(&opts, &pak_path).unwrap();
+    assert!(summary.portability.is_empty());
+    assert_eq!(pak::entries(&pak_path).unwrap()[0].path, COMPOSED);
+    assert_eq!(pak::entry_bytes(&pak_path, COMPOSED).unwrap(), b"coffee");
+}
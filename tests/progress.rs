@@ -0,0 +1,142 @@
+//! Progress events and cancellation of build, extract and verify.
+
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use nepak::pak::{self, CancelToken, PakError, Progress, Stage};
+
+const FILES: &[(&str, &[u8])] = &[("a.txt", b"alpha"), ("b/c.txt", b"charlie!")];
+
+/// `(stage, done, total, bytes_done, bytes_total, current)` of one event.
+type Event = (Stage, u64, u64, u64, u64, Option<String>);
+
+fn event(p: Progress) -> Event {
+    (
+        p.stage,
+        p.done,
+        p.total,
+        p.bytes_done,
+        p.bytes_total,
+        p.current,
+    )
+}
+
+fn input(dir: &Path) -> PathBuf {
+    let input = dir.join("in");
+    common::write_files(&input, FILES);
+    input
+}
+
+fn some(path: &str) -> Option<String> {
+    Some(path.to_string())
+}
+
+#[test]
+fn build_extract_and_verify_report_each_entry() {
+    let dir = common::scratch("progress-events");
+    let opts = common::options(&input(&dir));
+    let pak_path = dir.join("out.pak");
+    let cancel = CancelToken::new();
+
+    let mut events = Vec::new();
+    pak::build_with_progress(&opts, &pak_path, &cancel, |p| events.push(event(p))).unwrap();
+    use Stage::*;
+    assert_eq!(
+        events,
+        [
+            (Scanning, 0, 0, 0, 0, None),
+            (WritingPayloads, 0, 2, 0, 13, None),
+            (WritingPayloads, 1, 2, 5, 13, some("a.txt")),
+            (WritingPayloads, 2, 2, 13, 13, some("b/c.txt")),
+            (WritingIndex, 0, 1, 0, 0, None),
+            (WritingIndex, 1, 1, 0, 0, None),
+            (Finalizing, 0, 1, 0, 0, None),
+            (Finalizing, 1, 1, 0, 0, None),
+        ]
+    );
+
+    let mut events = Vec::new();
+    let out = dir.join("out");
+    pak::extract_with_progress(&pak_path, &out, &[], &cancel, |p| events.push(event(p))).unwrap();
+    assert_eq!(
+        events,
+        [
+            (Extracting, 0, 2, 0, 13, None),
+            (Extracting, 1, 2, 5, 13, some("a.txt")),
+            (Extracting, 2, 2, 13, 13, some("b/c.txt")),
+        ]
+    );
+
+    // Verify counts stored payload bytes (equal to raw here: nothing is compressed).
+    let mut events = Vec::new();
+    pak::verify_with_progress(&pak_path, &cancel, |p| events.push(event(p))).unwrap();
+    assert_eq!(
+        events,
+        [
+            (Verifying, 0, 2, 0, 13, None),
+            (Verifying, 1, 2, 5, 13, some("a.txt")),
+            (Verifying, 2, 2, 13, 13, some("b/c.txt")),
+        ]
+    );
+}
+
+/// Progress callback that cancels `cancel` once the first entry is done.
+fn cancel_after_first(cancel: &CancelToken) -> impl FnMut(Progress) + '_ {
+    move |p| {
+        if p.done == 1 && p.current.is_some() {
+            cancel.cancel();
+        }
+    }
+}
+
+#[test]
+fn cancelled_build_keeps_previous_output() {
+    let dir = common::scratch("progress-cancel-build");
+    let opts = common::options(&input(&dir));
+    let pak_path = dir.join("out.pak");
+    std::fs::write(&pak_path, b"previous").unwrap();
+
+    let cancel = CancelToken::new();
+    let err = pak::build_with_progress(&opts, &pak_path, &cancel, cancel_after_first(&cancel))
+        .unwrap_err();
+    assert!(matches!(err, PakError::Cancelled), "{err}");
+    assert_eq!(std::fs::read(&pak_path).unwrap(), b"previous");
+    // No temp or lock files are left next to the output.
+    let names: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name())
+        .collect();
+    assert_eq!(names.len(), 2, "{names:?}");
+
+    // A token cancelled up front stops the build before anything is written.
+    let err = pak::build_with_progress(&opts, &dir.join("new.pak"), &cancel, |_| {}).unwrap_err();
+    assert!(matches!(err, PakError::Cancelled), "{err}");
+    assert!(!dir.join("new.pak").exists());
+}
+
+#[test]
+fn cancelled_extract_and_verify_stop_early() {
+    let dir = common::scratch("progress-cancel-read");
+    let opts = common::options(&input(&dir));
+    let pak_path = dir.join("out.pak");
+    pak::build(&opts, &pak_path).unwrap();
+
+    let cancel = CancelToken::new();
+    let out = dir.join("out");
+    let err =
+        pak::extract_with_progress(&pak_path, &out, &[], &cancel, cancel_after_first(&cancel))
+            .unwrap_err();
+    assert!(matches!(err, PakError::Cancelled), "{err}");
+    assert!(!out.exists(), "a cancelled extract removes what it created");
+
+    let cancel = CancelToken::new();
+    let mut seen = 0;
+    let err = pak::verify_with_progress(&pak_path, &cancel, |p| {
+        seen = p.done;
+        cancel_after_first(&cancel)(p);
+    })
+    .unwrap_err();
+    assert!(matches!(err, PakError::Cancelled), "{err}");
+    assert_eq!(seen, 1);
+}
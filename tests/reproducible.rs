@@ -0,0 +1,39 @@
+//! Builds are byte-identical regardless of build time and file mtimes.
+
+mod common;
+
+use std::fs::File;
+use std::time::{Duration, SystemTime};
+
+use nepak::pak;
+
+#[test]
+fn output_ignores_source_date_epoch_and_mtimes() {
+    let dir = common::scratch("reproducible");
+    let input = dir.join("in");
+    let files: &[(&str, &[u8])] = &[("a.txt", b"alpha"), ("d/b.bin", &[1, 2, 3])];
+    common::write_files(&input, files);
+    let mut opts = common::options(&input);
+    // zstd frames carry no timestamp either; check them when zstd is built in.
+    opts.compress = cfg!(feature = "zstd");
+
+    let mut builds = Vec::new();
+    for (i, epoch) in ["0", "1700000000"].into_iter().enumerate() {
+        std::env::set_var("SOURCE_DATE_EPOCH", epoch);
+        let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000 * (i as u64 + 1));
+        for (path, _) in files {
+            File::options()
+                .write(true)
+                .open(input.join(path))
+                .unwrap()
+                .set_modified(mtime)
+                .unwrap();
+        }
+        let output = dir.join(format!("out{i}.pak"));
+        pak::build(&opts, &output).unwrap();
+        builds.push(std::fs::read(output).unwrap());
+    }
+    std::env::remove_var("SOURCE_DATE_EPOCH");
+
+    assert_eq!(builds[0], builds[1]);
+}
@@ -0,0 +1,226 @@
+//! `pak::salvage` on damaged paks: lost footers, cut-off indexes and carving.
+
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use nepak::pak::{self, BuildOptions, SalvageReport, SalvageSource, FOOTER_LEN};
+
+/// Sorted by path, like the index. Shared prefixes exercise front coding.
+const FILES: &[(&str, &[u8])] = &[
+    ("a.txt", b"alpha alpha alpha alpha"),
+    ("assets/textures/one.png", b"one one one one one"),
+    ("assets/textures/two.png", b"two two two two two"),
+    ("assets/textures/zz.png", b"last last last last"),
+];
+
+/// `(name, front_code_index, compress_index)` for every index encoding.
+const ENCODINGS: &[(&str, bool, bool)] = &[
+    ("plain", false, false),
+    ("front_coded", true, false),
+    ("zstd", false, true),
+    ("front_coded+zstd", true, true),
+];
+
+/// False for settings that need zstd when it isn't built in.
+fn available(zstd: bool) -> bool {
+    !zstd || cfg!(feature = "zstd")
+}
+
+fn build(
+    dir: &Path,
+    name: &str,
+    files: &[(&str, &[u8])],
+    tweak: impl Fn(&mut BuildOptions),
+) -> PathBuf {
+    let input = dir.join(format!("{name}-in"));
+    common::write_files(&input, files);
+    let mut opts = common::options(&input);
+    tweak(&mut opts);
+    let pak_path = dir.join(format!("{name}.pak"));
+    pak::build(&opts, &pak_path).unwrap();
+    pak_path
+}
+
+/// Drop the last `cut` bytes of `pak` and salvage it into `<pak>.out`.
+fn salvage_cut(pak_path: &Path, cut: usize) -> (SalvageReport, PathBuf) {
+    let bytes = std::fs::read(pak_path).unwrap();
+    let damaged = pak_path.with_extension("cut");
+    std::fs::write(&damaged, &bytes[..bytes.len() - cut]).unwrap();
+    let out = pak_path.with_extension("out");
+    let report = pak::salvage(&damaged, &out).unwrap();
+    (report, out)
+}
+
+fn recovered_paths(report: &SalvageReport) -> Vec<&str> {
+    report.recovered.iter().map(|e| e.path.as_str()).collect()
+}
+
+fn tree(files: &[(&str, &[u8])]) -> Vec<(String, Vec<u8>)> {
+    files
+        .iter()
+        .map(|(p, b)| (p.to_string(), b.to_vec()))
+        .collect()
+}
+
+#[test]
+fn lost_footer_keeps_names_for_every_index_encoding() {
+    let dir = common::scratch("salvage-encodings");
+    for &(name, front_code_index, compress_index) in ENCODINGS.iter().filter(|e| available(e.2)) {
+        for compress in [false, true].into_iter().filter(|&c| available(c)) {
+            let label = format!("{name}-{compress}");
+            let pak_path = build(&dir, &label, FILES, |o| {
+                o.front_code_index = front_code_index;
+                o.compress_index = compress_index;
+                o.compress = compress;
+            });
+            let (report, out) = salvage_cut(&pak_path, FOOTER_LEN as usize);
+
+            assert!(
+                matches!(report.source, SalvageSource::IndexScan { declared: 4, .. }),
+                "{label}: {:?}",
+                report.source
+            );
+            assert!(report.lost.is_empty(), "{label}: {:?}", report.lost);
+            assert!(report.recovered.iter().all(|e| e.verified), "{label}");
+            assert_eq!(common::read_tree(&out), tree(FILES), "{label}");
+        }
+    }
+}
+
+#[test]
+fn cut_off_index_recovers_leading_records() {
+    let dir = common::scratch("salvage-partial");
+    for &(name, front_code_index, _) in &ENCODINGS[..2] {
+        let pak_path = build(&dir, name, FILES, |o| o.front_code_index = front_code_index);
+        // Footer plus the tail of the last record (its hash).
+        let (report, out) = salvage_cut(&pak_path, FOOTER_LEN as usize + 10);
+
+        assert_eq!(
+            recovered_paths(&report),
+            [FILES[0].0, FILES[1].0, FILES[2].0],
+            "{name}"
+        );
+        assert_eq!(report.lost.len(), 1, "{name}");
+        assert!(
+            report.lost[0].reason.contains("1 record(s) cut off"),
+            "{name}"
+        );
+        assert_eq!(common::read_tree(&out), tree(&FILES[..3]), "{name}");
+    }
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn cut_off_zstd_index_recovers_complete_blocks() {
+    // Enough records for the index to span several zstd blocks (128 KiB each).
+    let paths: Vec<String> = (0..3000)
+        .map(|i| format!("assets/generated/level_{:04}/tile_{i:05}.bin", i / 100))
+        .collect();
+    let files: Vec<(&str, &[u8])> = paths.iter().map(|p| (p.as_str(), p.as_bytes())).collect();
+
+    let dir = common::scratch("salvage-zstd-partial");
+    let pak_path = build(&dir, "big", &files, |o| o.compress_index = true);
+    let (report, _) = salvage_cut(&pak_path, FOOTER_LEN as usize + 16);
+
+    assert!(matches!(
+        report.source,
+        SalvageSource::IndexScan { declared: 3000, .. }
+    ));
+    let recovered = recovered_paths(&report);
+    assert!(
+        !recovered.is_empty() && recovered.len() < 3000,
+        "{}",
+        recovered.len()
+    );
+    assert_eq!(recovered, paths[..recovered.len()]);
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn carving_skips_the_index_frame() {
+    use nepak::pak::MAGIC;
+
+    // A compressed payload, then a zstd index frame whose only record points
+    // past the index: no usable index, so salvage falls back to carving.
+    let mut index = MAGIC.to_vec();
+    index.extend_from_slice(&1u32.to_le_bytes());
+    index.extend_from_slice(&1u16.to_le_bytes());
+    index.push(b'a');
+    index.extend_from_slice(&u64::MAX.to_le_bytes());
+    index.extend_from_slice(&[0; 8 + 8 + 1 + 32]);
+
+    let mut data = MAGIC.to_vec();
+    data.extend(zstd::encode_all(&b"payload bytes"[..], 3).unwrap());
+    data.extend(zstd::encode_all(&index[..], 3).unwrap());
+
+    let dir = common::scratch("salvage-carve");
+    let damaged = dir.join("carve.pak");
+    std::fs::write(&damaged, data).unwrap();
+    let report = pak::salvage(&damaged, &dir.join("out")).unwrap();
+
+    assert!(matches!(report.source, SalvageSource::Carved));
+    assert_eq!(recovered_paths(&report), ["carved/000000_at_8.bin"]);
+    assert_eq!(
+        std::fs::read(dir.join("out/carved/000000_at_8.bin")).unwrap(),
+        b"payload bytes"
+    );
+}
+
+#[test]
+fn rebuild_holds_recovered_entries_with_their_codec() {
+    let dir = common::scratch("salvage-rebuild");
+    for compress in [false, true].into_iter().filter(|&c| available(c)) {
+        let label = format!("rebuild-{compress}");
+        let pak_path = build(&dir, &label, FILES, |o| o.compress = compress);
+        let original = pak::entries(&pak_path).unwrap();
+
+        // Lose the footer and break one payload.
+        let mut bytes = std::fs::read(&pak_path).unwrap();
+        bytes.truncate(bytes.len() - FOOTER_LEN as usize);
+        bytes[original[1].payload_offset as usize + 2] ^= 0xff;
+        let damaged = dir.join(format!("{label}.cut"));
+        std::fs::write(&damaged, bytes).unwrap();
+
+        // Files already in the output directory stay out of the rebuilt pak.
+        let out = dir.join(format!("{label}.out"));
+        common::write_files(&out, &[("stray.txt", b"not from the pak")]);
+        let rebuilt = dir.join(format!("{label}.rebuilt.pak"));
+        let report = pak::salvage_rebuild(&damaged, &out, &rebuilt).unwrap();
+        assert!(report.rebuilt, "{label}");
+        assert_eq!(report.lost.len(), 1, "{label}");
+
+        let entries = pak::entries(&rebuilt).unwrap();
+        let paths: Vec<&str> = entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, [FILES[0].0, FILES[2].0, FILES[3].0], "{label}");
+        for e in &entries {
+            let before = original.iter().find(|o| o.path == e.path).unwrap();
+            assert_eq!(e.payload_kind, before.payload_kind, "{label}");
+            assert_eq!(e.raw_hash_hex, before.raw_hash_hex, "{label}");
+            assert_eq!(
+                pak::entry_payload(&rebuilt, &e.path).unwrap(),
+                pak::entry_payload(&pak_path, &e.path).unwrap(),
+                "{label}"
+            );
+        }
+        assert!(pak::verify(&rebuilt).unwrap().is_ok(), "{label}");
+    }
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn rebuild_is_skipped_without_verified_entries() {
+    let mut data = nepak::pak::MAGIC.to_vec();
+    data.extend(zstd::encode_all(&b"payload bytes"[..], 3).unwrap());
+
+    let dir = common::scratch("salvage-rebuild-carved");
+    let damaged = dir.join("carve.pak");
+    std::fs::write(&damaged, data).unwrap();
+    let rebuilt = dir.join("rebuilt.pak");
+    let report = pak::salvage_rebuild(&damaged, &dir.join("out"), &rebuilt).unwrap();
+
+    // Carved frames are recovered but never rebuilt, so there is nothing to write.
+    assert_eq!(report.recovered.len(), 1);
+    assert!(!report.rebuilt);
+    assert!(!rebuilt.exists());
+}
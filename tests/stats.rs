@@ -0,0 +1,104 @@
+//! `pak::stats`: grouping by extension and directory, top lists, duplicates.
+
+mod common;
+
+use std::path::PathBuf;
+
+use nepak::pak::{self, GroupStats, StatsOptions};
+
+fn build(name: &str) -> PathBuf {
+    let dir = common::scratch(name);
+    let input = dir.join("in");
+    let files: Vec<(&str, Vec<u8>)> = vec![
+        ("README", vec![b'r'; 10]),
+        ("snd/.hidden", vec![b'h'; 5]),
+        ("snd/x.ogg", vec![b'x'; 70]),
+        ("snd/y.ogg", vec![b'x'; 70]),
+        ("tex/a.PNG", vec![b'a'; 100]),
+        ("tex/b.png", vec![b'b'; 50]),
+        ("tex/ui/c.png", vec![b'c'; 30]),
+    ];
+    let files: Vec<(&str, &[u8])> = files.iter().map(|(p, b)| (*p, b.as_slice())).collect();
+    common::write_files(&input, &files);
+    let pak_path = dir.join("stats.pak");
+    pak::build(&common::options(&input), &pak_path).unwrap();
+    pak_path
+}
+
+/// `(key, entries, raw)`; payloads equal raw sizes (nothing is compressed).
+fn groups(groups: &[GroupStats]) -> Vec<(&str, u64, u64)> {
+    groups
+        .iter()
+        .inspect(|g| assert_eq!((g.payload, g.ratio), (g.raw, 1.0), "{}", g.key))
+        .map(|g| (g.key.as_str(), g.entries, g.raw))
+        .collect()
+}
+
+#[test]
+fn groups_by_extension_and_directory() {
+    let pak_path = build("stats-groups");
+    let stats = pak::stats(&pak_path, &StatsOptions::default()).unwrap();
+
+    assert_eq!((stats.total.entries, stats.total.raw), (7, 335));
+    // Extensions are lowercased; dotfiles and extensionless names share "".
+    assert_eq!(
+        groups(&stats.by_extension),
+        [("png", 3, 180), ("ogg", 2, 140), ("", 2, 15)]
+    );
+    assert_eq!(
+        groups(&stats.by_directory),
+        [("tex", 3, 180), ("snd", 3, 145), ("", 1, 10)]
+    );
+    assert!(stats.tree.is_empty());
+
+    let opts = StatsOptions {
+        depth: 2,
+        ..Default::default()
+    };
+    let stats = pak::stats(&pak_path, &opts).unwrap();
+    assert_eq!(
+        groups(&stats.by_directory),
+        [
+            ("tex", 2, 150),
+            ("snd", 3, 145),
+            ("tex/ui", 1, 30),
+            ("", 1, 10)
+        ]
+    );
+}
+
+#[test]
+fn tree_aggregates_every_prefix() {
+    let opts = StatsOptions {
+        tree: true,
+        ..Default::default()
+    };
+    let stats = pak::stats(&build("stats-tree"), &opts).unwrap();
+    assert_eq!(
+        groups(&stats.tree),
+        [
+            ("", 7, 335),
+            ("snd", 3, 145),
+            ("tex", 3, 180),
+            ("tex/ui", 1, 30)
+        ]
+    );
+}
+
+#[test]
+fn top_lists_and_duplicates() {
+    let opts = StatsOptions {
+        top: 3,
+        ..Default::default()
+    };
+    let stats = pak::stats(&build("stats-top"), &opts).unwrap();
+
+    let largest: Vec<_> = stats.largest.iter().map(|e| e.path.as_str()).collect();
+    assert_eq!(largest, ["tex/a.PNG", "snd/x.ogg", "snd/y.ogg"]);
+    assert_eq!(stats.worst_compressed.len(), 3);
+
+    assert_eq!(stats.duplicates.len(), 1);
+    let dup = &stats.duplicates[0];
+    assert_eq!(dup.paths, ["snd/x.ogg", "snd/y.ogg"]);
+    assert_eq!((dup.raw, dup.wasted), (70, 70));
+}
@@ -0,0 +1,196 @@
+//! Asset transforms: built-ins, command transforms, renames and the cache.
+
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use nepak::pak::{
+    self, BuildOptions, CommandTransform, CrlfToLf, JsonMinify, PakError, StripBom, Transform,
+    TransformRule,
+};
+
+fn apply(t: &dyn Transform, bytes: &[u8]) -> Vec<u8> {
+    t.apply("x", bytes.to_vec()).unwrap()
+}
+
+/// Options building `files` (written under `dir/in`) with `rules`.
+fn options(dir: &Path, files: &[(&str, &[u8])], rules: Vec<TransformRule>) -> BuildOptions {
+    let input = dir.join("in");
+    common::write_files(&input, files);
+    BuildOptions {
+        transforms: rules,
+        ..common::options(&input)
+    }
+}
+
+fn build(dir: &Path, opts: &BuildOptions) -> pak::PakResult<PathBuf> {
+    let pak_path = dir.join("out.pak");
+    pak::build(opts, &pak_path).map(|_| pak_path)
+}
+
+#[test]
+fn json_minify_keeps_strings_intact() {
+    let json = br#"{ "a" : [1, 2.50 ],
+        "s": "keep  \" this \\", "t":"x y" }"#;
+    assert_eq!(
+        apply(&JsonMinify, json),
+        br#"{"a":[1,2.50],"s":"keep  \" this \\","t":"x y"}"#
+    );
+}
+
+#[test]
+fn invalid_json_fails_the_build() {
+    let dir = common::scratch("transform-bad-json");
+    let rules = vec![TransformRule::new("**/*.json", JsonMinify)];
+    let opts = options(&dir, &[("cfg/bad.json", b"{\"a\": }")], rules);
+    match build(&dir, &opts) {
+        Err(PakError::Transform {
+            path, transform, ..
+        }) => assert_eq!(
+            (path.as_str(), transform.as_str()),
+            ("cfg/bad.json", "json-minify")
+        ),
+        other => panic!("build not refused: {other:?}"),
+    }
+}
+
+#[test]
+fn crlf_to_lf_keeps_lone_carriage_returns() {
+    assert_eq!(apply(&CrlfToLf, b"a\r\nb\rc\r\n\r"), b"a\nb\rc\n\r");
+}
+
+#[test]
+fn strip_bom_removes_only_a_leading_bom() {
+    assert_eq!(apply(&StripBom, b"\xEF\xBB\xBFhi"), b"hi");
+    assert_eq!(apply(&StripBom, b"hi\xEF\xBB\xBF"), b"hi\xEF\xBB\xBF");
+    assert_eq!(apply(&StripBom, b"\xEF\xBB"), b"\xEF\xBB");
+}
+
+#[test]
+fn builtins_run_in_rule_order() {
+    let dir = common::scratch("transform-builtins");
+    let rules = vec![
+        TransformRule::new("**/*.json", StripBom),
+        TransformRule::new("**/*.json", JsonMinify),
+        TransformRule::new("*.txt", CrlfToLf),
+    ];
+    let files: &[(&str, &[u8])] = &[
+        ("d/a.json", b"\xEF\xBB\xBF{ \"k\": 1 }"),
+        ("b.txt", b"x\r\ny"),
+        ("d/c.txt", b"x\r\ny"),
+    ];
+    let pak_path = build(&dir, &options(&dir, files, rules)).unwrap();
+    assert_eq!(
+        pak::entry_bytes(&pak_path, "d/a.json").unwrap(),
+        b"{\"k\":1}"
+    );
+    assert_eq!(pak::entry_bytes(&pak_path, "b.txt").unwrap(), b"x\ny");
+    // `*` does not cross `/`.
+    assert_eq!(pak::entry_bytes(&pak_path, "d/c.txt").unwrap(), b"x\r\ny");
+}
+
+#[test]
+fn command_cache_key_follows_script_edits() {
+    let dir = common::scratch("transform-key");
+    let script = dir.join("minify.sh");
+    std::fs::write(&script, "#!/bin/sh\ncat\n").unwrap();
+    let transform = CommandTransform::new(&script, vec![]);
+
+    let before = transform.cache_key();
+    assert_eq!(transform.cache_key(), before);
+
+    // The same transform lives across watch rebuilds; an edit must show.
+    std::fs::write(&script, "#!/bin/sh\ntr -d ' '\n").unwrap();
+    assert_ne!(transform.cache_key(), before);
+}
+
+#[cfg(unix)]
+mod command {
+    use super::*;
+
+    fn sh(script: &str) -> CommandTransform {
+        CommandTransform::new("sh", vec!["-c".into(), script.into()])
+    }
+
+    #[test]
+    fn rename_stores_output_under_the_new_path() {
+        let dir = common::scratch("transform-rename");
+        let upper = sh("tr a-z A-Z").with_rename(".src", ".out");
+        let rules = vec![TransformRule::new("**/*.src", upper)];
+        let files: &[(&str, &[u8])] = &[("s/a.src", b"shader"), ("s/b.txt", b"text")];
+        let pak_path = build(&dir, &options(&dir, files, rules)).unwrap();
+
+        let paths: Vec<_> = pak::entries(&pak_path)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.path)
+            .collect();
+        assert_eq!(paths, ["s/a.out", "s/b.txt"]);
+        assert_eq!(pak::entry_bytes(&pak_path, "s/a.out").unwrap(), b"SHADER");
+    }
+
+    #[test]
+    fn rename_onto_an_existing_path_collides() {
+        let dir = common::scratch("transform-rename-collision");
+        let rules = vec![TransformRule::new(
+            "*.src",
+            sh("cat").with_rename(".src", ".out"),
+        )];
+        let files: &[(&str, &[u8])] = &[("a.out", b"1"), ("a.src", b"2")];
+        match build(&dir, &options(&dir, files, rules)) {
+            Err(PakError::Collision {
+                path,
+                first,
+                second,
+            }) => {
+                assert_eq!(path, "a.out");
+                let names = [first.file_name().unwrap(), second.file_name().unwrap()];
+                assert_eq!(names, ["a.out", "a.src"]);
+            }
+            other => panic!("build not refused: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn failing_command_reports_its_stderr() {
+        let dir = common::scratch("transform-fails");
+        let rules = vec![TransformRule::new(
+            "*",
+            sh("echo \"no good: $NEPAK_PATH\" >&2; exit 3"),
+        )];
+        let opts = options(&dir, &[("a.txt", b"x")], rules);
+        match build(&dir, &opts) {
+            Err(PakError::Transform { path, message, .. }) => {
+                assert_eq!(path, "a.txt");
+                assert!(message.contains("no good: a.txt"), "{message}");
+                assert!(message.contains('3'), "{message}");
+            }
+            other => panic!("build not refused: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn cache_hit_skips_the_command() {
+        let dir = common::scratch("transform-cache");
+        let log = dir.join("runs.log");
+        let script = format!("echo run >> '{}'; tr a-z A-Z", log.display());
+        let rules = vec![TransformRule::new("*.txt", sh(&script))];
+        let mut opts = options(&dir, &[("a.txt", b"abc"), ("b.txt", b"def")], rules);
+        opts.transform_cache = Some(dir.join("cache"));
+        let runs = || std::fs::read_to_string(&log).unwrap().lines().count();
+
+        let pak_path = build(&dir, &opts).unwrap();
+        assert_eq!(runs(), 2);
+        let first = std::fs::read(&pak_path).unwrap();
+
+        build(&dir, &opts).unwrap();
+        assert_eq!(runs(), 2, "cached outputs are reused");
+        assert_eq!(std::fs::read(&pak_path).unwrap(), first);
+
+        // Only the changed file runs again.
+        common::write_files(&dir.join("in"), &[("b.txt", b"xyz")]);
+        build(&dir, &opts).unwrap();
+        assert_eq!(runs(), 3);
+        assert_eq!(pak::entry_bytes(&pak_path, "b.txt").unwrap(), b"XYZ");
+    }
+}
@@ -0,0 +1,146 @@
+//! `pak::verify`: every problem ends up in the report, one issue per finding.
+
+mod common;
+
+use std::path::Path;
+
+use nepak::pak::{self, VerifyIssueKind, VerifyReport};
+
+fn kinds(report: &VerifyReport) -> Vec<(VerifyIssueKind, Option<&str>, u64, u64)> {
+    report
+        .issues
+        .iter()
+        .map(|i| (i.kind, i.path.as_deref(), i.offset, i.len))
+        .collect()
+}
+
+fn build(dir: &Path, compress: bool) -> std::path::PathBuf {
+    let input = dir.join("in");
+    common::write_files(
+        &input,
+        &[
+            ("a.txt", b"alpha alpha"),
+            ("b.txt", b"bravo bravo"),
+            ("c.txt", b"charlie"),
+        ],
+    );
+    let mut opts = common::options(&input);
+    opts.compress = compress;
+    let pak_path = dir.join(if compress { "zstd.pak" } else { "raw.pak" });
+    pak::build(&opts, &pak_path).unwrap();
+    pak_path
+}
+
+#[test]
+fn intact_pak_has_no_issues() {
+    let dir = common::scratch("verify-ok");
+    for compress in [false, cfg!(feature = "zstd")] {
+        let report = pak::verify(&build(&dir, compress)).unwrap();
+        assert!(report.is_ok(), "{:?}", report.issues);
+        assert_eq!(report.entries, 3);
+    }
+}
+
+#[test]
+fn corrupt_payload_is_reported_and_verify_goes_on() {
+    let dir = common::scratch("verify-corrupt");
+    let pak_path = build(&dir, false);
+    let entries = pak::entries(&pak_path).unwrap();
+    let (a, c) = (&entries[0], &entries[2]);
+
+    let mut bytes = std::fs::read(&pak_path).unwrap();
+    bytes[a.payload_offset as usize] ^= 0xff;
+    bytes[c.payload_offset as usize + 1] ^= 0xff;
+    std::fs::write(&pak_path, bytes).unwrap();
+
+    let report = pak::verify(&pak_path).unwrap();
+    assert_eq!(
+        kinds(&report),
+        [
+            (
+                VerifyIssueKind::HashMismatch,
+                Some("a.txt"),
+                a.payload_offset,
+                a.payload_len
+            ),
+            (
+                VerifyIssueKind::HashMismatch,
+                Some("c.txt"),
+                c.payload_offset,
+                c.payload_len
+            ),
+        ]
+    );
+    assert_eq!(
+        report.issues[0].to_string(),
+        format!(
+            "hash_mismatch at {}+{}: a.txt",
+            a.payload_offset, a.payload_len
+        )
+    );
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn undecodable_payload_is_a_decode_failure() {
+    let dir = common::scratch("verify-decode");
+    let pak_path = build(&dir, true);
+    let b = pak::entries(&pak_path).unwrap().remove(1);
+
+    // Break the zstd frame magic.
+    let mut bytes = std::fs::read(&pak_path).unwrap();
+    bytes[b.payload_offset as usize] ^= 0xff;
+    std::fs::write(&pak_path, bytes).unwrap();
+
+    let report = pak::verify(&pak_path).unwrap();
+    assert_eq!(
+        kinds(&report),
+        [(
+            VerifyIssueKind::DecodeFailure,
+            Some("b.txt"),
+            b.payload_offset,
+            b.payload_len
+        )]
+    );
+}
+
+#[test]
+fn layout_and_index_problems_are_reported() {
+    // Payload area (offsets from 8): aaaa bbbb gap! cc eeee tail
+    let area = b"aaaabbbbgap!cceeeetail";
+    let records: [(&str, u64, u64, &[u8]); 4] = [
+        ("a", 8, 4, b"aaaa"),
+        ("b", 10, 4, b"aabb"),
+        ("c", 20, 2, b"ccc"),
+        ("z/../e", 22, 4, b"eeee"),
+    ];
+    let dir = common::scratch("verify-layout");
+    let pak_path = dir.join("layout.pak");
+    std::fs::write(&pak_path, common::craft(area, &records)).unwrap();
+
+    let report = pak::verify(&pak_path).unwrap();
+    assert_eq!(report.entries, 4);
+    assert_eq!(
+        kinds(&report),
+        [
+            // Entry pass, in index order.
+            // One issue per defect: a size mismatch is not also a hash mismatch.
+            (VerifyIssueKind::SizeMismatch, Some("c"), 20, 2),
+            (VerifyIssueKind::InvalidPath, Some("z/../e"), 22, 4),
+            // Layout pass, in file order.
+            (VerifyIssueKind::Overlap, Some("b"), 10, 4),
+            (VerifyIssueKind::Gap, None, 14, 6),
+            (VerifyIssueKind::Gap, None, 26, 4),
+        ]
+    );
+    assert_eq!(report.issues[0].detail, "expected 3 bytes, decoded 2");
+    assert_eq!(
+        report.issues[2].to_string(),
+        "overlap at 10+4: b (overlaps a)"
+    );
+    assert_eq!(report.issues[4].detail, "before index");
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["issues"][2]["kind"], "overlap");
+    assert_eq!(json["issues"][3]["path"], serde_json::Value::Null);
+}